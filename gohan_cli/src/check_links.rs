@@ -0,0 +1,188 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use clap::Args;
+use md_parser::{
+    links::{self, BrokenLink},
+    lint,
+};
+
+use crate::{config, render};
+
+/// How long to wait for a server before reporting its URL as broken
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Args)]
+pub struct CheckLinksArgs {
+    /// Markdown files to check, or directories whose markdown files to check
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Directory absolute paths like `/docs/index.md` are resolved from
+    #[arg(long, default_value = ".")]
+    root: PathBuf,
+    /// Request external URLs too, reporting the ones that don't respond successfully
+    #[arg(short, long)]
+    external: bool,
+    /// Number of external URLs requested at the same time
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+    /// Prefix of external URLs never requested, on top of the ones of `gohan.toml`
+    #[arg(long)]
+    allow: Vec<String>,
+    /// Configuration file, `gohan.toml` by default
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+/// Prints the broken links of every file, failing when there are any
+pub fn run(args: &CheckLinksArgs) -> Result<(), Box<dyn Error>> {
+    let config = config::load(args.config.as_deref())?;
+    let mut allow = config.check_links.allow;
+    allow.extend(args.allow.iter().cloned());
+
+    let mut documents = Vec::new();
+    for path in args.paths.iter() {
+        let files = if path.is_dir() {
+            render::markdown_files(path)?
+        } else {
+            vec![path.clone()]
+        };
+        for file in files {
+            let markdown = fs::read_to_string(&file)
+                .map_err(|error| format!("could not read {}: {}", file.display(), error))?;
+            let destinations = links::destinations(&markdown);
+            documents.push((file, markdown, destinations));
+        }
+    }
+
+    let mut external = HashMap::new();
+    if args.external {
+        let urls: BTreeSet<String> = documents
+            .iter()
+            .flat_map(|(_, _, destinations)| destinations.iter())
+            .filter(|destination| destination.is_external())
+            .map(|destination| destination.url.clone())
+            .filter(|url| !allow.iter().any(|prefix| url.starts_with(prefix.as_str())))
+            .collect();
+        external = request_all(urls.into_iter().collect(), args.concurrency.max(1));
+    }
+
+    let mut broken = 0;
+    for (file, markdown, destinations) in documents {
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let mut found = links::check_local(&destinations, dir, &args.root);
+        found.extend(destinations.into_iter().filter_map(|destination| {
+            let reason = external.get(&destination.url)?.clone()?;
+            Some(BrokenLink {
+                destination,
+                reason,
+            })
+        }));
+        found.sort_by_key(|link| link.destination.span.start.start);
+
+        for link in found.iter() {
+            let (line, col) = lint::line_col(&markdown, link.destination.span.start.start);
+            println!(
+                "{}:{}:{}: broken link {}: {}",
+                file.display(),
+                line,
+                col,
+                link.destination.url,
+                link.reason
+            );
+        }
+        broken += found.len();
+    }
+    match broken {
+        0 => Ok(()),
+        1 => Err("1 broken link found".into()),
+        broken => Err(format!("{} broken links found", broken).into()),
+    }
+}
+
+/// Requests the URLs from a few threads at a time, giving the reason
+/// each broken one failed, or `None` for the ones that work
+fn request_all(urls: Vec<String>, concurrency: usize) -> HashMap<String, Option<String>> {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    let pending = Mutex::new(urls.into_iter());
+    let results = Mutex::new(HashMap::new());
+    thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| loop {
+                let Some(url) = pending.lock().unwrap().next() else {
+                    break;
+                };
+                let result = request(&agent, &url).err();
+                results.lock().unwrap().insert(url, result);
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+fn request(agent: &ureq::Agent, url: &str) -> Result<(), String> {
+    match agent.head(url).call() {
+        Ok(_) => Ok(()),
+        // Some servers only answer GET requests
+        Err(ureq::Error::Status(405, _)) => agent
+            .get(url)
+            .call()
+            .map(|_| ())
+            .map_err(|error| describe(&error)),
+        Err(error) => Err(describe(&error)),
+    }
+}
+
+fn describe(error: &ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(status, _) => format!("responded with status {}", status),
+        ureq::Error::Transport(error) => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn report_missing_files() {
+        let root = env::temp_dir().join(format!("gohan-links-{}", std::process::id()));
+        fs::create_dir_all(root.join("guide")).unwrap();
+        fs::write(
+            root.join("index.md"),
+            "[Guide](guide/intro.md) [Home](/index.md)",
+        )
+        .unwrap();
+        fs::write(root.join("guide/intro.md"), "[Back](../index.md#top)").unwrap();
+
+        let mut args = CheckLinksArgs {
+            paths: vec![root.clone()],
+            root: root.clone(),
+            external: false,
+            concurrency: 8,
+            allow: Vec::new(),
+            config: Some(root.join("missing.toml")),
+        };
+        assert!(run(&args).is_err());
+        args.config = None;
+        assert!(run(&args).is_ok());
+
+        fs::write(
+            root.join("guide/intro.md"),
+            "[Next](next.md) [API](https://docs.rs)",
+        )
+        .unwrap();
+        let error = run(&args).unwrap_err();
+        assert_eq!(error.to_string(), "1 broken link found");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use md_parser::lint::LintConfig;
+use serde::Deserialize;
+
+/// Configuration file looked up in the current directory
+pub const CONFIG_FILE: &str = "gohan.toml";
+
+/// Settings read from a `gohan.toml`, each command reading its own table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub lint: LintConfig,
+    pub check_links: CheckLinksConfig,
+}
+
+/// The `[check-links]` table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckLinksConfig {
+    /// Prefixes of the external URLs never requested, e.g. of sites blocking bots
+    pub allow: Vec<String>,
+}
+
+/// Reads the given configuration file, or the `gohan.toml` of the current
+/// directory if there is one. Without any, the defaults apply.
+pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None if Path::new(CONFIG_FILE).is_file() => PathBuf::from(CONFIG_FILE),
+        None => return Ok(Config::default()),
+    };
+    let content = fs::read_to_string(&path)
+        .map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+    let config = toml::from_str(&content)
+        .map_err(|error| format!("invalid configuration in {}: {}", path.display(), error))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use md_parser::lint::Level;
+
+    use super::*;
+
+    #[test]
+    fn parse_lint_levels() {
+        let config: Config = toml::from_str("[lint]\nno-bare-urls = \"error\"\n").unwrap();
+        assert_eq!(config.lint.no_bare_urls, Level::Error);
+        assert_eq!(config.lint.heading_increment, Level::Warning);
+        assert!(toml::from_str::<Config>("[lint]\nno-such-rule = \"off\"\n").is_err());
+
+        let config: Config =
+            toml::from_str("[check-links]\nallow = [\"https://localhost\"]\n").unwrap();
+        assert_eq!(config.check_links.allow, ["https://localhost"]);
+    }
+}
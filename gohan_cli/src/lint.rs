@@ -0,0 +1,79 @@
+use std::{error::Error, fs, path::PathBuf};
+
+use clap::Args;
+use md_parser::{
+    diagnostic::Severity,
+    lint::{self, Lint},
+};
+
+use crate::{config, render};
+
+#[derive(Debug, Args)]
+pub struct LintArgs {
+    /// Markdown files to check, or directories whose markdown files to check
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Configuration file setting the level of each rule, `gohan.toml` by default
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+/// Prints the lints of every file, failing when any of them is an error
+pub fn run(args: &LintArgs) -> Result<(), Box<dyn Error>> {
+    let config = config::load(args.config.as_deref())?;
+    let mut files = Vec::new();
+    for path in args.paths.iter() {
+        if path.is_dir() {
+            files.extend(render::markdown_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut errors = 0;
+    for file in files.iter() {
+        let markdown = fs::read_to_string(file)
+            .map_err(|error| format!("could not read {}: {}", file.display(), error))?;
+        for lint in lint::lint(&markdown, &config.lint) {
+            if lint.severity == Severity::Error {
+                errors += 1;
+            }
+            println!("{}", report(&file.display().to_string(), &markdown, &lint));
+        }
+    }
+    match errors {
+        0 => Ok(()),
+        1 => Err("1 lint error found".into()),
+        errors => Err(format!("{} lint errors found", errors).into()),
+    }
+}
+
+/// Formats the lint the way compilers do, so editors can jump to it
+fn report(file: &str, markdown: &str, lint: &Lint) -> String {
+    let (line, col) = lint::line_col(markdown, lint.span.start.start);
+    let severity = match lint.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    format!(
+        "{}:{}:{}: {}[{}]: {}",
+        file, line, col, severity, lint.rule, lint.message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use md_parser::lint::LintConfig;
+
+    use super::*;
+
+    #[test]
+    fn report_lints() {
+        let markdown = "# Gohan\n\nSee https://gohan.dev\n";
+        let lints = lint::lint(markdown, &LintConfig::default());
+        assert_eq!(
+            report("README.md", markdown, &lints[0]),
+            "README.md:3:5: warning[no-bare-urls]: bare URL https://gohan.dev, write it as a link"
+        );
+    }
+}
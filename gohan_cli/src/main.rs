@@ -0,0 +1,48 @@
+//! The `gohan` command, rendering markdown files from the shell
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+mod check_links;
+mod config;
+mod lint;
+mod render;
+mod serve;
+mod watch;
+
+#[derive(Debug, Parser)]
+#[command(name = "gohan", version, about = "Markdown parser and HTML renderer")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Renders a markdown file as HTML, or any other output format
+    Render(render::RenderArgs),
+    /// Serves rendered markdown pages, reloading them whenever files change
+    Serve(serve::ServeArgs),
+    /// Checks markdown files against the lint rules configured in `gohan.toml`
+    Lint(lint::LintArgs),
+    /// Reports the links of markdown files pointing to missing files or pages
+    CheckLinks(check_links::CheckLinksArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Render(args) => render::run(&args),
+        Command::Serve(args) => serve::run(&args),
+        Command::Lint(args) => lint::run(&args),
+        Command::CheckLinks(args) => check_links::run(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("gohan: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
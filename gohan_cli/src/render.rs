@@ -0,0 +1,339 @@
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::{Args, ValueEnum};
+use md_parser::{
+    ast, frontmatter,
+    man::{self, ManOptions},
+    markdown,
+    parser::{self, Extensions, ParserOptions},
+    renderer::{self, HeadingAnchors, HtmlOptions, RenderError},
+    text::{self, TextStyle},
+};
+
+use crate::watch;
+
+#[derive(Debug, Args)]
+pub struct RenderArgs {
+    /// Markdown file to render, directory whose markdown files to render,
+    /// or `-` to read the standard input
+    pub input: PathBuf,
+    /// File to write the output into, or `-` for the standard output,
+    /// or directory to write the output of each file into
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Html)]
+    pub format: Format,
+    /// Keep watching the input, rendering files again whenever they change
+    #[arg(short, long)]
+    pub watch: bool,
+    #[command(flatten)]
+    pub options: OutputOptions,
+}
+
+/// What the markdown is rendered into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Html,
+    /// Plain text
+    Text,
+    /// Text styled for terminals
+    Ansi,
+    /// Normalized markdown
+    Markdown,
+    /// Man page written in roff
+    Man,
+    /// AST following the stable JSON schema
+    JsonAst,
+}
+
+impl Format {
+    /// Extension of the files written in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Text | Self::Ansi => "txt",
+            Self::Markdown => "md",
+            Self::Man => "7",
+            Self::JsonAst => "json",
+        }
+    }
+}
+
+/// Flags mapped onto the parser and renderer options
+#[derive(Debug, Default, Clone, Args)]
+pub struct OutputOptions {
+    /// Parse `<!-- -->` comments as text
+    #[arg(long)]
+    no_html_comments: bool,
+    /// Pass HTML comments through to the output
+    #[arg(long)]
+    keep_comments: bool,
+    /// Write raw HTML as is. Only meant for trusted input.
+    #[arg(long)]
+    unsafe_html: bool,
+    /// Close void elements XHTML style, e.g. `<br />`
+    #[arg(long)]
+    self_closing_tags: bool,
+    /// Wrap the output into a complete HTML document
+    #[arg(long)]
+    full_document: bool,
+    /// Defer loading images until they're needed
+    #[arg(long)]
+    lazy_images: bool,
+    /// Give headings an `id`, and optionally a permalink pointing to it
+    #[arg(long, value_enum, default_value_t = Anchors::None)]
+    heading_anchors: Anchors,
+    /// Levels added to every heading, clamped at `<h6>`
+    #[arg(long, default_value_t = 0)]
+    heading_offset: u8,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Anchors {
+    #[default]
+    None,
+    Id,
+    Append,
+    Wrap,
+}
+
+impl OutputOptions {
+    pub fn html_options(&self) -> HtmlOptions {
+        let extensions = if self.no_html_comments {
+            Extensions::all() - Extensions::HTML_COMMENTS
+        } else {
+            Extensions::all()
+        };
+        HtmlOptions {
+            keep_comments: self.keep_comments,
+            unsafe_html: self.unsafe_html,
+            self_closing_tags: self.self_closing_tags,
+            full_document: self.full_document,
+            trailing_newline: true,
+            lazy_images: self.lazy_images,
+            heading_anchors: match self.heading_anchors {
+                Anchors::None => HeadingAnchors::None,
+                Anchors::Id => HeadingAnchors::Id,
+                Anchors::Append => HeadingAnchors::Append,
+                Anchors::Wrap => HeadingAnchors::Wrap,
+            },
+            heading_offset: self.heading_offset,
+            parser: ParserOptions {
+                extensions,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+pub fn run(args: &RenderArgs) -> Result<(), Box<dyn Error>> {
+    if args.input.is_dir() {
+        if args.output.is_none() {
+            return Err("rendering a directory needs an output directory".into());
+        }
+        for path in markdown_files(&args.input)? {
+            render_file(args, &path)?;
+        }
+    } else {
+        render_file(args, &args.input)?;
+    }
+
+    if args.watch {
+        if is_stdin(&args.input) {
+            return Err("the standard input can't be watched".into());
+        }
+        watch::watch(args)?;
+    }
+    Ok(())
+}
+
+/// Renders one of the input files, printing how long it took when watching them
+pub fn render_file(args: &RenderArgs, path: &Path) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let markdown = if is_stdin(path) {
+        io::read_to_string(io::stdin())
+            .map_err(|error| format!("could not read the standard input: {}", error))?
+    } else {
+        fs::read_to_string(path)
+            .map_err(|error| format!("could not read {}: {}", path.display(), error))?
+    };
+    let title = path
+        .file_stem()
+        .filter(|_| !is_stdin(path))
+        .map(|stem| stem.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    let output = render(&markdown, args.format, &args.options.html_options(), &title)?;
+
+    let Some(target) = output_path(args, path) else {
+        print!("{}", output);
+        return Ok(());
+    };
+    if let Some(parent) = target
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("could not create {}: {}", parent.display(), error))?;
+    }
+    fs::write(&target, output)
+        .map_err(|error| format!("could not write {}: {}", target.display(), error))?;
+    if args.watch {
+        eprintln!(
+            "rendered {} into {} in {:.1?}",
+            path.display(),
+            target.display(),
+            started.elapsed()
+        );
+    }
+    Ok(())
+}
+
+/// Where the output of the given input file goes, or `None` for the standard output.
+/// Files of an input directory keep their path within the output directory.
+fn output_path(args: &RenderArgs, path: &Path) -> Option<PathBuf> {
+    let output = args.output.as_ref().filter(|output| !is_stdin(output))?;
+    match path.strip_prefix(&args.input) {
+        Ok(relative) if args.input.is_dir() => Some(
+            output
+                .join(relative)
+                .with_extension(args.format.extension()),
+        ),
+        _ => Some(output.clone()),
+    }
+}
+
+/// Markdown files within the directory and its subdirectories, in a stable order
+pub fn markdown_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|error| format!("could not read {}: {}", dir.display(), error))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_markdown(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether the path stands for the standard input or output
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "md" || extension == "markdown")
+}
+
+/// Renders the markdown, without its front matter, into the given format.
+/// Man pages are titled after the given title.
+pub fn render(
+    markdown: &str,
+    format: Format,
+    options: &HtmlOptions,
+    title: &str,
+) -> Result<String, RenderError> {
+    let (_, body) = frontmatter::split(markdown);
+    let ast = parser::parse_with_options(body, options.parser.clone())?;
+    Ok(match format {
+        Format::Html => renderer::render_ast(&ast, options),
+        Format::Text => text::render_text(&ast, TextStyle::Plain),
+        Format::Ansi => text::render_text(&ast, TextStyle::Ansi),
+        Format::Markdown => markdown::render_markdown(&ast),
+        Format::Man => {
+            let options = ManOptions {
+                section: "7".to_string(),
+                title: title.to_string(),
+            };
+            man::render_man(body, &options)?
+        }
+        Format::JsonAst => ast::to_json(&ast) + "\n",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn render_into_every_format() {
+        let markdown = "---\ntitle: Gohan\n---\n# Gohan\n\n__bold__ <!-- note -->\n";
+        let options = OutputOptions {
+            heading_offset: 1,
+            ..Default::default()
+        };
+        let html = |options: &OutputOptions| {
+            render(markdown, Format::Html, &options.html_options(), "").unwrap()
+        };
+        assert_eq!(
+            html(&options),
+            "<h2>Gohan</h2><p><strong>bold</strong> </p>\n"
+        );
+        let options = OutputOptions {
+            no_html_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            html(&options),
+            "<h1>Gohan</h1><p><strong>bold</strong> &lt;!-- note --&gt;</p>\n"
+        );
+
+        let options = HtmlOptions::default();
+        let output = |format| render(markdown, format, &options, "GOHAN").unwrap();
+        assert!(output(Format::Markdown).starts_with("# Gohan\n\n**bold**"));
+        assert_eq!(output(Format::Text), "Gohan\n\nbold\n");
+        assert!(output(Format::Ansi).starts_with("\x1b[1mGohan\x1b[22m\n"));
+        assert!(output(Format::Man).starts_with(".TH \"GOHAN\" \"7\"\n.SH Gohan\n"));
+        assert!(output(Format::JsonAst).starts_with(r#"{"version":1,"children":[{"type":"header""#));
+    }
+
+    #[test]
+    fn render_directories() {
+        let root = env::temp_dir().join(format!("gohan-render-{}", std::process::id()));
+        let input = root.join("docs");
+        fs::create_dir_all(input.join("guide")).unwrap();
+        fs::write(input.join("index.md"), "# Home").unwrap();
+        fs::write(input.join("guide/intro.markdown"), "*Intro*").unwrap();
+        fs::write(input.join("icon.png"), "").unwrap();
+
+        let args = RenderArgs {
+            input: input.clone(),
+            output: Some(root.join("dist")),
+            format: Format::Html,
+            watch: false,
+            options: OutputOptions::default(),
+        };
+        run(&args).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("dist/index.html")).unwrap(),
+            "<h1>Home</h1>\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("dist/guide/intro.html")).unwrap(),
+            "<p><em>Intro</em></p>\n"
+        );
+        assert!(!root.join("dist/icon.html").exists());
+
+        let args = RenderArgs {
+            output: None,
+            ..args
+        };
+        assert!(run(&args).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use clap::Args;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use md_parser::links;
+
+use crate::render::{self, Format, OutputOptions};
+
+/// Path of the server-sent events stream telling pages to reload
+const RELOAD_PATH: &str = "/__gohan/reload";
+
+/// Added to every page, reloading it whenever the server says so
+const RELOAD_SCRIPT: &str =
+    r#"<script>new EventSource("/__gohan/reload").onmessage = () => location.reload();</script>"#;
+
+const HTML: &str = "text/html; charset=utf-8";
+const TEXT: &str = "text/plain; charset=utf-8";
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Directory of markdown files to serve
+    root: PathBuf,
+    /// Port to listen to on localhost
+    #[arg(short, long, default_value_t = 4000)]
+    port: u16,
+    #[command(flatten)]
+    options: OutputOptions,
+}
+
+/// Pages rendered so far, and the pages waiting to be reloaded
+struct Site {
+    root: PathBuf,
+    options: OutputOptions,
+    pages: Mutex<HashMap<PathBuf, String>>,
+    clients: Mutex<Vec<mpsc::Sender<()>>>,
+}
+
+/// Serves the markdown files of a directory as HTML pages, rendered on demand
+/// and kept in memory. Pages reload whenever a file of the directory changes.
+pub fn run(args: &ServeArgs) -> Result<(), Box<dyn Error>> {
+    if !args.root.is_dir() {
+        return Err(format!("{} is not a directory", args.root.display()).into());
+    }
+    let site = Arc::new(Site {
+        root: args.root.clone(),
+        options: args.options.clone(),
+        pages: Mutex::default(),
+        clients: Mutex::default(),
+    });
+
+    let (sender, receiver) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&args.root, RecursiveMode::Recursive)?;
+    let watched = Arc::clone(&site);
+    thread::spawn(move || {
+        for event in receiver.into_iter().flatten() {
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                watched.reload();
+            }
+        }
+    });
+
+    let listener = TcpListener::bind(("127.0.0.1", args.port))
+        .map_err(|error| format!("could not listen to port {}: {}", args.port, error))?;
+    eprintln!(
+        "serving {} at http://localhost:{}",
+        args.root.display(),
+        args.port
+    );
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let site = Arc::clone(&site);
+        thread::spawn(move || {
+            if let Err(error) = site.respond(stream) {
+                eprintln!("gohan: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+impl Site {
+    /// Forgets the rendered pages and tells the open ones to reload
+    fn reload(&self) {
+        self.pages.lock().unwrap().clear();
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|client| client.send(()).is_ok());
+    }
+
+    fn respond(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // The headers don't matter, but they have to be read before responding
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return write_response(stream, "400 Bad Request", TEXT, b"bad request");
+        };
+        if method != "GET" {
+            return write_response(stream, "405 Method Not Allowed", TEXT, b"");
+        }
+        let url = target.split(['?', '#']).next().unwrap_or_default();
+        if url == RELOAD_PATH {
+            return self.stream_reloads(stream);
+        }
+
+        match resolve(&self.root, url) {
+            Some(Resource::Page(path)) => match self.page(&path) {
+                Ok(html) => write_response(stream, "200 OK", HTML, html.as_bytes()),
+                Err(error) => {
+                    let message = format!("could not render {}: {}", path.display(), error);
+                    write_response(
+                        stream,
+                        "500 Internal Server Error",
+                        TEXT,
+                        message.as_bytes(),
+                    )
+                }
+            },
+            Some(Resource::Index) => {
+                let html = self.index();
+                write_response(stream, "200 OK", HTML, html.as_bytes())
+            }
+            Some(Resource::File(path)) => {
+                let content = fs::read(&path)?;
+                write_response(stream, "200 OK", content_type(&path), &content)
+            }
+            None => write_response(stream, "404 Not Found", TEXT, b"not found"),
+        }
+    }
+
+    /// Rendered page of the given markdown file, rendering it unless it already is
+    fn page(&self, path: &Path) -> Result<String, Box<dyn Error>> {
+        if let Some(html) = self.pages.lock().unwrap().get(path) {
+            return Ok(html.clone());
+        }
+        let markdown = fs::read_to_string(path)?;
+        let options = self.options.html_options();
+        let html = render::render(&markdown, Format::Html, &options, "")?;
+        let html = live_page(&html);
+        self.pages
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), html.clone());
+        Ok(html)
+    }
+
+    /// Page linking to every markdown file, for sites without an `index.md`
+    fn index(&self) -> String {
+        let files = render::markdown_files(&self.root).unwrap_or_default();
+        let mut html = String::from("<h1>Pages</h1><ul>");
+        for file in files.iter() {
+            let Ok(relative) = file.strip_prefix(&self.root) else {
+                continue;
+            };
+            let href = relative.with_extension("html");
+            let href = encode_path(&href.to_string_lossy().replace('\\', "/"));
+            let name = relative.to_string_lossy();
+            html.push_str(&format!(
+                r#"<li><a href="/{}">{}</a></li>"#,
+                escape(&href),
+                escape(&name)
+            ));
+        }
+        html.push_str("</ul>");
+        live_page(&html)
+    }
+
+    /// Keeps the connection open, sending an event whenever pages should reload
+    fn stream_reloads(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+        )?;
+        let (sender, receiver) = mpsc::channel();
+        self.clients.lock().unwrap().push(sender);
+        for () in receiver {
+            stream.write_all(b"data: reload\n\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a URL of the site points to
+#[derive(Debug, PartialEq, Eq)]
+enum Resource {
+    /// Markdown file to render
+    Page(PathBuf),
+    /// List of the pages of a site without an `index.md`
+    Index,
+    /// File to serve as is, like an image
+    File(PathBuf),
+}
+
+/// Finds what the URL points to within the root directory. Pages are reached
+/// through their `.html` path, or without any extension, and directories
+/// through their `index.md`.
+fn resolve(root: &Path, url: &str) -> Option<Resource> {
+    // Checking the components once decoded, so `%2e%2e` can't climb out either
+    let url = links::percent_decode(url)?;
+    let relative = Path::new(url.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let path = root.join(relative);
+    if path.is_dir() {
+        let index = path.join("index.md");
+        return if index.is_file() {
+            Some(Resource::Page(index))
+        } else if path == root {
+            Some(Resource::Index)
+        } else {
+            None
+        };
+    }
+
+    let page = match path.extension() {
+        Some(extension) if extension == "html" => path.with_extension("md"),
+        None => path.with_extension("md"),
+        Some(_) => path.clone(),
+    };
+    if render::is_markdown(&page) && page.is_file() {
+        Some(Resource::Page(page))
+    } else if path.is_file() {
+        Some(Resource::File(path))
+    } else {
+        None
+    }
+}
+
+/// Percent-encodes a path to link to, keeping its slashes and unreserved characters
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'/' | b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Wraps the HTML into a page reloading itself
+fn live_page(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], RELOAD_SCRIPT, &html[end..]),
+        None => format!(
+            r#"<!DOCTYPE html><html><head><meta charset="utf-8"></head><body>{}{}</body></html>"#,
+            html, RELOAD_SCRIPT
+        ),
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("html") => HTML,
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt" | "md") => TEXT,
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_response(
+    mut stream: TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn resolve_urls() {
+        let root = env::temp_dir().join(format!("gohan-serve-{}", std::process::id()));
+        fs::create_dir_all(root.join("guide")).unwrap();
+        fs::write(root.join("about.md"), "# About").unwrap();
+        fs::write(root.join("guide/index.md"), "# Guide").unwrap();
+        fs::write(root.join("logo.png"), "").unwrap();
+
+        assert_eq!(resolve(&root, "/"), Some(Resource::Index));
+        assert_eq!(
+            resolve(&root, "/about.html"),
+            Some(Resource::Page(root.join("about.md")))
+        );
+        assert_eq!(
+            resolve(&root, "/about"),
+            Some(Resource::Page(root.join("about.md")))
+        );
+        assert_eq!(
+            resolve(&root, "/guide/"),
+            Some(Resource::Page(root.join("guide/index.md")))
+        );
+        assert_eq!(
+            resolve(&root, "/logo.png"),
+            Some(Resource::File(root.join("logo.png")))
+        );
+        assert_eq!(resolve(&root, "/missing.html"), None);
+        assert_eq!(resolve(&root, "/../etc/passwd"), None);
+        assert_eq!(resolve(&root, "/%2e%2e/etc/passwd"), None);
+        assert_eq!(resolve(&root, "/guide%2F..%2F..%2Fetc"), None);
+        assert_eq!(resolve(&root, "/about%"), None);
+
+        fs::write(root.join("my notes.md"), "# Notes").unwrap();
+        fs::write(root.join("café.md"), "# Café").unwrap();
+        assert_eq!(
+            resolve(&root, &format!("/{}", encode_path("my notes.html"))),
+            Some(Resource::Page(root.join("my notes.md")))
+        );
+        assert_eq!(
+            resolve(&root, &format!("/{}", encode_path("café.html"))),
+            Some(Resource::Page(root.join("café.md")))
+        );
+
+        fs::write(root.join("index.md"), "# Home").unwrap();
+        assert_eq!(
+            resolve(&root, "/"),
+            Some(Resource::Page(root.join("index.md")))
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn encode_paths() {
+        assert_eq!(encode_path("guide/my notes.html"), "guide/my%20notes.html");
+        assert_eq!(encode_path("café#1.html"), "caf%C3%A9%231.html");
+        let encoded = encode_path("guide/café#1.html");
+        assert_eq!(
+            links::percent_decode(&encoded).as_deref(),
+            Some("guide/café#1.html")
+        );
+    }
+
+    #[test]
+    fn inject_the_reload_script() {
+        let page = live_page("<!DOCTYPE html><html><body><p>Hi</p></body></html>");
+        assert_eq!(
+            page,
+            format!(
+                "<!DOCTYPE html><html><body><p>Hi</p>{}</body></html>",
+                RELOAD_SCRIPT
+            )
+        );
+        assert!(live_page("<p>Hi</p>").contains(&format!("<p>Hi</p>{}</body>", RELOAD_SCRIPT)));
+    }
+}
@@ -0,0 +1,54 @@
+use std::{collections::BTreeSet, error::Error, path::PathBuf, sync::mpsc, time::Duration};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::render::{self, RenderArgs};
+
+/// How long to wait for more changes before rendering, as editors
+/// usually save files through several filesystem events
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Renders the markdown files of the input again whenever they change,
+/// until the process is stopped
+pub fn watch(args: &RenderArgs) -> Result<(), Box<dyn Error>> {
+    let (sender, receiver) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&args.input, RecursiveMode::Recursive)?;
+    eprintln!("watching {} for changes", args.input.display());
+    // Some platforms report the canonical paths of the files that changed
+    let root = args.input.canonicalize()?;
+
+    loop {
+        let mut changed = BTreeSet::new();
+        collect_changes(&mut changed, receiver.recv()??);
+        while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+            collect_changes(&mut changed, event?);
+        }
+
+        for path in changed {
+            let path = match path.strip_prefix(&root) {
+                Ok(relative) if relative.as_os_str().is_empty() => args.input.clone(),
+                Ok(relative) => args.input.join(relative),
+                Err(_) => path,
+            };
+            // Files may be gone already, like the temporary files of some editors
+            if !path.is_file() {
+                continue;
+            }
+            if let Err(error) = render::render_file(args, &path) {
+                eprintln!("gohan: {}", error);
+            }
+        }
+    }
+}
+
+fn collect_changes(changed: &mut BTreeSet<PathBuf>, event: notify::Event) {
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        changed.extend(
+            event
+                .paths
+                .into_iter()
+                .filter(|path| render::is_markdown(path)),
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::panic;
+
+use md_parser::renderer;
+
+use crate::corpus::Case;
+
+/// HTML rendered by both implementations for a case
+#[derive(Debug)]
+pub struct Comparison {
+    pub case: Case,
+    /// HTML rendered by gohan, or why it couldn't be rendered
+    pub gohan: Result<String, String>,
+    /// HTML rendered by pulldown-cmark
+    pub reference: String,
+}
+
+impl Comparison {
+    /// Whether gohan's HTML differs from the reference one, or couldn't be rendered at all
+    pub fn diverges(&self) -> bool {
+        match &self.gohan {
+            Ok(html) => normalize_html(html) != normalize_html(&self.reference),
+            Err(_) => true,
+        }
+    }
+}
+
+/// Renders every case with both implementations. Panics of gohan are reported
+/// as divergences, without printing them in between the summary.
+pub fn compare_all(cases: Vec<Case>) -> Vec<Comparison> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let comparisons = cases.into_iter().map(compare).collect();
+    panic::set_hook(hook);
+    comparisons
+}
+
+fn compare(case: Case) -> Comparison {
+    let gohan = match panic::catch_unwind(|| renderer::render_html(&case.markdown)) {
+        Ok(Ok(html)) => Ok(html),
+        Ok(Err(error)) => Err(error.to_string()),
+        Err(_) => Err("gohan panicked".to_string()),
+    };
+    let mut reference = String::new();
+    pulldown_cmark::html::push_html(&mut reference, pulldown_cmark::Parser::new(&case.markdown));
+    Comparison {
+        case,
+        gohan,
+        reference,
+    }
+}
+
+/// Makes HTML comparable without caring about insignificant differences,
+/// like whitespace between tags or how void elements are closed
+pub fn normalize_html(html: &str) -> String {
+    let mut normalized = html.replace(" />", ">").replace("/>", ">");
+    while let Some(idx) = normalized.find(">\n") {
+        normalized.replace_range(idx..idx + 2, ">");
+    }
+    normalized.trim().to_string()
+}
+
+/// Line of the difference between the expected and the actual HTML
+#[derive(Debug, PartialEq, Eq)]
+pub enum Line<'a> {
+    Same(&'a str),
+    /// Only in the expected HTML
+    Missing(&'a str),
+    /// Only in the actual HTML
+    Extra(&'a str),
+}
+
+/// Line by line difference between two normalized HTML strings, broken into lines
+/// after every tag, found from their longest common subsequence of lines
+pub fn diff<'a>(expected: &'a str, actual: &'a str) -> Vec<Line<'a>> {
+    let expected: Vec<&str> = expected.split_inclusive('>').collect();
+    let actual: Vec<&str> = actual.split_inclusive('>').collect();
+    // Length of the longest common subsequence of `expected[i..]` and `actual[j..]`
+    let mut lengths = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < expected.len() && j < actual.len() {
+        if expected[i] == actual[j] {
+            lines.push(Line::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            lines.push(Line::Missing(expected[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Extra(actual[j]));
+            j += 1;
+        }
+    }
+    lines.extend(expected[i..].iter().map(|line| Line::Missing(line)));
+    lines.extend(actual[j..].iter().map(|line| Line::Extra(line)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_normalized_html() {
+        let case = |markdown: &str| Case {
+            origin: "test".to_string(),
+            construct: "paragraph".to_string(),
+            markdown: markdown.to_string(),
+        };
+        let comparisons = compare_all(vec![case("# Title\n\n***\n"), case("Some `code`")]);
+        assert!(!comparisons[0].diverges());
+        assert_eq!(comparisons[0].reference, "<h1>Title</h1>\n<hr />\n");
+        assert!(comparisons[1].diverges());
+    }
+
+    #[test]
+    fn diff_html_by_tag() {
+        assert_eq!(
+            diff("<p>a</p><p>b</p>", "<p>a</p><h1>b</h1>"),
+            [
+                Line::Same("<p>"),
+                Line::Same("a</p>"),
+                Line::Missing("<p>"),
+                Line::Missing("b</p>"),
+                Line::Extra("<h1>"),
+                Line::Extra("b</h1>"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    error::Error,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{CodeBlockKind, Event, Tag};
+
+/// Fence opening and closing every example of the CommonMark spec
+const EXAMPLE_FENCE: &str = "````````````````````````````````";
+
+/// Markdown rendered by both implementations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    /// Where the markdown comes from, like `docs/intro.md:12` or `spec example 42`
+    pub origin: String,
+    /// Construct the case is grouped by in the report
+    pub construct: String,
+    pub markdown: String,
+}
+
+/// Cases of the markdown files found at the given path,
+/// which is either a file or a directory searched recursively
+pub fn from_path(path: &Path) -> Result<Vec<Case>, Box<dyn Error>> {
+    let files = match path.is_dir() {
+        true => markdown_files(path)?,
+        false => vec![path.to_path_buf()],
+    };
+    let mut cases = Vec::new();
+    for file in files {
+        let markdown = fs::read_to_string(&file)
+            .map_err(|error| format!("could not read {}: {}", file.display(), error))?;
+        cases.extend(blocks(&markdown).into_iter().map(|(construct, range)| {
+            let line = markdown[..range.start].matches('\n').count() + 1;
+            Case {
+                origin: format!("{}:{}", file.display(), line),
+                construct: construct.to_string(),
+                markdown: markdown[range].to_string(),
+            }
+        }));
+    }
+    Ok(cases)
+}
+
+fn markdown_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|error| format!("could not read {}: {}", dir.display(), error))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "md" || ext == "markdown")
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Top-level blocks of the document as pulldown-cmark parses them,
+/// with the construct each one is made of and its range in the source.
+/// Blocks are compared one at a time, so a divergence is pinned down to its block.
+pub fn blocks(markdown: &str) -> Vec<(&'static str, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut depth = 0;
+    for (event, range) in pulldown_cmark::Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    blocks.push((construct(&tag, &markdown[range.clone()]), range));
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            Event::Rule if depth == 0 => blocks.push(("thematic break", range)),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn construct(tag: &Tag, source: &str) -> &'static str {
+    match tag {
+        Tag::Paragraph => "paragraph",
+        Tag::Heading { .. } if source.trim_start().starts_with('#') => "ATX heading",
+        Tag::Heading { .. } => "setext heading",
+        Tag::BlockQuote(_) => "block quote",
+        Tag::CodeBlock(CodeBlockKind::Fenced(_)) => "fenced code block",
+        Tag::CodeBlock(CodeBlockKind::Indented) => "indented code block",
+        Tag::HtmlBlock => "HTML block",
+        Tag::List(Some(_)) => "ordered list",
+        Tag::List(None) => "bullet list",
+        _ => "other",
+    }
+}
+
+/// Examples of the CommonMark spec, grouped by the section they belong to.
+/// `→` stands for the tabs of the examples.
+pub fn spec_examples(spec: &str) -> Vec<Case> {
+    let mut cases = Vec::new();
+    let mut section = String::new();
+    let mut lines = spec.lines();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with('#') && !line.starts_with("#####") {
+            section = line.trim_start_matches('#').trim().to_string();
+            continue;
+        }
+        if !line.starts_with(EXAMPLE_FENCE) || !line.ends_with(" example") {
+            continue;
+        }
+
+        let mut markdown = String::new();
+        for line in lines.by_ref().take_while(|line| *line != ".") {
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        // The expected HTML is rendered by pulldown-cmark instead
+        for _ in lines.by_ref().take_while(|line| *line != EXAMPLE_FENCE) {}
+
+        cases.push(Case {
+            origin: format!("spec example {}", cases.len() + 1),
+            construct: section.clone(),
+            markdown: markdown.replace('→', "\t"),
+        });
+    }
+    cases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_top_level_blocks() {
+        let markdown = "# Title\n\nSome *text*\n\n- a\n- b\n\n---\n\n```rust\nfn main() {}\n```\n";
+        let blocks: Vec<(&str, &str)> = blocks(markdown)
+            .into_iter()
+            .map(|(construct, range)| (construct, markdown[range].trim_end()))
+            .collect();
+        assert_eq!(
+            blocks,
+            [
+                ("ATX heading", "# Title"),
+                ("paragraph", "Some *text*"),
+                ("bullet list", "- a\n- b"),
+                ("thematic break", "---"),
+                ("fenced code block", "```rust\nfn main() {}\n```"),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_spec_examples() {
+        let spec = format!(
+            "## Tabs\n\n{fence} example\n→foo\n.\n<pre><code>foo\n</code></pre>\n{fence}\n",
+            fence = EXAMPLE_FENCE
+        );
+        assert_eq!(
+            spec_examples(&spec),
+            [Case {
+                origin: "spec example 1".to_string(),
+                construct: "Tabs".to_string(),
+                markdown: "\tfoo\n".to_string(),
+            }]
+        );
+    }
+}
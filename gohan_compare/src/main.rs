@@ -0,0 +1,74 @@
+//! The `gohan-compare` command, rendering a corpus of markdown with both gohan
+//! and pulldown-cmark and reporting where their HTML diverges
+
+use std::{error::Error, fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+
+mod compare;
+mod corpus;
+mod report;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "gohan-compare",
+    version,
+    about = "Compares the HTML rendered by gohan with the one of pulldown-cmark"
+)]
+struct Args {
+    /// Markdown files, or directories whose markdown files to compare block by block
+    paths: Vec<PathBuf>,
+    /// CommonMark `spec.txt` whose examples to compare, fetched with `make commonmark-spec`
+    #[arg(long)]
+    spec: Option<PathBuf>,
+    /// Where to write the HTML report of the divergences
+    #[arg(short, long, default_value = "gohan-compare.html")]
+    output: PathBuf,
+}
+
+fn main() -> ExitCode {
+    match run(&Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("gohan-compare: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    if args.paths.is_empty() && args.spec.is_none() {
+        return Err("nothing to compare, give markdown files or a spec with --spec".into());
+    }
+
+    let mut cases = Vec::new();
+    if let Some(spec) = &args.spec {
+        let text = fs::read_to_string(spec)
+            .map_err(|error| format!("could not read {}: {}", spec.display(), error))?;
+        cases.extend(corpus::spec_examples(&text));
+    }
+    for path in args.paths.iter() {
+        cases.extend(corpus::from_path(path)?);
+    }
+
+    let results = compare::compare_all(cases);
+    let report = report::Report::new(&results);
+    fs::write(&args.output, report.to_html())
+        .map_err(|error| format!("could not write {}: {}", args.output.display(), error))?;
+
+    for group in report.groups.iter() {
+        println!(
+            "{:<24} {:>6} of {:>6} diverge",
+            group.construct,
+            group.divergences.len(),
+            group.total
+        );
+    }
+    println!(
+        "\n{} of {} cases diverge, see {}",
+        report.divergences(),
+        results.len(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Write};
+
+use crate::compare::{self, Comparison, Line};
+
+/// Comparisons of a construct
+pub struct Group<'a> {
+    pub construct: &'a str,
+    pub total: usize,
+    pub divergences: Vec<&'a Comparison>,
+}
+
+/// Divergences grouped by construct, the constructs with the most divergences first
+pub struct Report<'a> {
+    pub groups: Vec<Group<'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(comparisons: &'a [Comparison]) -> Self {
+        let mut groups: BTreeMap<&str, Group> = BTreeMap::new();
+        for comparison in comparisons.iter() {
+            let construct = comparison.case.construct.as_str();
+            let group = groups.entry(construct).or_insert_with(|| Group {
+                construct,
+                total: 0,
+                divergences: Vec::new(),
+            });
+            group.total += 1;
+            if comparison.diverges() {
+                group.divergences.push(comparison);
+            }
+        }
+        let mut groups: Vec<Group> = groups.into_values().collect();
+        // Sorting is stable, so constructs with as many divergences stay sorted by name
+        groups.sort_by_key(|group| Reverse(group.divergences.len()));
+        Self { groups }
+    }
+
+    pub fn divergences(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.divergences.len())
+            .sum()
+    }
+
+    /// Standalone page listing every divergence, with the difference between the HTML
+    /// rendered by pulldown-cmark and gohan
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html)
+            .expect("writing to a string never fails");
+        html
+    }
+
+    fn write_html(&self, out: &mut String) -> std::fmt::Result {
+        let total: usize = self.groups.iter().map(|group| group.total).sum();
+        write!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>gohan-compare</title>\n<style>{}</style>\n</head>\n<body>\n\
+             <h1>gohan-compare</h1>\n<p>{} of {} cases diverge from pulldown-cmark.</p>\n",
+            STYLE,
+            self.divergences(),
+            total
+        )?;
+
+        out.push_str("<table>\n<tr><th>Construct</th><th>Diverging</th><th>Total</th></tr>\n");
+        for (idx, group) in self.groups.iter().enumerate() {
+            let name = escape(group.construct);
+            let name = match group.divergences.is_empty() {
+                true => name,
+                false => format!("<a href=\"#construct-{}\">{}</a>", idx, name),
+            };
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                name,
+                group.divergences.len(),
+                group.total
+            )?;
+        }
+        out.push_str("</table>\n");
+
+        for (idx, group) in self.groups.iter().enumerate() {
+            if group.divergences.is_empty() {
+                continue;
+            }
+            writeln!(
+                out,
+                "<section id=\"construct-{}\">\n<h2>{} ({} of {})</h2>",
+                idx,
+                escape(group.construct),
+                group.divergences.len(),
+                group.total
+            )?;
+            for comparison in group.divergences.iter() {
+                write_divergence(out, comparison)?;
+            }
+            out.push_str("</section>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        Ok(())
+    }
+}
+
+fn write_divergence(out: &mut String, comparison: &Comparison) -> std::fmt::Result {
+    writeln!(
+        out,
+        "<details>\n<summary>{}</summary>\n<pre class=\"markdown\">{}</pre>",
+        escape(&comparison.case.origin),
+        escape(&comparison.case.markdown)
+    )?;
+    let gohan = match &comparison.gohan {
+        Ok(html) => html,
+        Err(error) => {
+            writeln!(out, "<p class=\"error\">{}</p>\n</details>", escape(error))?;
+            return Ok(());
+        }
+    };
+
+    let expected = compare::normalize_html(&comparison.reference);
+    let actual = compare::normalize_html(gohan);
+    out.push_str("<pre class=\"diff\">");
+    for line in compare::diff(&expected, &actual) {
+        match line {
+            Line::Same(line) => writeln!(out, "  {}", escape(line))?,
+            Line::Missing(line) => writeln!(out, "<del>- {}</del>", escape(line))?,
+            Line::Extra(line) => writeln!(out, "<ins>+ {}</ins>", escape(line))?,
+        }
+    }
+    out.push_str("</pre>\n</details>\n");
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60rem; margin: 2rem auto; }\
+    table { border-collapse: collapse; }\
+    th, td { padding: 0.25rem 1rem; text-align: left; border-bottom: 1px solid #ddd; }\
+    summary { cursor: pointer; font-family: monospace; }\
+    pre { background: #f6f8fa; padding: 0.5rem; overflow-x: auto; }\
+    del { color: #b31d28; text-decoration: none; }\
+    ins { color: #22863a; text-decoration: none; }\
+    .error { color: #b31d28; }";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::Case;
+
+    fn comparison(construct: &str, gohan: Result<&str, &str>) -> Comparison {
+        Comparison {
+            case: Case {
+                origin: "notes.md:1".to_string(),
+                construct: construct.to_string(),
+                markdown: "`a` <b>".to_string(),
+            },
+            gohan: gohan.map(str::to_string).map_err(str::to_string),
+            reference: "<p><code>a</code></p>\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn group_divergences_by_construct() {
+        let comparisons = [
+            comparison("paragraph", Ok("<p><code>a</code></p>")),
+            comparison("heading", Ok("<p>`a`</p>")),
+            comparison("paragraph", Err("gohan panicked")),
+        ];
+        let report = Report::new(&comparisons);
+        let groups: Vec<(&str, usize, usize)> = report
+            .groups
+            .iter()
+            .map(|group| (group.construct, group.divergences.len(), group.total))
+            .collect();
+        assert_eq!(groups, [("heading", 1, 1), ("paragraph", 1, 2)]);
+        assert_eq!(report.divergences(), 2);
+
+        let html = report.to_html();
+        assert!(html.contains("2 of 3 cases diverge"));
+        assert!(html.contains("<pre class=\"markdown\">`a` &lt;b&gt;</pre>"));
+        assert!(html.contains("<del>- &lt;code&gt;</del>"));
+        assert!(html.contains("<p class=\"error\">gohan panicked</p>"));
+    }
+}
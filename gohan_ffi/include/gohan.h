@@ -0,0 +1,56 @@
+/* C interface to the gohan markdown renderer */
+
+#ifndef GOHAN_H
+#define GOHAN_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Settings controlling how the HTML output is produced */
+typedef struct GohanOptions {
+  /* Write raw HTML as is. Only meant for trusted input. */
+  bool unsafe_html;
+  /* Pass HTML comments through to the output */
+  bool keep_comments;
+  /* Close void elements XHTML style, e.g. `<br />` */
+  bool self_closing_tags;
+  /* Wrap the output into a complete HTML document */
+  bool full_document;
+  /* Defer loading images until they're needed */
+  bool lazy_images;
+  /* Give headings an `id` derived from their text */
+  bool heading_ids;
+  /* Levels added to every heading, clamped at `<h6>` */
+  uint8_t heading_offset;
+} GohanOptions;
+
+/* Options the renderer uses when none are given */
+GohanOptions gohan_options_default(void);
+
+/*
+ * Renders `length` bytes of UTF-8 markdown as a NUL-terminated HTML string,
+ * to be freed with `gohan_free_string`, or returns NULL when the markdown
+ * can't be rendered. `options` may be NULL to render with the default ones.
+ */
+char *gohan_render_html(const char *input, size_t length, const GohanOptions *options);
+
+/* Frees a string returned by the library. Does nothing given NULL. */
+void gohan_free_string(char *string);
+
+/*
+ * Message of the last failed call on the calling thread, or NULL when the
+ * last call succeeded. The message is owned by the library and stays valid
+ * until the next call on the same thread.
+ */
+const char *gohan_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GOHAN_H */
@@ -0,0 +1,221 @@
+//! C interface to the markdown renderer, for apps calling gohan natively
+//! instead of through WebAssembly. The matching declarations live in
+//! `include/gohan.h`.
+//!
+//! Strings returned by the library are owned by the caller, who hands them
+//! back to [`gohan_free_string`] once done. When a call fails, it returns
+//! `NULL` and [`gohan_last_error`] tells why.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic, ptr, slice, str,
+};
+
+use md_parser::renderer::{self, HeadingAnchors, HtmlOptions};
+
+/// Settings controlling how the HTML output is produced,
+/// a subset of the renderer's [`HtmlOptions`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GohanOptions {
+    /// Write raw HTML as is. Only meant for trusted input.
+    pub unsafe_html: bool,
+    /// Pass HTML comments through to the output
+    pub keep_comments: bool,
+    /// Close void elements XHTML style, e.g. `<br />`
+    pub self_closing_tags: bool,
+    /// Wrap the output into a complete HTML document
+    pub full_document: bool,
+    /// Defer loading images until they're needed
+    pub lazy_images: bool,
+    /// Give headings an `id` derived from their text
+    pub heading_ids: bool,
+    /// Levels added to every heading, clamped at `<h6>`
+    pub heading_offset: u8,
+}
+
+impl Default for GohanOptions {
+    fn default() -> Self {
+        let options = HtmlOptions::default();
+        Self {
+            unsafe_html: options.unsafe_html,
+            keep_comments: options.keep_comments,
+            self_closing_tags: options.self_closing_tags,
+            full_document: options.full_document,
+            lazy_images: options.lazy_images,
+            heading_ids: options.heading_anchors != HeadingAnchors::None,
+            heading_offset: options.heading_offset,
+        }
+    }
+}
+
+impl From<GohanOptions> for HtmlOptions {
+    fn from(options: GohanOptions) -> Self {
+        Self {
+            unsafe_html: options.unsafe_html,
+            keep_comments: options.keep_comments,
+            self_closing_tags: options.self_closing_tags,
+            full_document: options.full_document,
+            lazy_images: options.lazy_images,
+            heading_anchors: if options.heading_ids {
+                HeadingAnchors::Id
+            } else {
+                HeadingAnchors::None
+            },
+            heading_offset: options.heading_offset,
+            ..Default::default()
+        }
+    }
+}
+
+thread_local! {
+    /// Message of the last error on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Options the renderer uses when none are given
+#[no_mangle]
+pub extern "C" fn gohan_options_default() -> GohanOptions {
+    GohanOptions::default()
+}
+
+/// Renders `length` bytes of UTF-8 markdown as a NUL-terminated HTML string,
+/// or returns `NULL` when the markdown can't be rendered.
+/// `options` may be `NULL` to render with the default ones.
+///
+/// # Safety
+///
+/// `input` must point to `length` readable bytes, and `options`
+/// must be `NULL` or point to valid options.
+#[no_mangle]
+pub unsafe extern "C" fn gohan_render_html(
+    input: *const c_char,
+    length: usize,
+    options: *const GohanOptions,
+) -> *mut c_char {
+    if input.is_null() && length > 0 {
+        set_last_error("input is NULL".to_string());
+        return ptr::null_mut();
+    }
+    let bytes: &[u8] = if length == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(input.cast::<u8>(), length)
+    };
+    let options = options.as_ref().copied().unwrap_or_default();
+
+    let result = panic::catch_unwind(|| render(bytes, options.into()))
+        .unwrap_or_else(|_| Err("the renderer panicked".to_string()));
+    match result {
+        Ok(html) => {
+            clear_last_error();
+            html.into_raw()
+        }
+        Err(message) => {
+            set_last_error(message);
+            ptr::null_mut()
+        }
+    }
+}
+
+fn render(bytes: &[u8], options: HtmlOptions) -> Result<CString, String> {
+    let markdown = str::from_utf8(bytes).map_err(|error| format!("invalid UTF-8: {}", error))?;
+    // CommonMark replaces NUL characters, which C strings couldn't hold anyway
+    let markdown = markdown.replace('\0', "\u{FFFD}");
+    let html =
+        renderer::render_html_with(&markdown, &options).map_err(|error| error.to_string())?;
+    CString::new(html).map_err(|_| "the HTML contains a NUL byte".to_string())
+}
+
+/// Frees a string returned by the library. Does nothing given `NULL`.
+///
+/// # Safety
+///
+/// `string` must be `NULL` or a string returned by the library,
+/// which isn't used anymore once freed.
+#[no_mangle]
+pub unsafe extern "C" fn gohan_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Message of the last failed call on the calling thread, or `NULL` when
+/// the last call succeeded. The message is owned by the library and stays
+/// valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn gohan_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|error| *error.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    fn render_html(markdown: &[u8], options: Option<&GohanOptions>) -> Result<String, String> {
+        let options = options.map_or(ptr::null(), |options| options as *const _);
+        unsafe {
+            let html = gohan_render_html(markdown.as_ptr().cast(), markdown.len(), options);
+            if html.is_null() {
+                let error = CStr::from_ptr(gohan_last_error());
+                return Err(error.to_str().unwrap().to_string());
+            }
+            let result = CStr::from_ptr(html).to_str().unwrap().to_string();
+            gohan_free_string(html);
+            assert!(gohan_last_error().is_null());
+            Ok(result)
+        }
+    }
+
+    #[test]
+    fn render_through_the_c_interface() {
+        assert_eq!(
+            render_html(b"# Gohan\n\nSome **bold**", None).unwrap(),
+            "<h1>Gohan</h1><p>Some <strong>bold</strong></p>"
+        );
+        let options = GohanOptions {
+            self_closing_tags: true,
+            heading_ids: true,
+            heading_offset: 1,
+            ..gohan_options_default()
+        };
+        assert_eq!(
+            render_html(b"# Gohan\n\n---", Some(&options)).unwrap(),
+            r#"<h2 id="gohan">Gohan</h2><hr />"#
+        );
+        assert_eq!(render_html(b"", None).unwrap(), "");
+        assert_eq!(render_html(b"a\0b", None).unwrap(), "<p>a\u{FFFD}b</p>");
+    }
+
+    #[test]
+    fn report_errors() {
+        assert!(render_html(b"caf\xC3", None)
+            .unwrap_err()
+            .starts_with("invalid UTF-8"));
+        // Elements nested too deep are rendered as text rather than rejected
+        assert!(render_html(">".repeat(500).as_bytes(), None)
+            .unwrap()
+            .contains("&gt;&gt;"));
+        unsafe {
+            assert!(gohan_render_html(ptr::null(), 1, ptr::null()).is_null());
+            gohan_free_string(ptr::null_mut());
+        }
+    }
+}
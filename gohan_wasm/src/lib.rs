@@ -0,0 +1,129 @@
+//! JavaScript bindings to the markdown parser and renderer,
+//! built into an npm package with `wasm-pack`.
+
+use md_parser::{
+    ast, parser,
+    renderer::{self, HeadingAnchors, HtmlOptions},
+};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const RENDER_OPTIONS: &str = r#"
+/** Settings controlling how the HTML output is produced */
+export interface RenderOptions {
+    /** Write raw HTML as is. Only meant for trusted input. */
+    unsafeHtml?: boolean;
+    /** Pass HTML comments through to the output */
+    keepComments?: boolean;
+    /** Schemes, in lowercase, that link and image destinations may use */
+    allowedSchemes?: string[];
+    /** Close void elements XHTML style, e.g. `<br />` */
+    selfClosingTags?: boolean;
+    /** Wrap the output into a complete HTML document */
+    fullDocument?: boolean;
+    /** Defer loading images until they're needed */
+    lazyImages?: boolean;
+    /** Give headings an `id`, and optionally a permalink pointing to it */
+    headingAnchors?: "none" | "id" | "append" | "wrap";
+    /** Levels added to every heading, clamped at `<h6>` */
+    headingOffset?: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "RenderOptions")]
+    pub type RenderOptions;
+}
+
+/// Renders the markdown as HTML, throwing when it can't be parsed
+#[wasm_bindgen]
+pub fn render_html(input: &str, options: Option<RenderOptions>) -> Result<String, JsError> {
+    let options: Options = match options {
+        Some(options) => serde_wasm_bindgen::from_value(options.into())?,
+        None => Options::default(),
+    };
+    Ok(renderer::render_html_with(input, &options.into())?)
+}
+
+/// Parses the markdown into its AST, written as JSON following the
+/// schema documented in `md_parser::ast`
+#[wasm_bindgen]
+pub fn parse_to_json(input: &str) -> Result<String, JsError> {
+    let ast = parser::parse(input)?;
+    Ok(ast::to_json(&ast))
+}
+
+/// Options read from a `RenderOptions` object, with the missing ones
+/// falling back to the renderer defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Options {
+    unsafe_html: Option<bool>,
+    keep_comments: Option<bool>,
+    allowed_schemes: Option<Vec<String>>,
+    self_closing_tags: Option<bool>,
+    full_document: Option<bool>,
+    lazy_images: Option<bool>,
+    heading_anchors: Option<Anchors>,
+    heading_offset: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Anchors {
+    None,
+    Id,
+    Append,
+    Wrap,
+}
+
+impl From<Options> for HtmlOptions {
+    fn from(options: Options) -> Self {
+        let defaults = HtmlOptions::default();
+        Self {
+            unsafe_html: options.unsafe_html.unwrap_or(defaults.unsafe_html),
+            keep_comments: options.keep_comments.unwrap_or(defaults.keep_comments),
+            allowed_schemes: options.allowed_schemes.unwrap_or(defaults.allowed_schemes),
+            self_closing_tags: options
+                .self_closing_tags
+                .unwrap_or(defaults.self_closing_tags),
+            full_document: options.full_document.unwrap_or(defaults.full_document),
+            lazy_images: options.lazy_images.unwrap_or(defaults.lazy_images),
+            heading_anchors: match options.heading_anchors {
+                Some(Anchors::None) => HeadingAnchors::None,
+                Some(Anchors::Id) => HeadingAnchors::Id,
+                Some(Anchors::Append) => HeadingAnchors::Append,
+                Some(Anchors::Wrap) => HeadingAnchors::Wrap,
+                None => defaults.heading_anchors,
+            },
+            heading_offset: options.heading_offset.unwrap_or(defaults.heading_offset),
+            ..defaults
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fall_back_to_default_options() {
+        let options = Options {
+            heading_anchors: Some(Anchors::Id),
+            heading_offset: Some(1),
+            allowed_schemes: Some(vec!["ftp".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            renderer::render_html_with("# Gohan\n\n[a](ftp://a) [b](https://b)", &options.into())
+                .unwrap(),
+            r#"<h2 id="gohan">Gohan</h2><p><a href="ftp://a">a</a> <a href="">b</a></p>"#
+        );
+        assert_eq!(
+            renderer::render_html_with("<b>", &Options::default().into()).unwrap(),
+            renderer::render_html("<b>").unwrap()
+        );
+    }
+}
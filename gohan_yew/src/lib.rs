@@ -0,0 +1,151 @@
+//! Renders the markdown AST into Yew virtual DOM nodes, so components
+//! can show markdown without injecting an HTML string into the page.
+
+use core::ops::Range;
+
+use md_parser::{
+    ast::{collect_text, Node},
+    renderer::{self, HtmlOptions},
+    token::Span,
+};
+use yew::{classes, html, virtual_dom::VText, Html};
+
+/// Renders the AST into the nodes the default HTML renderer would write
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::parser;
+/// use yew::{function_component, Html};
+///
+/// #[function_component]
+/// fn Preview() -> Html {
+///     let ast = parser::parse("Hello **Gohan**").unwrap();
+///     gohan_yew::render_yew(&ast)
+/// }
+/// ```
+pub fn render_yew(ast: &[Node]) -> Html {
+    render_yew_with(ast, &HtmlOptions::default())
+}
+
+/// Renders the AST following the options that apply to a virtual DOM:
+/// the destinations allowed for links and images, how they are rewritten,
+/// heading levels, lazy images and source positions.
+///
+/// Text is never parsed as HTML, whatever `unsafe_html` is,
+/// and comments are always left out.
+pub fn render_yew_with(ast: &[Node], options: &HtmlOptions) -> Html {
+    render_nodes(ast, options)
+}
+
+fn render_nodes(nodes: &[Node], options: &HtmlOptions) -> Html {
+    nodes
+        .iter()
+        .map(|node| render_node(node, options))
+        .collect()
+}
+
+fn render_node(node: &Node, options: &HtmlOptions) -> Html {
+    match node {
+        Node::Header(header) => {
+            let level = header.level.saturating_add(options.heading_offset).min(6);
+            let position = source_position(options, &header.span);
+            html! {
+                <@{format!("h{}", level)} data-sourcepos={position}>
+                    { render_nodes(&header.children, options) }
+                </@>
+            }
+        }
+        Node::Paragraph(paragraph) => {
+            let position = source_position(options, &paragraph.span);
+            html! {
+                <p data-sourcepos={position}>{ paragraph_content(&paragraph.children, options) }</p>
+            }
+        }
+        Node::CodeBlock(code_block) => {
+            let class = classes!(code_block
+                .language
+                .as_ref()
+                .map(|language| format!("language-{}", language)));
+            let position = source_position(options, &code_block.span);
+            html! {
+                <pre data-sourcepos={position}>
+                    <code class={class}>{ plain_text(&code_block.code) }</code>
+                </pre>
+            }
+        }
+        Node::BlockQuote(quote) => {
+            let position = source_position(options, &quote.span);
+            html! {
+                <blockquote data-sourcepos={position}>{ render_nodes(&quote.children, options) }</blockquote>
+            }
+        }
+        Node::List(list) if list.ordered => {
+            let start = list
+                .start
+                .filter(|start| *start != 1)
+                .map(|start| start.to_string());
+            let position = source_position(options, &list.span);
+            html! {
+                <ol start={start} data-sourcepos={position}>{ render_nodes(&list.children, options) }</ol>
+            }
+        }
+        Node::List(list) => {
+            let position = source_position(options, &list.span);
+            html! { <ul data-sourcepos={position}>{ render_nodes(&list.children, options) }</ul> }
+        }
+        Node::ListItem(item) => {
+            // List items render their paragraphs tightly,
+            // without wrapping them in a paragraph element.
+            let children: Html = item
+                .children
+                .iter()
+                .map(|child| match child {
+                    Node::Paragraph(paragraph) => paragraph_content(&paragraph.children, options),
+                    child => render_node(child, options),
+                })
+                .collect();
+            let position = source_position(options, &item.span);
+            html! { <li data-sourcepos={position}>{ children }</li> }
+        }
+        Node::ThematicBreak => html! { <hr /> },
+        Node::Bold(bold) => html! { <strong>{ render_nodes(&bold.children, options) }</strong> },
+        Node::Italic(italic) => html! { <em>{ render_nodes(&italic.children, options) }</em> },
+        Node::Link(link) => {
+            let href = renderer::sanitize_url(options, &plain_text(&link.url));
+            let title = link.title.as_deref().map(plain_text);
+            html! { <a href={href} title={title}>{ render_nodes(&link.children, options) }</a> }
+        }
+        Node::Image(image) => {
+            let src = renderer::sanitize_url(options, &plain_text(&image.url));
+            let alt = plain_text(&image.alt);
+            let title = image.title.as_deref().map(plain_text);
+            let loading = options.lazy_images.then_some("lazy");
+            let decoding = options.lazy_images.then_some("async");
+            html! { <img src={src} alt={alt} title={title} loading={loading} decoding={decoding} /> }
+        }
+        Node::Text(text) | Node::Digit(text) => VText::new(text.to_string()).into(),
+        Node::LineBreak => html! { <br /> },
+        Node::Comment(_) => Html::default(),
+    }
+}
+
+/// `data-sourcepos` of a block element, when the options ask for it
+fn source_position(options: &HtmlOptions, span: &Range<Span>) -> Option<String> {
+    options.source_positions.then(|| renderer::sourcepos(span))
+}
+
+/// Inline children of a paragraph, without the line break it may end with
+fn paragraph_content(nodes: &[Node], options: &HtmlOptions) -> Html {
+    let nodes = match nodes.split_last() {
+        Some((Node::LineBreak, init)) => init,
+        _ => nodes,
+    };
+    render_nodes(nodes, options)
+}
+
+fn plain_text(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    collect_text(&mut text, nodes);
+    text
+}
@@ -0,0 +1,32 @@
+//! Documents shared by the benchmarks
+
+/// Documents the benchmarks run on, from the smallest to the largest
+pub fn corpora() -> Vec<(&'static str, String)> {
+    let readme = include_str!("../../../README.md").to_string();
+
+    // Every snapshot input repeated until it reaches the size of a book chapter
+    let inputs = [
+        include_str!("../../src/snapshot_inputs/badges.md"),
+        include_str!("../../src/snapshot_inputs/code_blocks.md"),
+        include_str!("../../src/snapshot_inputs/input2.md"),
+        include_str!("../../src/snapshot_inputs/lexer_input.md"),
+        include_str!("../../src/snapshot_inputs/quotes_and_lists.md"),
+    ]
+    .join("\n\n");
+    let mut chapter = String::new();
+    while chapter.len() < 64 * 1024 {
+        chapter.push_str(&inputs);
+        chapter.push_str("\n\n");
+    }
+
+    // Emphasis which never closes, nested as deep as the parser allows,
+    // so every delimiter is retried as plain text
+    let emphasis = "*a **b ".repeat(60);
+    let nested_emphasis = format!("{}\n\n", emphasis).repeat(64);
+
+    vec![
+        ("readme", readme),
+        ("chapter", chapter),
+        ("nested_emphasis", nested_emphasis),
+    ]
+}
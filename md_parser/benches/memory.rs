@@ -0,0 +1,103 @@
+//! Memory allocated while parsing documents of different shapes,
+//! as the AST takes up much more memory than the markdown it comes from.
+//! Nodes owning their children are compared with the flat [`Arena`].
+//!
+//! Run with `cargo bench -p md_parser --bench memory`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    mem,
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+use common::corpora;
+use md_parser::{
+    ast::{Arena, ArenaNode, Node},
+    events::EventIter,
+    parser,
+};
+
+mod common;
+
+/// Number of allocations, including reallocations, since the last reset
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// Bytes allocated since the last reset
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+/// Bytes currently allocated
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+/// Most bytes allocated at once since the last reset
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// System allocator keeping track of the memory it allocates
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE.fetch_sub(layout.size(), Relaxed);
+        record_allocation(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+fn record_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Relaxed);
+    ALLOCATED.fetch_add(size, Relaxed);
+    let live = LIVE.fetch_add(size, Relaxed) + size;
+    PEAK.fetch_max(live, Relaxed);
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn main() {
+    println!(
+        "Each node takes {} bytes, and each arena node {} bytes\n",
+        mem::size_of::<Node>(),
+        mem::size_of::<ArenaNode>()
+    );
+    println!(
+        "{:<16} {:<6} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "Corpus", "Nodes", "Markdown", "Allocations", "Allocated", "Peak", "Retained"
+    );
+    for (name, markdown) in corpora() {
+        measure(name, "tree", &markdown, |markdown| {
+            parser::parse(markdown).unwrap()
+        });
+        measure(name, "arena", &markdown, |markdown| {
+            Arena::from_events(EventIter::new(markdown))
+        });
+    }
+}
+
+/// Prints the memory allocated while parsing the markdown
+fn measure<'s, T>(name: &str, nodes: &str, markdown: &'s str, parse: impl Fn(&'s str) -> T) {
+    let before = LIVE.load(Relaxed);
+    ALLOCATIONS.store(0, Relaxed);
+    ALLOCATED.store(0, Relaxed);
+    PEAK.store(before, Relaxed);
+
+    let parsed = parse(markdown);
+    // What's left once parsing is done is the nodes themselves
+    let retained = LIVE.load(Relaxed) - before;
+    println!(
+        "{:<16} {:<6} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        name,
+        nodes,
+        markdown.len(),
+        ALLOCATIONS.load(Relaxed),
+        ALLOCATED.load(Relaxed),
+        PEAK.load(Relaxed) - before,
+        retained
+    );
+    drop(parsed);
+}
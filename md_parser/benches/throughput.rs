@@ -0,0 +1,90 @@
+//! Throughput of each stage of the pipeline on documents of different shapes.
+//!
+//! Run with `cargo bench -p md_parser`, optionally filtered by stage,
+//! e.g. `cargo bench -p md_parser -- parse`.
+
+use common::corpora;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use md_parser::{lexer::Lexer, parser, renderer, sanitizer::Sanitizer};
+
+mod common;
+
+fn lex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+    for (name, markdown) in corpora().iter() {
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            markdown,
+            |b, markdown| b.iter(|| Lexer::new(black_box(markdown)).into_tokens()),
+        );
+    }
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, markdown) in corpora().iter() {
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            markdown,
+            |b, markdown| b.iter(|| parser::parse(black_box(markdown)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    let options = renderer::HtmlOptions::default();
+    for (name, markdown) in corpora().iter() {
+        let ast = parser::parse(markdown).unwrap();
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &ast, |b, ast| {
+            b.iter(|| renderer::render_ast(black_box(ast), &options))
+        });
+    }
+    group.finish();
+}
+
+/// The whole pipeline, from markdown to HTML
+fn render_html(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_html");
+    for (name, markdown) in corpora().iter() {
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            markdown,
+            |b, markdown| b.iter(|| renderer::render_html(black_box(markdown)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+/// Inputs which used to take quadratic time, rendered from markdown to HTML
+fn pathological(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pathological");
+    let options = renderer::HtmlOptions {
+        sanitizer: Some(Sanitizer::default()),
+        ..Default::default()
+    };
+    let cases = [
+        ("unterminated_comments", "<!--".repeat(50_000)),
+        ("content_tags", "<style></style>".repeat(40_000)),
+    ];
+    for (name, markdown) in cases.iter() {
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            markdown,
+            |b, markdown| {
+                b.iter(|| renderer::render_html_with(black_box(markdown), &options).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lex, parse, render, render_html, pathological);
+criterion_main!(benches);
@@ -1,15 +1,180 @@
-use serde::{Deserialize, Serialize};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::Range;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::token::Span;
+
+mod arena;
+mod json;
+mod tree;
+
+pub use arena::{Arena, ArenaNode};
+pub use json::{from_json, to_json, JsonError, JSON_VERSION};
+pub use tree::{debug_tree, DebugTree};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Node<'s> {
     Header(Header<'s>),
     Paragraph(Paragraph<'s>),
+    CodeBlock(CodeBlock<'s>),
+    BlockQuote(BlockQuote<'s>),
+    List(List<'s>),
+    ListItem(ListItem<'s>),
+    ThematicBreak,
     Link(Link<'s>),
+    Image(Image<'s>),
     Bold(Bold<'s>),
     Italic(Italic<'s>),
-    Digit(&'s str),
-    Text(&'s str),
+    #[serde(borrow)]
+    Digit(Cow<'s, str>),
+    /// Text with its backslash escapes resolved. It's borrowed when it's written
+    /// as is in the source, and owned when it had to be joined from pieces,
+    /// like the text around an escaped character.
+    #[serde(borrow)]
+    Text(Cow<'s, str>),
     LineBreak,
+    /// Content of an HTML comment, without its `<!--` and `-->` delimiters
+    #[serde(borrow)]
+    Comment(Cow<'s, str>),
+}
+
+impl<'s> Node<'s> {
+    /// Copies any text still borrowed from the markdown source,
+    /// so the tree can outlive it, e.g. to be cached or sent to another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use md_parser::{ast::Node, parser};
+    /// let ast: Vec<Node<'static>> = {
+    ///     let markdown = String::from("Hello **Gohan**");
+    ///     let ast = parser::parse(&markdown).unwrap();
+    ///     ast.into_iter().map(Node::into_owned).collect()
+    /// };
+    /// let handle = std::thread::spawn(move || ast.len());
+    /// assert_eq!(handle.join().unwrap(), 1);
+    /// ```
+    pub fn into_owned(self) -> Node<'static> {
+        match self {
+            Node::Header(header) => Node::Header(Header {
+                level: header.level,
+                children: into_owned_nodes(header.children),
+                span: header.span,
+            }),
+            Node::Paragraph(paragraph) => Node::Paragraph(Paragraph {
+                children: into_owned_nodes(paragraph.children),
+                span: paragraph.span,
+            }),
+            Node::CodeBlock(code_block) => Node::CodeBlock(CodeBlock {
+                language: code_block.language,
+                attrs: code_block.attrs,
+                code: into_owned_nodes(code_block.code),
+                span: code_block.span,
+            }),
+            Node::BlockQuote(quote) => Node::BlockQuote(BlockQuote {
+                children: into_owned_nodes(quote.children),
+                span: quote.span,
+            }),
+            Node::List(list) => Node::List(List {
+                ordered: list.ordered,
+                start: list.start,
+                children: into_owned_nodes(list.children),
+                span: list.span,
+            }),
+            Node::ListItem(item) => Node::ListItem(ListItem {
+                children: into_owned_nodes(item.children),
+                span: item.span,
+            }),
+            Node::ThematicBreak => Node::ThematicBreak,
+            Node::Link(link) => Node::Link(Link {
+                children: into_owned_nodes(link.children),
+                url: into_owned_nodes(link.url),
+                title: link.title.map(into_owned_nodes),
+                span: link.span,
+            }),
+            Node::Image(image) => Node::Image(Image {
+                alt: into_owned_nodes(image.alt),
+                url: into_owned_nodes(image.url),
+                title: image.title.map(into_owned_nodes),
+                span: image.span,
+            }),
+            Node::Bold(bold) => Node::Bold(Bold {
+                children: into_owned_nodes(bold.children),
+                span: bold.span,
+            }),
+            Node::Italic(italic) => Node::Italic(Italic {
+                children: into_owned_nodes(italic.children),
+                span: italic.span,
+            }),
+            Node::Digit(digit) => Node::Digit(Cow::Owned(digit.into_owned())),
+            Node::Text(text) => Node::Text(Cow::Owned(text.into_owned())),
+            Node::LineBreak => Node::LineBreak,
+            Node::Comment(comment) => Node::Comment(Cow::Owned(comment.into_owned())),
+        }
+    }
+
+    /// Child nodes nested within this node, like the text of a link or the items of a list.
+    /// Link destinations and titles are attributes rather than children.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<'s>>> {
+        match self {
+            Node::Header(header) => Some(&mut header.children),
+            Node::Paragraph(paragraph) => Some(&mut paragraph.children),
+            Node::CodeBlock(code_block) => Some(&mut code_block.code),
+            Node::BlockQuote(quote) => Some(&mut quote.children),
+            Node::List(list) => Some(&mut list.children),
+            Node::ListItem(item) => Some(&mut item.children),
+            Node::Link(link) => Some(&mut link.children),
+            Node::Image(image) => Some(&mut image.alt),
+            Node::Bold(bold) => Some(&mut bold.children),
+            Node::Italic(italic) => Some(&mut italic.children),
+            Node::ThematicBreak
+            | Node::Digit(_)
+            | Node::Text(_)
+            | Node::LineBreak
+            | Node::Comment(_) => None,
+        }
+    }
+}
+
+/// An AST not borrowing from anything, which unlike `Vec<Node>` can be
+/// deserialized from any source, e.g. a cache read from a network stream.
+/// Serializes the same way as the nodes it holds.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::OwnedAst, parser};
+/// let ast = parser::parse("Hello **Gohan**").unwrap();
+/// let json = serde_json::to_vec(&ast).unwrap();
+/// let cached: OwnedAst = serde_json::from_reader(json.as_slice()).unwrap();
+/// assert_eq!(cached.0, ast);
+/// ```
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct OwnedAst(pub Vec<Node<'static>>);
+
+impl<'de> Deserialize<'de> for OwnedAst {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ast = Vec::<Node<'de>>::deserialize(deserializer)?;
+        Ok(Self(into_owned_nodes(ast)))
+    }
+}
+
+impl From<Vec<Node<'_>>> for OwnedAst {
+    fn from(ast: Vec<Node<'_>>) -> Self {
+        Self(into_owned_nodes(ast))
+    }
+}
+
+impl From<OwnedAst> for Vec<Node<'static>> {
+    fn from(ast: OwnedAst) -> Self {
+        ast.0
+    }
+}
+
+fn into_owned_nodes(nodes: Vec<Node>) -> Vec<Node<'static>> {
+    nodes.into_iter().map(Node::into_owned).collect()
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -17,32 +182,143 @@ pub struct Header<'s> {
     pub level: u8,
     #[serde(borrow)]
     pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Paragraph<'s> {
     #[serde(borrow)]
     pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CodeBlock<'s> {
+    /// Language taken from the info string after the opening fence
+    pub language: Option<String>,
+    /// Raw attributes from the info string, e.g. `linenos startline=5`
+    /// for a fence like ` ```{.rust linenos startline=5} `
+    pub attrs: Option<String>,
+    /// List of Text nodes, taken verbatim from the source
+    #[serde(borrow)]
+    pub code: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockQuote<'s> {
+    #[serde(borrow)]
+    pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct List<'s> {
+    pub ordered: bool,
+    /// Number of the first item of an ordered list
+    pub start: Option<u32>,
+    /// List of ListItem nodes
+    #[serde(borrow)]
+    pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ListItem<'s> {
+    #[serde(borrow)]
+    pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Link<'s> {
+    /// Link text, empty for links like `[](/url)`
     #[serde(borrow)]
     pub children: Vec<Node<'s>>,
+    /// List of Text nodes, empty for links like `[text]()` or `[text](<>)`
+    pub url: Vec<Node<'s>>,
+    /// List of Text nodes, used for tooltips
+    pub title: Option<Vec<Node<'s>>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Image<'s> {
+    /// Inline nodes describing the image,
+    /// flattened to plain text when rendered as the `alt` attribute
+    #[serde(borrow)]
+    pub alt: Vec<Node<'s>>,
     /// List of Text nodes
     pub url: Vec<Node<'s>>,
-    // TODO: Support title for tooltips
-    // title: Option<&'s str>
+    /// List of Text nodes, used for tooltips
+    pub title: Option<Vec<Node<'s>>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Bold<'s> {
     #[serde(borrow)]
     pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Italic<'s> {
     #[serde(borrow)]
     pub children: Vec<Node<'s>>,
+    /// Positions of the first and last source tokens of the element
+    pub span: Range<Span>,
+}
+
+/// Flattens the given nodes into their plain text content,
+/// dropping any markup around them.
+pub fn collect_text(buffer: &mut String, nodes: &[Node]) {
+    for node in nodes.iter() {
+        match node {
+            Node::Text(text) | Node::Digit(text) => buffer.push_str(text),
+            Node::LineBreak => buffer.push(' '),
+            Node::ThematicBreak | Node::Comment(_) => {}
+            Node::Bold(bold) => collect_text(buffer, &bold.children),
+            Node::Italic(italic) => collect_text(buffer, &italic.children),
+            Node::Link(link) => collect_text(buffer, &link.children),
+            Node::Image(image) => collect_text(buffer, &image.alt),
+            Node::Header(header) => collect_text(buffer, &header.children),
+            Node::Paragraph(paragraph) => collect_text(buffer, &paragraph.children),
+            Node::CodeBlock(code_block) => collect_text(buffer, &code_block.code),
+            Node::BlockQuote(quote) => collect_text(buffer, &quote.children),
+            Node::List(list) => collect_text(buffer, &list.children),
+            Node::ListItem(item) => collect_text(buffer, &item.children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::parser;
+
+    use super::*;
+
+    #[test]
+    fn deserialize_owned_ast_from_readers() {
+        let markdown = "# Gohan\n\n> [a *b*](/c \"d\") `e`\n\n```rust\nlet f = 1;\n```\n";
+        let ast = parser::parse(markdown).unwrap();
+        let json = serde_json::to_string(&ast).unwrap();
+
+        // Readers can't lend out their strings, so borrowed nodes can't be read from them
+        let cached: OwnedAst = serde_json::from_reader(Cursor::new(json.clone())).unwrap();
+        assert_eq!(cached.0, ast);
+        assert_eq!(serde_json::to_string(&cached).unwrap(), json);
+        assert_eq!(OwnedAst::from(ast), cached);
+    }
 }
@@ -0,0 +1,189 @@
+//! Flat representation of the AST, with the nodes of the whole document
+//! in a single list rather than every element owning a list of its children.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{mem, ops::Range};
+
+use super::Node;
+use crate::events::{self, Event, Tag};
+
+/// Node of an [`Arena`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArenaNode<'s> {
+    /// Element along with the position of its children in the arena
+    Element {
+        tag: Tag<'s>,
+        children: Range<usize>,
+    },
+    Text(Cow<'s, str>),
+    Digit(Cow<'s, str>),
+    LineBreak,
+    ThematicBreak,
+    /// Content of an HTML comment, without its `<!--` and `-->` delimiters
+    Comment(Cow<'s, str>),
+}
+
+/// Nodes of a document all kept in a single list, where the children of
+/// every element follow each other. Building it takes a handful of allocations
+/// however many elements there are, while [`Node`]s allocate their children
+/// one element at a time.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::{Arena, ArenaNode}, events::{EventIter, Tag}};
+/// let arena = Arena::from_events(EventIter::new("# Gohan\n\nSome **bold** text"));
+/// assert_eq!(arena.roots().len(), 2);
+/// let ArenaNode::Element { tag: Tag::Paragraph { .. }, .. } = &arena.roots()[1] else {
+///     unreachable!()
+/// };
+/// let children = arena.children(&arena.roots()[1]);
+/// assert_eq!(children[0], ArenaNode::Text("Some ".into()));
+/// assert_eq!(arena.children(&children[1]), [ArenaNode::Text("bold".into())]);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Arena<'s> {
+    nodes: Vec<ArenaNode<'s>>,
+    /// Position of the top-level nodes of the document
+    roots: Range<usize>,
+}
+
+impl<'s> Arena<'s> {
+    /// Builds the arena from the events of a document, like [`events::build_ast`]
+    /// builds its nodes. Elements which never end are left out.
+    pub fn from_events(events: impl IntoIterator<Item = Event<'s>>) -> Self {
+        let mut nodes = Vec::new();
+        // Nodes whose element hasn't ended yet, moved into the arena together once it ends
+        let mut pending = Vec::new();
+        // Elements started but not yet ended, along with where their children start in `pending`
+        let mut open: Vec<(Tag<'s>, usize)> = Vec::new();
+
+        for event in events {
+            let node = match event {
+                Event::Start(tag) => {
+                    open.push((tag, pending.len()));
+                    continue;
+                }
+                Event::End(_) => match open.pop() {
+                    Some((tag, start)) => {
+                        let first = nodes.len();
+                        nodes.extend(pending.drain(start..));
+                        ArenaNode::Element {
+                            tag,
+                            children: first..nodes.len(),
+                        }
+                    }
+                    None => continue,
+                },
+                Event::Text(text) => ArenaNode::Text(text),
+                Event::Digit(digit) => ArenaNode::Digit(digit),
+                Event::LineBreak => ArenaNode::LineBreak,
+                Event::ThematicBreak => ArenaNode::ThematicBreak,
+                Event::Comment(comment) => ArenaNode::Comment(comment),
+            };
+            pending.push(node);
+        }
+
+        if let Some((_, start)) = open.first() {
+            pending.truncate(*start);
+        }
+        let first = nodes.len();
+        nodes.extend(pending);
+        nodes.shrink_to_fit();
+        let roots = first..nodes.len();
+        Self { nodes, roots }
+    }
+
+    /// Top-level nodes of the document
+    pub fn roots(&self) -> &[ArenaNode<'s>] {
+        &self.nodes[self.roots.clone()]
+    }
+
+    /// Children of the given node of the arena, which are none unless it's an element
+    pub fn children(&self, node: &ArenaNode<'s>) -> &[ArenaNode<'s>] {
+        match node {
+            ArenaNode::Element { children, .. } => &self.nodes[children.clone()],
+            _ => &[],
+        }
+    }
+
+    /// Number of nodes in the whole document
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Turns the arena into the nodes [`events::build_ast`] would have built
+    pub fn into_nodes(mut self) -> Vec<Node<'s>> {
+        take_nodes(&mut self.nodes, self.roots.clone())
+    }
+}
+
+fn take_nodes<'s>(nodes: &mut [ArenaNode<'s>], range: Range<usize>) -> Vec<Node<'s>> {
+    let mut taken = Vec::with_capacity(range.len());
+    for idx in range {
+        let node = match mem::replace(&mut nodes[idx], ArenaNode::LineBreak) {
+            ArenaNode::Element { tag, children } => {
+                let children = take_nodes(nodes, children);
+                events::element(tag, children)
+            }
+            ArenaNode::Text(text) => Node::Text(text),
+            ArenaNode::Digit(digit) => Node::Digit(digit),
+            ArenaNode::LineBreak => Node::LineBreak,
+            ArenaNode::ThematicBreak => Node::ThematicBreak,
+            ArenaNode::Comment(comment) => Node::Comment(comment),
+        };
+        taken.push(node);
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{events::EventIter, parser};
+
+    use super::*;
+
+    #[test]
+    fn keep_children_next_to_each_other() {
+        let markdown = "> - *a* b\n> - c\n\nd [e](f) **g**\n\n- h";
+        let arena = Arena::from_events(EventIter::new(markdown));
+        assert_eq!(arena.roots().len(), 3);
+        let ArenaNode::Element { children, .. } = &arena.roots()[0] else {
+            panic!("expected the block quote first");
+        };
+        assert_eq!(children.len(), 1);
+        let list = arena.children(&arena.roots()[0]);
+        assert_eq!(arena.children(&list[0]).len(), 2);
+        // Top-level nodes come last, after all the nodes within them
+        assert_eq!(arena.roots.end, arena.len());
+    }
+
+    #[test]
+    fn build_the_same_nodes_as_the_ast() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/snapshot_inputs");
+        for entry in fs::read_dir(dir).unwrap() {
+            let markdown = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let arena = Arena::from_events(EventIter::new(&markdown));
+            assert_eq!(arena.into_nodes(), parser::parse(&markdown).unwrap());
+        }
+    }
+
+    #[test]
+    fn leave_out_elements_never_ended() {
+        let events = [
+            Event::Text("before".into()),
+            Event::Start(Tag::Paragraph {
+                span: Default::default(),
+            }),
+            Event::Text("never ended".into()),
+        ];
+        let arena = Arena::from_events(events);
+        assert_eq!(arena.roots(), [ArenaNode::Text("before".into())]);
+    }
+}
@@ -0,0 +1,755 @@
+//! Stable JSON representation of the AST, meant for tools outside of Rust.
+//!
+//! The document is an object holding the schema `version` and its top-level
+//! `children`. Every node is an object with a `type` tag, and elements
+//! carry their `span` along with their `children`:
+//!
+//! ```json
+//! {"version":1,"children":[
+//!   {"type":"paragraph",
+//!    "span":{"start":{"line":1,"col":5,"start":0,"end":5},"end":{"line":1,"col":5,"start":0,"end":5}},
+//!    "children":[
+//!     {"type":"text","value":"Hello"}
+//!   ]}
+//! ]}
+//! ```
+//!
+//! | `type`         | Fields besides `type`                                  |
+//! |----------------|--------------------------------------------------------|
+//! | `header`       | `level`, `span`, `children`                            |
+//! | `paragraph`    | `span`, `children`                                     |
+//! | `code_block`   | `language`, `attrs`, `span`, `children`                |
+//! | `block_quote`  | `span`, `children`                                     |
+//! | `list`         | `ordered`, `start`, `span`, `children`                 |
+//! | `list_item`    | `span`, `children`                                     |
+//! | `link`         | `url`, `title`, `span`, `children`                     |
+//! | `image`        | `url`, `title`, `span`, `children` (the alt text)      |
+//! | `bold`         | `span`, `children`                                     |
+//! | `italic`       | `span`, `children`                                     |
+//! | `text`         | `value`                                                |
+//! | `digit`        | `value`                                                |
+//! | `comment`      | `value`                                                |
+//! | `line_break`   |                                                        |
+//! | `thematic_break` |                                                      |
+//!
+//! Optional fields are `null` when missing. `url` is a list of nodes,
+//! and so is `title` when there is one. Spans hold the positions of the
+//! first and last source tokens of the element: the `line` and `col` where
+//! each token ends, and the `start` and `end` byte offsets of the token.
+//! Byte offsets were added after the first version, so readers take them
+//! as `0` when they are missing.
+//!
+//! Fields may be added to nodes without bumping [`JSON_VERSION`],
+//! so readers should ignore the ones they don't know about.
+//! Renaming or removing fields, or changing their meaning, bumps the version.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use super::{
+    BlockQuote, Bold, CodeBlock, Header, Image, Italic, Link, List, ListItem, Node, Paragraph,
+};
+use crate::token::Span;
+
+/// Version of the JSON schema written by [`to_json`]
+pub const JSON_VERSION: u64 = 1;
+
+/// Reasons why [`from_json`] couldn't read a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// The input isn't valid JSON
+    Syntax { offset: usize },
+    /// The document was written with a schema version this crate doesn't know about
+    UnsupportedVersion(u64),
+    /// The JSON is valid, but doesn't follow the schema
+    Schema(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { offset } => write!(f, "invalid JSON at byte {}", offset),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported AST schema version {}", version)
+            }
+            Self::Schema(message) => write!(f, "invalid AST: {}", message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {}
+
+/// Writes the given AST as a JSON document following the stable schema
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast, parser};
+/// let ast = parser::parse("*Gohan*").unwrap();
+/// let json = ast::to_json(&ast);
+/// assert!(json.starts_with(r#"{"version":1,"children":[{"type":"paragraph""#));
+/// assert_eq!(ast::from_json(&json).unwrap(), ast);
+/// ```
+pub fn to_json(ast: &[Node]) -> String {
+    let mut json = format!(r#"{{"version":{},"children":"#, JSON_VERSION);
+    write_nodes(&mut json, ast);
+    json.push('}');
+    json
+}
+
+/// Reads an AST back from a JSON document following the stable schema
+pub fn from_json(json: &str) -> Result<Vec<Node<'static>>, JsonError> {
+    let mut reader = Reader { json, offset: 0 };
+    let document = reader.value()?;
+    reader.whitespace();
+    if reader.offset != json.len() {
+        return Err(JsonError::Syntax {
+            offset: reader.offset,
+        });
+    }
+
+    let version = field(&document, "version")?
+        .as_number()
+        .ok_or_else(|| schema("`version` must be a number"))?;
+    if version != JSON_VERSION {
+        return Err(JsonError::UnsupportedVersion(version));
+    }
+    read_nodes(field(&document, "children")?)
+}
+
+fn write_nodes(json: &mut String, nodes: &[Node]) {
+    json.push('[');
+    for (idx, node) in nodes.iter().enumerate() {
+        if idx > 0 {
+            json.push(',');
+        }
+        write_node(json, node);
+    }
+    json.push(']');
+}
+
+fn write_node(json: &mut String, node: &Node) {
+    let (kind, span, children) = match node {
+        Node::Header(header) => ("header", &header.span, &header.children),
+        Node::Paragraph(paragraph) => ("paragraph", &paragraph.span, &paragraph.children),
+        Node::CodeBlock(code_block) => ("code_block", &code_block.span, &code_block.code),
+        Node::BlockQuote(quote) => ("block_quote", &quote.span, &quote.children),
+        Node::List(list) => ("list", &list.span, &list.children),
+        Node::ListItem(item) => ("list_item", &item.span, &item.children),
+        Node::Link(link) => ("link", &link.span, &link.children),
+        Node::Image(image) => ("image", &image.span, &image.alt),
+        Node::Bold(bold) => ("bold", &bold.span, &bold.children),
+        Node::Italic(italic) => ("italic", &italic.span, &italic.children),
+        Node::Text(value) => return write_leaf(json, "text", Some(value)),
+        Node::Digit(value) => return write_leaf(json, "digit", Some(value)),
+        Node::Comment(value) => return write_leaf(json, "comment", Some(value)),
+        Node::LineBreak => return write_leaf(json, "line_break", None),
+        Node::ThematicBreak => return write_leaf(json, "thematic_break", None),
+    };
+
+    write_kind(json, kind);
+    match node {
+        Node::Header(header) => json.push_str(&format!(r#","level":{}"#, header.level)),
+        Node::CodeBlock(code_block) => {
+            json.push_str(r#","language":"#);
+            write_optional_string(json, code_block.language.as_deref());
+            json.push_str(r#","attrs":"#);
+            write_optional_string(json, code_block.attrs.as_deref());
+        }
+        Node::List(list) => {
+            json.push_str(&format!(r#","ordered":{},"start":"#, list.ordered));
+            match list.start {
+                Some(start) => json.push_str(&start.to_string()),
+                None => json.push_str("null"),
+            }
+        }
+        Node::Link(link) => write_destination(json, &link.url, &link.title),
+        Node::Image(image) => write_destination(json, &image.url, &image.title),
+        _ => {}
+    }
+    json.push_str(r#","span":"#);
+    write_span(json, span);
+    json.push_str(r#","children":"#);
+    write_nodes(json, children);
+    json.push('}');
+}
+
+fn write_kind(json: &mut String, kind: &str) {
+    json.push_str(r#"{"type":"#);
+    write_string(json, kind);
+}
+
+fn write_leaf(json: &mut String, kind: &str, value: Option<&str>) {
+    write_kind(json, kind);
+    if let Some(value) = value {
+        json.push_str(r#","value":"#);
+        write_string(json, value);
+    }
+    json.push('}');
+}
+
+fn write_destination(json: &mut String, url: &[Node], title: &Option<Vec<Node>>) {
+    json.push_str(r#","url":"#);
+    write_nodes(json, url);
+    json.push_str(r#","title":"#);
+    match title {
+        Some(title) => write_nodes(json, title),
+        None => json.push_str("null"),
+    }
+}
+
+fn write_span(json: &mut String, span: &Range<Span>) {
+    json.push_str(r#"{"start":"#);
+    write_position(json, &span.start);
+    json.push_str(r#","end":"#);
+    write_position(json, &span.end);
+    json.push('}');
+}
+
+fn write_position(json: &mut String, position: &Span) {
+    json.push_str(&format!(
+        r#"{{"line":{},"col":{},"start":{},"end":{}}}"#,
+        position.line, position.col, position.start, position.end
+    ));
+}
+
+fn write_optional_string(json: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => write_string(json, value),
+        None => json.push_str("null"),
+    }
+}
+
+fn write_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+fn read_nodes(value: &Value) -> Result<Vec<Node<'static>>, JsonError> {
+    match value {
+        Value::Array(values) => values.iter().map(read_node).collect(),
+        _ => Err(schema("nodes must be listed in an array")),
+    }
+}
+
+fn read_node(value: &Value) -> Result<Node<'static>, JsonError> {
+    let kind = field(value, "type")?
+        .as_str()
+        .ok_or_else(|| schema("`type` must be a string"))?;
+    let node = match kind {
+        "header" => Node::Header(Header {
+            level: field(value, "level")?
+                .as_number()
+                .and_then(|level| u8::try_from(level).ok())
+                .ok_or_else(|| schema("`level` must be a small number"))?,
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "paragraph" => Node::Paragraph(Paragraph {
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "code_block" => Node::CodeBlock(CodeBlock {
+            language: optional_string(value, "language")?,
+            attrs: optional_string(value, "attrs")?,
+            code: children(value)?,
+            span: span(value)?,
+        }),
+        "block_quote" => Node::BlockQuote(BlockQuote {
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "list" => Node::List(List {
+            ordered: match field(value, "ordered")? {
+                Value::Bool(ordered) => *ordered,
+                _ => return Err(schema("`ordered` must be a boolean")),
+            },
+            start: match field(value, "start")? {
+                Value::Null => None,
+                start => Some(
+                    start
+                        .as_number()
+                        .and_then(|start| u32::try_from(start).ok())
+                        .ok_or_else(|| schema("`start` must be a number"))?,
+                ),
+            },
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "list_item" => Node::ListItem(ListItem {
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "link" => Node::Link(Link {
+            children: children(value)?,
+            url: read_nodes(field(value, "url")?)?,
+            title: title(value)?,
+            span: span(value)?,
+        }),
+        "image" => Node::Image(Image {
+            alt: children(value)?,
+            url: read_nodes(field(value, "url")?)?,
+            title: title(value)?,
+            span: span(value)?,
+        }),
+        "bold" => Node::Bold(Bold {
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "italic" => Node::Italic(Italic {
+            children: children(value)?,
+            span: span(value)?,
+        }),
+        "text" => Node::Text(Cow::Owned(string(value, "value")?)),
+        "digit" => Node::Digit(Cow::Owned(string(value, "value")?)),
+        "comment" => Node::Comment(Cow::Owned(string(value, "value")?)),
+        "line_break" => Node::LineBreak,
+        "thematic_break" => Node::ThematicBreak,
+        kind => return Err(schema(&format!("unknown node type `{}`", kind))),
+    };
+    Ok(node)
+}
+
+fn children(value: &Value) -> Result<Vec<Node<'static>>, JsonError> {
+    read_nodes(field(value, "children")?)
+}
+
+fn title(value: &Value) -> Result<Option<Vec<Node<'static>>>, JsonError> {
+    match field(value, "title")? {
+        Value::Null => Ok(None),
+        title => read_nodes(title).map(Some),
+    }
+}
+
+fn span(value: &Value) -> Result<Range<Span>, JsonError> {
+    let span = field(value, "span")?;
+    Ok(position(field(span, "start")?)?..position(field(span, "end")?)?)
+}
+
+fn position(value: &Value) -> Result<Span, JsonError> {
+    let number = |name| {
+        field(value, name)?
+            .as_number()
+            .and_then(|number| usize::try_from(number).ok())
+            .ok_or_else(|| schema(&format!("`{}` must be a number", name)))
+    };
+    let offset = |name| match field(value, name) {
+        Ok(_) => number(name),
+        Err(_) => Ok(0),
+    };
+    Ok(Span {
+        line: number("line")?,
+        col: number("col")?,
+        start: offset("start")?,
+        end: offset("end")?,
+        tab: false,
+    })
+}
+
+fn string(value: &Value, name: &str) -> Result<String, JsonError> {
+    field(value, name)?
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| schema(&format!("`{}` must be a string", name)))
+}
+
+fn optional_string(value: &Value, name: &str) -> Result<Option<String>, JsonError> {
+    match field(value, name)? {
+        Value::Null => Ok(None),
+        _ => string(value, name).map(Some),
+    }
+}
+
+fn field<'v>(value: &'v Value, name: &str) -> Result<&'v Value, JsonError> {
+    let Value::Object(fields) = value else {
+        return Err(schema("nodes must be objects"));
+    };
+    fields
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| schema(&format!("missing field `{}`", name)))
+}
+
+fn schema(message: &str) -> JsonError {
+    JsonError::Schema(message.to_string())
+}
+
+/// JSON values, as far as the schema needs them
+enum Value {
+    Null,
+    Bool(bool),
+    /// Numbers the schema never uses, like negative or fractional ones,
+    /// are still read but kept as `None`
+    Number(Option<u64>),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn as_number(&self) -> Option<u64> {
+        match self {
+            Value::Number(number) => *number,
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+/// Nested arrays and objects deeper than this are rejected,
+/// so hostile input can't overflow the stack
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    json: &'a str,
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.nested_value(0)
+    }
+
+    fn nested_value(&mut self, depth: usize) -> Result<Value, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error());
+        }
+        self.whitespace();
+        match self.peek().ok_or_else(|| self.error())? {
+            b'n' => self.keyword("null", Value::Null),
+            b't' => self.keyword("true", Value::Bool(true)),
+            b'f' => self.keyword("false", Value::Bool(false)),
+            b'"' => self.string().map(Value::String),
+            b'-' | b'0'..=b'9' => self.number(),
+            b'[' => {
+                self.offset += 1;
+                let mut values = Vec::new();
+                if !self.close(b']') {
+                    loop {
+                        values.push(self.nested_value(depth + 1)?);
+                        if self.close(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            b'{' => {
+                self.offset += 1;
+                let mut fields = Vec::new();
+                if !self.close(b'}') {
+                    loop {
+                        self.whitespace();
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        fields.push((key, self.nested_value(depth + 1)?));
+                        if self.close(b'}') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Value::Object(fields))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, JsonError> {
+        if !self.json[self.offset..].starts_with(keyword) {
+            return Err(self.error());
+        }
+        self.offset += keyword.len();
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.offset;
+        let negative = self.peek() == Some(b'-');
+        if negative {
+            self.offset += 1;
+        }
+        let integer = self.digits()?;
+        // Leading zeros aren't allowed, besides the one of a number below 1
+        if integer > 1 && self.json.as_bytes()[self.offset - integer] == b'0' {
+            return Err(JsonError::Syntax {
+                offset: self.offset - integer,
+            });
+        }
+        let integer_end = self.offset;
+        if self.peek() == Some(b'.') {
+            self.offset += 1;
+            self.digits()?;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.offset += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.offset += 1;
+            }
+            self.digits()?;
+        }
+
+        let number = match !negative && self.offset == integer_end {
+            true => self.json[start..integer_end].parse().ok(),
+            false => None,
+        };
+        Ok(Value::Number(number))
+    }
+
+    /// Consumes a run of at least one digit, returning its length
+    fn digits(&mut self) -> Result<usize, JsonError> {
+        let length = self.json[self.offset..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if length == 0 {
+            return Err(self.error());
+        }
+        self.offset += length;
+        Ok(length)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut string = String::new();
+        loop {
+            let rest = &self.json[self.offset..];
+            let end = rest
+                .find(|c: char| c == '"' || c == '\\' || c < ' ')
+                .ok_or_else(|| self.error())?;
+            string.push_str(&rest[..end]);
+            self.offset += end;
+            match self.peek() {
+                Some(b'"') => {
+                    self.offset += 1;
+                    return Ok(string);
+                }
+                Some(b'\\') => {
+                    self.offset += 1;
+                    string.push(self.escape()?);
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// Character of the escape sequence following a backslash
+    fn escape(&mut self) -> Result<char, JsonError> {
+        let escape = self.peek().ok_or_else(|| self.error())?;
+        self.offset += 1;
+        let c = match escape {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{08}',
+            b'f' => '\u{0c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.hex()?;
+                // Characters outside the basic plane are written as surrogate pairs
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    if !self.json[self.offset..].starts_with("\\u") {
+                        return Err(self.error());
+                    }
+                    self.offset += 2;
+                    let low = self.hex()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(self.error());
+                    }
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error())?
+            }
+            _ => return Err(self.error()),
+        };
+        Ok(c)
+    }
+
+    fn hex(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .json
+            .get(self.offset..self.offset + 4)
+            .ok_or_else(|| self.error())?;
+        // `from_str_radix` would also take a leading `+`
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(self.error());
+        }
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error())?;
+        self.offset += 4;
+        Ok(code)
+    }
+
+    /// Consumes the given closing bracket when it comes next
+    fn close(&mut self, bracket: u8) -> bool {
+        self.whitespace();
+        let closes = self.peek() == Some(bracket);
+        if closes {
+            self.offset += 1;
+        }
+        closes
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        self.whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error());
+        }
+        self.offset += 1;
+        Ok(())
+    }
+
+    fn whitespace(&mut self) {
+        let rest = &self.json[self.offset..];
+        self.offset += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.offset).copied()
+    }
+
+    fn error(&self) -> JsonError {
+        JsonError::Syntax {
+            offset: self.offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::parser;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_json() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/snapshot_inputs");
+        let mut checked = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|extension| extension == "md") {
+                let markdown = fs::read_to_string(&path).unwrap();
+                let ast = parser::parse(&markdown).unwrap();
+                assert_eq!(
+                    from_json(&to_json(&ast)).unwrap(),
+                    ast,
+                    "input: {}",
+                    path.display()
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0, "no markdown found in {}", dir);
+    }
+
+    #[test]
+    fn write_stable_schema() {
+        let ast = parser::parse("# A\n\n[b](/c \"d\")").unwrap();
+        assert_eq!(
+            to_json(&ast),
+            concat!(
+                r#"{"version":1,"children":["#,
+                r#"{"type":"header","level":1,"span":{"start":{"line":1,"col":1,"start":0,"end":1},"end":{"line":1,"col":3,"start":2,"end":3}},"children":[{"type":"text","value":"A"}]},"#,
+                r#"{"type":"paragraph","span":{"start":{"line":3,"col":1,"start":5,"end":6},"end":{"line":3,"col":11,"start":15,"end":16}},"children":["#,
+                r#"{"type":"link","url":[{"type":"text","value":"/c"}],"title":[{"type":"text","value":"d"}],"span":{"start":{"line":3,"col":1,"start":5,"end":6},"end":{"line":3,"col":11,"start":15,"end":16}},"children":[{"type":"text","value":"b"}]}"#,
+                r#"]}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn read_json_from_other_tools() {
+        let json = r#"
+            {
+                "version": 1,
+                "generator": "unknown fields are ignored",
+                "weight": -1,
+                "ratio": 1.5e-3,
+                "big": 123456789012345678901234567890,
+                "children": [
+                    {
+                        "type": "paragraph",
+                        "span": {"start": {"line": 1, "col": 1}, "end": {"line": 1, "col": 3}},
+                        "children": [{"type": "text", "value": "café \"🍜\"\n"}]
+                    }
+                ]
+            }
+        "#;
+        let ast = from_json(json).unwrap();
+        let Node::Paragraph(paragraph) = &ast[0] else {
+            panic!("expected a paragraph, got {:?}", ast[0]);
+        };
+        assert_eq!(paragraph.children, [Node::Text("café \"🍜\"\n".into())]);
+    }
+
+    #[test]
+    fn reject_invalid_json() {
+        let cases = [
+            ("", JsonError::Syntax { offset: 0 }),
+            (
+                r#"{"version":1,"children":[]"#,
+                JsonError::Syntax { offset: 26 },
+            ),
+            (
+                r#"{"version":1,"children":[]} x"#,
+                JsonError::Syntax { offset: 28 },
+            ),
+            (
+                r#"{"version":2,"children":[]}"#,
+                JsonError::UnsupportedVersion(2),
+            ),
+            (
+                r#"{"children":[]}"#,
+                JsonError::Schema("missing field `version`".to_string()),
+            ),
+            (
+                r#"{"version":1,"children":[{"type":"table"}]}"#,
+                JsonError::Schema("unknown node type `table`".to_string()),
+            ),
+            (
+                r#"{"version":1.0,"children":[]}"#,
+                JsonError::Schema("`version` must be a number".to_string()),
+            ),
+            (r#"{"version":01}"#, JsonError::Syntax { offset: 11 }),
+            (r#"{"version":-}"#, JsonError::Syntax { offset: 12 }),
+            (r#"{"version":1.}"#, JsonError::Syntax { offset: 13 }),
+            (r#"["\u+041"]"#, JsonError::Syntax { offset: 4 }),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(from_json(json), Err(expected), "input: {}", json);
+        }
+
+        let nested = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        assert!(matches!(from_json(&nested), Err(JsonError::Syntax { .. })));
+    }
+}
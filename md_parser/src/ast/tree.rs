@@ -0,0 +1,219 @@
+//! Indented tree view of the AST, easier to scan than its JSON
+//! when debugging the parser.
+
+use alloc::string::String;
+use core::{fmt, slice};
+
+use super::{collect_text, Node};
+
+/// Displays nodes as an indented tree, one node per line with
+/// its children below it. Spans are left out.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast, parser};
+/// let ast = parser::parse("## Title\n\nSome **bold**").unwrap();
+/// assert_eq!(
+///     ast::debug_tree(&ast).to_string(),
+///     concat!(
+///         "Header(2)\n",
+///         "└─ Text(\"Title\")\n",
+///         "Paragraph\n",
+///         "├─ Text(\"Some \")\n",
+///         "└─ Bold\n",
+///         "   └─ Text(\"bold\")\n",
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DebugTree<'a, 's>(&'a [Node<'s>]);
+
+/// Tree view of the given AST, see [`DebugTree`]
+pub fn debug_tree<'a, 's>(ast: &'a [Node<'s>]) -> DebugTree<'a, 's> {
+    DebugTree(ast)
+}
+
+impl<'s> Node<'s> {
+    /// Tree view of this node and its children, see [`DebugTree`]
+    pub fn debug_tree(&self) -> DebugTree<'_, 's> {
+        DebugTree(slice::from_ref(self))
+    }
+}
+
+impl fmt::Display for DebugTree<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in self.0.iter() {
+            write_node(f, node, &mut String::new(), "")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the node after the branch linking it to its parent, then
+/// its children, indented by the prefix of the branches above them.
+fn write_node(
+    f: &mut fmt::Formatter<'_>,
+    node: &Node,
+    prefix: &mut String,
+    branch: &str,
+) -> fmt::Result {
+    f.write_str(prefix)?;
+    f.write_str(branch)?;
+    write_label(f, node)?;
+    f.write_str("\n")?;
+
+    let children = match node {
+        Node::Header(header) => &header.children,
+        Node::Paragraph(paragraph) => &paragraph.children,
+        Node::CodeBlock(code_block) => &code_block.code,
+        Node::BlockQuote(quote) => &quote.children,
+        Node::List(list) => &list.children,
+        Node::ListItem(item) => &item.children,
+        Node::Link(link) => &link.children,
+        Node::Image(image) => &image.alt,
+        Node::Bold(bold) => &bold.children,
+        Node::Italic(italic) => &italic.children,
+        _ => return Ok(()),
+    };
+    let length = prefix.len();
+    prefix.push_str(match branch {
+        "├─ " => "│  ",
+        "└─ " => "   ",
+        _ => "",
+    });
+    for (idx, child) in children.iter().enumerate() {
+        let last = idx + 1 == children.len();
+        write_node(f, child, prefix, if last { "└─ " } else { "├─ " })?;
+    }
+    prefix.truncate(length);
+    Ok(())
+}
+
+/// Kind of the node, along with its attributes
+fn write_label(f: &mut fmt::Formatter<'_>, node: &Node) -> fmt::Result {
+    match node {
+        Node::Header(header) => write!(f, "Header({})", header.level),
+        Node::Paragraph(_) => f.write_str("Paragraph"),
+        Node::CodeBlock(code_block) => match &code_block.language {
+            Some(language) => write!(f, "CodeBlock({:?})", language),
+            None => f.write_str("CodeBlock"),
+        },
+        Node::BlockQuote(_) => f.write_str("BlockQuote"),
+        Node::List(list) => match (list.ordered, list.start) {
+            (true, Some(start)) => write!(f, "List(ordered, start: {})", start),
+            (true, None) => f.write_str("List(ordered)"),
+            (false, _) => f.write_str("List(bullet)"),
+        },
+        Node::ListItem(_) => f.write_str("ListItem"),
+        Node::ThematicBreak => f.write_str("ThematicBreak"),
+        Node::Link(link) => write_destination(f, "Link", &link.url, link.title.as_deref()),
+        Node::Image(image) => write_destination(f, "Image", &image.url, image.title.as_deref()),
+        Node::Bold(_) => f.write_str("Bold"),
+        Node::Italic(_) => f.write_str("Italic"),
+        Node::Digit(digit) => write!(f, "Digit({:?})", digit),
+        Node::Text(text) => write!(f, "Text({:?})", text),
+        Node::LineBreak => f.write_str("LineBreak"),
+        Node::Comment(comment) => write!(f, "Comment({:?})", comment),
+    }
+}
+
+fn write_destination(
+    f: &mut fmt::Formatter<'_>,
+    kind: &str,
+    url: &[Node],
+    title: Option<&[Node]>,
+) -> fmt::Result {
+    let mut text = String::new();
+    collect_text(&mut text, url);
+    write!(f, "{}({:?}", kind, text)?;
+    if let Some(title) = title {
+        text.clear();
+        collect_text(&mut text, title);
+        write!(f, ", title: {:?}", text)?;
+    }
+    f.write_str(")")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{BlockQuote, CodeBlock, Italic, Link, List, ListItem, Paragraph};
+
+    use super::*;
+
+    #[test]
+    fn print_nested_nodes() {
+        let span = Default::default()..Default::default();
+        let paragraph = |children| {
+            Node::Paragraph(Paragraph {
+                children,
+                span: span.clone(),
+            })
+        };
+        let ast = vec![
+            Node::BlockQuote(BlockQuote {
+                children: vec![Node::List(List {
+                    ordered: false,
+                    start: None,
+                    children: vec![
+                        Node::ListItem(ListItem {
+                            children: vec![paragraph(vec![
+                                Node::Link(Link {
+                                    children: vec![
+                                        Node::Text("a ".into()),
+                                        Node::Italic(Italic {
+                                            children: vec![Node::Text("b".into())],
+                                            span: span.clone(),
+                                        }),
+                                    ],
+                                    url: vec![Node::Text("/a".into())],
+                                    title: Some(vec![Node::Text("T".into())]),
+                                    span: span.clone(),
+                                }),
+                                Node::LineBreak,
+                                Node::Digit("1".into()),
+                            ])],
+                            span: span.clone(),
+                        }),
+                        Node::ListItem(ListItem {
+                            children: vec![paragraph(vec![Node::Text("c".into())])],
+                            span: span.clone(),
+                        }),
+                    ],
+                    span: span.clone(),
+                })],
+                span: span.clone(),
+            }),
+            Node::CodeBlock(CodeBlock {
+                language: Some("rust".to_string()),
+                attrs: None,
+                code: vec![Node::Text("fn\n".into())],
+                span: span.clone(),
+            }),
+        ];
+        assert_eq!(
+            debug_tree(&ast).to_string(),
+            concat!(
+                "BlockQuote\n",
+                "└─ List(bullet)\n",
+                "   ├─ ListItem\n",
+                "   │  └─ Paragraph\n",
+                "   │     ├─ Link(\"/a\", title: \"T\")\n",
+                "   │     │  ├─ Text(\"a \")\n",
+                "   │     │  └─ Italic\n",
+                "   │     │     └─ Text(\"b\")\n",
+                "   │     ├─ LineBreak\n",
+                "   │     └─ Digit(\"1\")\n",
+                "   └─ ListItem\n",
+                "      └─ Paragraph\n",
+                "         └─ Text(\"c\")\n",
+                "CodeBlock(\"rust\")\n",
+                "└─ Text(\"fn\\n\")\n",
+            )
+        );
+        assert_eq!(
+            ast[1].debug_tree().to_string(),
+            "CodeBlock(\"rust\")\n└─ Text(\"fn\\n\")\n"
+        );
+    }
+}
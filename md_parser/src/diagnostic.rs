@@ -0,0 +1,43 @@
+use alloc::string::String;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::token::Span;
+
+/// How serious the problem reported by a diagnostic or a lint is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Severity {
+    /// Part of the document couldn't be parsed and was kept as plain text,
+    /// or the document breaks a lint rule set to fail
+    Error,
+    /// The document was parsed, but likely not the way the author intended
+    Warning,
+}
+
+/// Problem found in the markdown source while parsing it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub severity: Severity,
+    /// Positions of the first and last tokens involved
+    pub span: Range<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Range<Span>) -> Self {
+        Self {
+            message: message.into(),
+            severity: Severity::Error,
+            span,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Range<Span>) -> Self {
+        Self {
+            message: message.into(),
+            severity: Severity::Warning,
+            span,
+        }
+    }
+}
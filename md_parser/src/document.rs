@@ -0,0 +1,475 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    ast::Node,
+    diagnostic::Diagnostic,
+    lexer::{indentation_table, Lexer},
+    parser::{ParseError, Parser, ParserOptions},
+    token::{Span, Token},
+};
+
+/// Markdown document kept parsed while it's being edited.
+///
+/// Edits only re-parse the blocks around them. The blocks after an edit
+/// are reused as soon as the re-parsed blocks line up with them again.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{document::Document, renderer};
+/// let mut document = Document::new("# Gohan\n\nSome text");
+/// document.apply_edit(14..18, "*markdown*");
+/// assert_eq!(document.text(), "# Gohan\n\nSome *markdown*");
+/// assert_eq!(
+///     renderer::render_ast(document.ast(), &Default::default()),
+///     "<h1>Gohan</h1><p>Some <em>markdown</em></p>"
+/// );
+/// ```
+pub struct Document {
+    text: String,
+    options: ParserOptions,
+    /// Top-level blocks of the document
+    nodes: Vec<Node<'static>>,
+    /// Where each of the top-level blocks starts, along with what was found while parsing it
+    blocks: Vec<Block>,
+}
+
+struct Block {
+    /// Byte offset where parsing the block started,
+    /// including any blank lines skipped before it
+    start: usize,
+    /// Line of the byte at `start`
+    line: usize,
+    /// Whether parsing can be resumed at `start` without the text before it,
+    /// which is only the case at the start of a line
+    resumable: bool,
+    /// Whether the block depends on text up to the end of the document,
+    /// like an unterminated comment opener which a later `-->` would close
+    reads_to_end: bool,
+    error: Option<ParseError>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Document {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self::with_options(text, ParserOptions::default())
+    }
+
+    pub fn with_options(text: impl Into<String>, options: ParserOptions) -> Self {
+        let mut document = Self {
+            text: text.into(),
+            options,
+            nodes: Vec::new(),
+            blocks: Vec::new(),
+        };
+        document.reparse_from(0, None);
+        document
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Top-level nodes of the document, as [`parser::parse`](crate::parser::parse) would return them
+    pub fn ast(&self) -> &[Node<'static>] {
+        &self.nodes
+    }
+
+    /// First error found while parsing the document
+    pub fn error(&self) -> Option<&ParseError> {
+        self.blocks.iter().find_map(|block| block.error.as_ref())
+    }
+
+    /// Problems found while parsing the document, in source order
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.diagnostics.iter())
+    }
+
+    /// Replaces the text within the given byte range, re-parsing the blocks it touches.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the range is out of bounds or doesn't lie on UTF-8 character boundaries,
+    /// like [`String::replace_range`].
+    pub fn apply_edit(&mut self, range: Range<usize>, replacement: &str) {
+        self.text.replace_range(range.clone(), replacement);
+
+        // A block with an unterminated comment depends on all the text after it,
+        // so parsing has to start over from there
+        let changed = self
+            .blocks
+            .iter()
+            .take_while(|block| block.start < range.start)
+            .find(|block| block.reads_to_end)
+            .map_or(range.start, |block| block.start);
+        // Blocks before the changed one are parsed again as well
+        // when the parser may have looked at the changed text while parsing them,
+        // e.g. when a heading becomes text continuing the paragraph before it
+        let mut first = self
+            .blocks
+            .partition_point(|block| block.start <= changed)
+            .saturating_sub(1);
+        while first > 0
+            && !(self.blocks[first].resumable
+                && lookahead_end(&self.text[..changed], self.blocks[first].start) < changed)
+        {
+            first -= 1;
+        }
+
+        let reusable = Reusable {
+            old_end: range.end,
+            length_change: replacement.len() as isize - range.len() as isize,
+        };
+        self.reparse_from(first, Some(reusable));
+    }
+
+    /// Parses the blocks from the one at the given index onwards,
+    /// stopping once the following blocks can be reused
+    fn reparse_from(&mut self, first: usize, reusable: Option<Reusable>) {
+        let (start, line) = match self.blocks.get(first) {
+            Some(block) => (block.start, block.line),
+            None => (0, 1),
+        };
+        let mut old_nodes = self.nodes.split_off(first).into_iter();
+        let mut old_blocks = self.blocks.split_off(first).into_iter();
+
+        let source = &self.text[start..];
+        let tokens = Lexer::new(source).into_tokens();
+        let indentation = indentation_table(&tokens);
+        let mut position = 0;
+        // The parser may step past the end of file token
+        while position < tokens.len() {
+            let block_start = start + tokens[position].1.start;
+            let resumable = is_line_start(&tokens, position);
+            // Tokens are given the line where they end, counted from the start of the parsed text.
+            // Counting the lines of the text instead would disagree with the lexer,
+            // which doesn't treat a lone `\r` within a comment as a line break.
+            let block_line = match position {
+                0 => line,
+                _ => line + tokens[position - 1].1.line - 1,
+            };
+
+            if let Some(reusable) = &reusable {
+                if resumable && block_start >= reusable.new_end() {
+                    // Old blocks starting before the edit can never line up
+                    let old_start = block_start as isize - reusable.length_change;
+                    let skipped = old_blocks
+                        .as_slice()
+                        .iter()
+                        .take_while(|block| (block.start as isize) < old_start)
+                        .count();
+                    for _ in 0..skipped {
+                        old_blocks.next();
+                        old_nodes.next();
+                    }
+
+                    let line_change = old_blocks
+                        .as_slice()
+                        .first()
+                        .filter(|block| block.resumable && block.start as isize == old_start)
+                        .map(|block| block_line as isize - block.line as isize);
+                    if let Some(line_change) = line_change {
+                        for mut block in old_blocks {
+                            block.start = block.start.wrapping_add_signed(reusable.length_change);
+                            block.line = block.line.wrapping_add_signed(line_change);
+                            shift_problems(&mut block, line_change, reusable.length_change);
+                            self.blocks.push(block);
+                        }
+                        for mut node in old_nodes {
+                            shift_node(&mut node, line_change, reusable.length_change);
+                            self.nodes.push(node);
+                        }
+                        return;
+                    }
+                }
+            }
+
+            let mut parser = Parser::resume(
+                source,
+                &tokens,
+                &indentation,
+                position,
+                self.options.clone(),
+            );
+            let Some(node) = parser.next_block() else {
+                return;
+            };
+            // Block rules may look at any of the text after the block to tell where it ends
+            let reads_to_end = !self.options.block_rules.is_empty()
+                || (position..parser.position()).any(|idx| {
+                    tokens[idx].0 == Token::LessThan
+                        && source[tokens[idx].1.start..].starts_with("<!--")
+                });
+            position = parser.position();
+
+            let (error, diagnostics) = parser.into_problems();
+            let mut block = Block {
+                start: block_start,
+                line: block_line,
+                resumable,
+                reads_to_end,
+                error,
+                diagnostics,
+            };
+            let mut node = node.into_owned();
+            // Spans are counted from the start of the parsed text
+            shift_problems(&mut block, line as isize - 1, start as isize);
+            shift_node(&mut node, line as isize - 1, start as isize);
+            self.blocks.push(block);
+            self.nodes.push(node);
+        }
+    }
+}
+
+/// Edit after which the old blocks may be reused
+struct Reusable {
+    /// End of the edited range, in the old text
+    old_end: usize,
+    /// How many bytes the edit added, or removed when negative
+    length_change: isize,
+}
+
+impl Reusable {
+    /// End of the replacement, in the new text
+    fn new_end(&self) -> usize {
+        self.old_end.wrapping_add_signed(self.length_change)
+    }
+}
+
+/// Offset up to which the parser may look at the text while parsing
+/// the block ending at the given line start. Block ends are decided by looking
+/// past the blank lines after the block, at the next line with any content,
+/// while inline elements like links may continue until an empty line.
+/// Comments are single tokens, so blank lines within them don't count,
+/// and an unterminated comment reads up to the end of the text.
+fn lookahead_end(text: &str, block_end: usize) -> usize {
+    let mut seen_blank_line = false;
+    // Blocks may end right before the line break of their last line,
+    // whose rest is neither blank nor content after a blank line
+    let mut within_line = block_end > 0 && !text[..block_end].ends_with('\n');
+    let mut line_start = block_end;
+    while let Some(length) = text[line_start..].find('\n') {
+        let mut line_end = line_start + length;
+        let mut cursor = line_start;
+        while let Some(comment) = text[cursor..line_end].find("<!--") {
+            let comment_start = cursor + comment;
+            cursor = match text[comment_start + 4..].find("-->") {
+                Some(length) => comment_start + 4 + length + 3,
+                None => return text.len(),
+            };
+            if cursor > line_end {
+                // The line continues after the comment
+                line_end = match text[cursor..].find('\n') {
+                    Some(length) => cursor + length,
+                    None => return text.len(),
+                };
+            }
+        }
+        let line = &text[line_start..line_end];
+        line_start = line_end + 1;
+        if within_line {
+            within_line = false;
+        } else if line.is_empty() || line == "\r" {
+            seen_blank_line = true;
+        } else if line.trim().is_empty() {
+            // Blank for blocks, but not for inline elements
+        } else if seen_blank_line {
+            return line_start;
+        }
+    }
+    text.len()
+}
+
+/// Whether the token at the given position starts a line,
+/// or ends the line before it
+fn is_line_start(tokens: &[(Token, Span)], position: usize) -> bool {
+    position == 0
+        || tokens[position].0 == Token::Newline
+        || tokens[position - 1].0 == Token::Newline
+}
+
+/// Moves the position by the given number of lines and bytes
+fn shift_position(position: &mut Span, lines: isize, bytes: isize) {
+    position.line = position.line.wrapping_add_signed(lines);
+    position.start = position.start.wrapping_add_signed(bytes);
+    position.end = position.end.wrapping_add_signed(bytes);
+}
+
+fn shift_span(span: &mut Range<Span>, lines: isize, bytes: isize) {
+    shift_position(&mut span.start, lines, bytes);
+    shift_position(&mut span.end, lines, bytes);
+}
+
+fn shift_problems(block: &mut Block, lines: isize, bytes: isize) {
+    if let Some(ParseError::NestingTooDeep { span, .. }) = &mut block.error {
+        shift_position(span, lines, bytes);
+    }
+    for diagnostic in block.diagnostics.iter_mut() {
+        shift_span(&mut diagnostic.span, lines, bytes);
+    }
+}
+
+/// Moves the spans of the node and its children by the given number of lines and bytes
+fn shift_node(node: &mut Node, lines: isize, bytes: isize) {
+    if lines == 0 && bytes == 0 {
+        return;
+    }
+    let span = match node {
+        Node::Header(header) => &mut header.span,
+        Node::Paragraph(paragraph) => &mut paragraph.span,
+        Node::CodeBlock(code_block) => &mut code_block.span,
+        Node::BlockQuote(quote) => &mut quote.span,
+        Node::List(list) => &mut list.span,
+        Node::ListItem(item) => &mut item.span,
+        Node::Link(link) => &mut link.span,
+        Node::Image(image) => &mut image.span,
+        Node::Bold(bold) => &mut bold.span,
+        Node::Italic(italic) => &mut italic.span,
+        Node::ThematicBreak
+        | Node::Digit(_)
+        | Node::Text(_)
+        | Node::LineBreak
+        | Node::Comment(_) => return,
+    };
+    shift_span(span, lines, bytes);
+    if let Some(children) = node.children_mut() {
+        for child in children.iter_mut() {
+            shift_node(child, lines, bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::parser::{self, BlockRule};
+
+    use super::*;
+
+    fn assert_same_as_full_parse(document: &Document) {
+        let (expected, diagnostics) = parser::parse_with_diagnostics(document.text());
+        assert_eq!(document.ast(), expected, "text: {:?}", document.text());
+        assert_eq!(
+            document.diagnostics().cloned().collect::<Vec<_>>(),
+            diagnostics,
+            "text: {:?}",
+            document.text()
+        );
+    }
+
+    #[test]
+    fn reparse_edited_blocks() {
+        let mut document = Document::new("# Title\n\nFirst paragraph\n\n- one\n- two\n");
+        let cases = [
+            // Typing into a paragraph
+            (17..17, " and more"),
+            // A heading becoming text of the paragraph before it
+            (0..0, "Intro\n"),
+            // Splitting a list by a blank line, then joining it back
+            (42..42, "\n"),
+            (42..43, ""),
+            // Opening a code block swallows everything after it
+            (0..0, "```\n"),
+            (0..4, ""),
+            ("# Title".len()..0, ""),
+        ];
+        for (range, replacement) in cases {
+            let range = range.start..range.end.max(range.start);
+            document.apply_edit(range, replacement);
+            assert_same_as_full_parse(&document);
+        }
+    }
+
+    #[test]
+    fn reuse_blocks_after_the_edit() {
+        let mut document = Document::new("First\n\nSecond\n\nThird");
+        let text_of = |document: &Document, idx: usize| match &document.ast()[idx] {
+            Node::Paragraph(paragraph) => match &paragraph.children[0] {
+                Node::Text(text) => text.as_ptr(),
+                node => panic!("expected text, got {:?}", node),
+            },
+            node => panic!("expected a paragraph, got {:?}", node),
+        };
+        let third = text_of(&document, 2);
+
+        document.apply_edit(0..0, "Very\n\n");
+        assert_same_as_full_parse(&document);
+        // The last paragraph is the same one, moved two lines down
+        assert_eq!(text_of(&document, 3), third);
+    }
+
+    #[test]
+    fn reparse_blocks_before_an_edit_with_block_rules() {
+        /// Claims everything from a `start` line up to an `end` as a single thematic break
+        struct UntilEnd;
+
+        impl BlockRule for UntilEnd {
+            fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)> {
+                let (Token::Text("start"), _) = tokens.first()? else {
+                    return None;
+                };
+                let end = tokens.iter().position(|(t, _)| t == &Token::Text("end"))?;
+                Some((Node::ThematicBreak, end + 1))
+            }
+        }
+
+        let options = ParserOptions::default().with_block_rule(UntilEnd);
+        let mut document = Document::with_options("start\n\nText\n\nMore", options.clone());
+        document.apply_edit(document.text().len()..document.text().len(), "\n\nend");
+        let expected = parser::parse_with_options(document.text(), options).unwrap();
+        assert_eq!(document.ast(), expected);
+        assert_eq!(expected, [Node::ThematicBreak]);
+    }
+
+    #[test]
+    fn match_full_parse_after_random_edits() {
+        let snippets = [
+            "\n", "\n\n", "# ", "- ", "1. ", "> ", "```", "    ", "*", "**", "_", "[", "](", ")",
+            "![", "<!--", "-->", "---", "text", " ", "\t", "\r\n", "é", "2",
+        ];
+        let mut document = Document::new("# Gohan\n\nSome *text*\n\n- a\n- b\n\n> quote\n");
+        // Small linear congruential generator, so failures can be reproduced
+        let mut seed: u64 = 0x5eed;
+        let mut random = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % bound.max(1)
+        };
+
+        for _ in 0..2000 {
+            let text = document.text();
+            let boundaries: Vec<usize> = (0..=text.len())
+                .filter(|&idx| text.is_char_boundary(idx))
+                .collect();
+            let start = boundaries[random(boundaries.len())];
+            let end = boundaries
+                .iter()
+                .copied()
+                .filter(|&idx| idx >= start && idx <= start + 8)
+                .nth(random(3))
+                .unwrap_or(start);
+            let replacement = if random(3) == 0 {
+                String::new()
+            } else {
+                snippets[random(snippets.len())].to_string()
+            };
+
+            let before = document.text().to_string();
+            document.apply_edit(start..end, &replacement);
+            let (expected, _) = parser::parse_with_diagnostics(document.text());
+            assert_eq!(
+                document.ast(),
+                expected,
+                "replacing {:?} with {:?} in {:?}",
+                start..end,
+                replacement,
+                before
+            );
+        }
+    }
+}
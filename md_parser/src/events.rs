@@ -0,0 +1,560 @@
+use alloc::{borrow::Cow, string::String, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    ast::{
+        BlockQuote, Bold, CodeBlock, Header, Image, Italic, Link, List, ListItem, Node, Paragraph,
+    },
+    diagnostic::Diagnostic,
+    lexer::{indentation_table, Lexer, Tokens},
+    parser::{ParseError, Parser, ParserOptions},
+    token::{Span, Token},
+};
+
+/// Number of tokens lexed at once at the start of a document,
+/// doubling every time more are needed
+const MIN_LEXED_TOKENS: usize = 256;
+
+/// A single step while walking through a markdown document,
+/// in the same order the elements show up in the source.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event<'s> {
+    /// An element with children starts, followed by the events of its children
+    Start(Tag<'s>),
+    /// The most recently started element ends
+    End(TagEnd),
+    Text(Cow<'s, str>),
+    Digit(Cow<'s, str>),
+    LineBreak,
+    ThematicBreak,
+    /// Content of an HTML comment, without its `<!--` and `-->` delimiters
+    Comment(Cow<'s, str>),
+}
+
+/// Element with children, along with its own attributes
+#[derive(Debug, PartialEq, Eq)]
+pub enum Tag<'s> {
+    Header {
+        level: u8,
+        span: Range<Span>,
+    },
+    Paragraph {
+        span: Range<Span>,
+    },
+    CodeBlock {
+        language: Option<String>,
+        attrs: Option<String>,
+        span: Range<Span>,
+    },
+    BlockQuote {
+        span: Range<Span>,
+    },
+    List {
+        ordered: bool,
+        start: Option<u32>,
+        span: Range<Span>,
+    },
+    ListItem {
+        span: Range<Span>,
+    },
+    /// Children of a link are the events of its text
+    Link {
+        url: Vec<Node<'s>>,
+        title: Option<Vec<Node<'s>>>,
+        span: Range<Span>,
+    },
+    /// Children of an image are the events of its alt text
+    Image {
+        url: Vec<Node<'s>>,
+        title: Option<Vec<Node<'s>>>,
+        span: Range<Span>,
+    },
+    Bold {
+        span: Range<Span>,
+    },
+    Italic {
+        span: Range<Span>,
+    },
+}
+
+/// Kind of element ended by an [`Event::End`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagEnd {
+    Header,
+    Paragraph,
+    CodeBlock,
+    BlockQuote,
+    List,
+    ListItem,
+    Link,
+    Image,
+    Bold,
+    Italic,
+}
+
+/// Lazily yields the events of a markdown document.
+///
+/// Blocks are parsed one at a time, only once the events
+/// of the previous block have all been consumed. The document is lexed
+/// along the way, only as far as the parser may look while parsing a block.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::events::{Event, EventIter, Tag, TagEnd};
+/// let events: Vec<Event> = EventIter::new("# Gohan").collect();
+/// assert!(matches!(events[0], Event::Start(Tag::Header { level: 1, .. })));
+/// assert_eq!(events[1], Event::Text("Gohan".into()));
+/// assert_eq!(events[2], Event::End(TagEnd::Header));
+/// ```
+pub struct EventIter<'a> {
+    /// Whole document, which the text of the events is borrowed from
+    markdown: &'a str,
+    /// Tokens of the document lexed so far,
+    /// followed by an end of file until the whole document is lexed
+    tokens: Vec<(Token<'a>, Span)>,
+    /// Indentation table of the tokens lexed so far, see [`indentation_table`]
+    indentation: Vec<usize>,
+    /// Tokens yet to be lexed, until the end of the document is reached
+    lexer: Option<Tokens<'a>>,
+    /// Token position of the next block to be parsed
+    position: usize,
+    /// Children of the elements started but not yet ended, innermost last
+    open: Vec<(TagEnd, vec::IntoIter<Node<'a>>)>,
+    error: Option<ParseError>,
+    diagnostics: Vec<Diagnostic>,
+    options: ParserOptions,
+}
+
+impl<'a> EventIter<'a> {
+    pub fn new(markdown: &'a str) -> Self {
+        Self::with_options(markdown, ParserOptions::default())
+    }
+
+    pub fn with_options(markdown: &'a str, options: ParserOptions) -> Self {
+        // Block rules can't tell the end of the tokens lexed so far from the end of the document
+        let (tokens, lexer) = match options.block_rules.is_empty() {
+            true => (
+                vec![(Token::EndOfFile, Span::default())],
+                Some(Lexer::new(markdown).iter()),
+            ),
+            false => (Lexer::new(markdown).into_tokens(), None),
+        };
+        Self {
+            markdown,
+            indentation: indentation_table(&tokens),
+            tokens,
+            lexer,
+            position: 0,
+            open: Vec::new(),
+            error: None,
+            diagnostics: Vec::new(),
+            options,
+        }
+    }
+
+    /// First error found in the blocks parsed so far
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+
+    /// Problems found in the blocks parsed so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    fn next_block(&mut self) -> Option<Node<'a>> {
+        // Parsing is only kept when it didn't depend on the end of file
+        // standing in for the tokens not lexed yet
+        let (block, parser) = loop {
+            let mut parser = Parser::resume(
+                self.markdown,
+                &self.tokens,
+                &self.indentation,
+                self.position,
+                self.options.clone(),
+            );
+            let block = parser.next_block();
+            if self.lexer.is_none() || self.is_lexed_past(parser.position()) {
+                break (block, parser);
+            }
+            self.lex_more();
+        };
+        self.position = parser.position();
+
+        let (error, diagnostics) = parser.into_problems();
+        self.error = self.error.take().or(error);
+        self.diagnostics.extend(diagnostics);
+        block
+    }
+
+    /// Whether enough tokens are lexed after the block ending at the given position
+    /// to tell how it ends. Blocks end after the blank lines following them,
+    /// at the next line with any content, while inline elements like links
+    /// may look ahead until an empty line.
+    fn is_lexed_past(&self, block_end: usize) -> bool {
+        // Without the end of file standing in for the tokens not lexed yet
+        let tokens = &self.tokens[..self.tokens.len() - 1];
+        if block_end >= tokens.len() {
+            return false;
+        }
+        let mut seen_blank_line = false;
+        // Blocks may end right before the line break of their last line,
+        // whose rest is neither blank nor content after a blank line
+        let mut within_line = block_end > 0 && tokens[block_end - 1].0 != Token::Newline;
+        let mut line_start = block_end;
+        while let Some(length) = tokens[line_start..]
+            .iter()
+            .position(|(t, _)| t == &Token::Newline)
+        {
+            let line = &tokens[line_start..line_start + length];
+            line_start += length + 1;
+            if within_line {
+                within_line = false;
+            } else if line.is_empty() {
+                seen_blank_line = true;
+            } else if line.iter().all(|(t, _)| t == &Token::Space) {
+                // Blank for blocks, but not for inline elements
+            } else if seen_blank_line {
+                return line_start < tokens.len();
+            }
+        }
+        false
+    }
+
+    /// Lexes as many tokens again as are lexed so far
+    fn lex_more(&mut self) {
+        let Some(lexer) = self.lexer.as_mut() else {
+            return;
+        };
+        self.tokens.pop();
+        let count = self.tokens.len().max(MIN_LEXED_TOKENS);
+        self.tokens.extend(lexer.take(count));
+        match self.tokens.last() {
+            Some((Token::EndOfFile, _)) => self.lexer = None,
+            last => {
+                let span = last.map_or(Span::default(), |(_, span)| *span);
+                self.tokens.push((Token::EndOfFile, span));
+            }
+        }
+        self.indentation = indentation_table(&self.tokens);
+    }
+
+    /// Event for the given node, keeping track of its children when it has any
+    fn enter(&mut self, node: Node<'a>) -> Event<'a> {
+        let (tag, end, children) = match node {
+            Node::Header(header) => (
+                Tag::Header {
+                    level: header.level,
+                    span: header.span,
+                },
+                TagEnd::Header,
+                header.children,
+            ),
+            Node::Paragraph(paragraph) => (
+                Tag::Paragraph {
+                    span: paragraph.span,
+                },
+                TagEnd::Paragraph,
+                paragraph.children,
+            ),
+            Node::CodeBlock(code_block) => (
+                Tag::CodeBlock {
+                    language: code_block.language,
+                    attrs: code_block.attrs,
+                    span: code_block.span,
+                },
+                TagEnd::CodeBlock,
+                code_block.code,
+            ),
+            Node::BlockQuote(quote) => (
+                Tag::BlockQuote { span: quote.span },
+                TagEnd::BlockQuote,
+                quote.children,
+            ),
+            Node::List(list) => (
+                Tag::List {
+                    ordered: list.ordered,
+                    start: list.start,
+                    span: list.span,
+                },
+                TagEnd::List,
+                list.children,
+            ),
+            Node::ListItem(item) => (
+                Tag::ListItem { span: item.span },
+                TagEnd::ListItem,
+                item.children,
+            ),
+            Node::Link(link) => (
+                Tag::Link {
+                    url: link.url,
+                    title: link.title,
+                    span: link.span,
+                },
+                TagEnd::Link,
+                link.children,
+            ),
+            Node::Image(image) => (
+                Tag::Image {
+                    url: image.url,
+                    title: image.title,
+                    span: image.span,
+                },
+                TagEnd::Image,
+                image.alt,
+            ),
+            Node::Bold(bold) => (Tag::Bold { span: bold.span }, TagEnd::Bold, bold.children),
+            Node::Italic(italic) => (
+                Tag::Italic { span: italic.span },
+                TagEnd::Italic,
+                italic.children,
+            ),
+            Node::Text(text) => return Event::Text(text),
+            Node::Digit(digit) => return Event::Digit(digit),
+            Node::LineBreak => return Event::LineBreak,
+            Node::ThematicBreak => return Event::ThematicBreak,
+            Node::Comment(comment) => return Event::Comment(comment),
+        };
+
+        self.open.push((end, children.into_iter()));
+        Event::Start(tag)
+    }
+}
+
+impl<'a> Iterator for EventIter<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = match self.open.last_mut() {
+            Some((end, children)) => match children.next() {
+                Some(child) => child,
+                None => {
+                    let end = *end;
+                    self.open.pop();
+                    return Some(Event::End(end));
+                }
+            },
+            None => self.next_block()?,
+        };
+
+        Some(self.enter(node))
+    }
+}
+
+/// Assembles the AST back from a stream of events
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::events::{self, EventIter};
+/// let ast = events::build_ast(EventIter::new("Some **bold** text"));
+/// assert_eq!(ast.len(), 1);
+/// ```
+pub fn build_ast<'s>(events: impl IntoIterator<Item = Event<'s>>) -> Vec<Node<'s>> {
+    // Nodes can't keep their first few children inline, as they would contain themselves.
+    // Instead, children are collected on a single stack and moved into a list of their
+    // exact size once their element ends, rather than growing a list for every element.
+    let mut nodes = Vec::new();
+    // Elements started but not yet ended, along with where their children start in `nodes`
+    let mut open: Vec<(Tag<'s>, usize)> = Vec::new();
+
+    for event in events {
+        let node = match event {
+            Event::Start(tag) => {
+                open.push((tag, nodes.len()));
+                continue;
+            }
+            // An end without a matching start has nothing to close
+            Event::End(_) => match open.pop() {
+                Some((tag, start)) => element(tag, nodes.drain(start..).collect()),
+                None => continue,
+            },
+            Event::Text(text) => Node::Text(text),
+            Event::Digit(digit) => Node::Digit(digit),
+            Event::LineBreak => Node::LineBreak,
+            Event::ThematicBreak => Node::ThematicBreak,
+            Event::Comment(comment) => Node::Comment(comment),
+        };
+
+        nodes.push(node);
+    }
+
+    // Elements which never ended are left out
+    if let Some((_, start)) = open.first() {
+        nodes.truncate(*start);
+    }
+    nodes
+}
+
+pub(crate) fn element<'s>(tag: Tag<'s>, children: Vec<Node<'s>>) -> Node<'s> {
+    match tag {
+        Tag::Header { level, span } => Node::Header(Header {
+            level,
+            children,
+            span,
+        }),
+        Tag::Paragraph { span } => Node::Paragraph(Paragraph { children, span }),
+        Tag::CodeBlock {
+            language,
+            attrs,
+            span,
+        } => Node::CodeBlock(CodeBlock {
+            language,
+            attrs,
+            code: children,
+            span,
+        }),
+        Tag::BlockQuote { span } => Node::BlockQuote(BlockQuote { children, span }),
+        Tag::List {
+            ordered,
+            start,
+            span,
+        } => Node::List(List {
+            ordered,
+            start,
+            children,
+            span,
+        }),
+        Tag::ListItem { span } => Node::ListItem(ListItem { children, span }),
+        Tag::Link { url, title, span } => Node::Link(Link {
+            children,
+            url,
+            title,
+            span,
+        }),
+        Tag::Image { url, title, span } => Node::Image(Image {
+            alt: children,
+            url,
+            title,
+            span,
+        }),
+        Tag::Bold { span } => Node::Bold(Bold { children, span }),
+        Tag::Italic { span } => Node::Italic(Italic { children, span }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yield_events_in_document_order() {
+        let events: Vec<Event> = EventIter::new("- a *b*\n\n---\n").collect();
+        let kinds: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                Event::Start(tag) => format!("start {:?}", tag_end(tag)),
+                Event::End(end) => format!("end {:?}", end),
+                Event::Text(text) => format!("text {}", text),
+                event => format!("{:?}", event),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "start List",
+                "start ListItem",
+                "start Paragraph",
+                "text a ",
+                "start Italic",
+                "text b",
+                "end Italic",
+                "LineBreak",
+                "end Paragraph",
+                "end ListItem",
+                "end List",
+                "ThematicBreak",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_blocks_lazily() {
+        let mut events = EventIter::new("# Title\n\n```\nnever closed");
+        for event in events.by_ref() {
+            if event == Event::End(TagEnd::Header) {
+                break;
+            }
+        }
+        // The unclosed code block hasn't been parsed yet
+        assert!(events.diagnostics().is_empty());
+
+        events.by_ref().for_each(drop);
+        assert_eq!(events.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn rebuild_ast_from_events() {
+        let markdown = "> [Gohan](/gohan \"title\") **bold**\n\n1. one\n2. ![two](2.png)\n";
+        let tokens = Lexer::new(markdown).into_tokens();
+        let expected = Parser::new(&tokens, ParserOptions::default()).parse();
+        assert_eq!(build_ast(EventIter::new(markdown)), expected);
+    }
+
+    #[test]
+    fn build_children_without_spare_capacity() {
+        let markdown = "- one\n- two\n- three *a* b *c* d *e*\n";
+        let ast = build_ast(EventIter::new(markdown));
+        let Node::List(list) = &ast[0] else {
+            unreachable!()
+        };
+        assert_eq!(list.children.capacity(), 3);
+        let Node::ListItem(item) = &list.children[2] else {
+            unreachable!()
+        };
+        let Node::Paragraph(paragraph) = &item.children[0] else {
+            unreachable!()
+        };
+        assert_eq!(paragraph.children.capacity(), paragraph.children.len());
+
+        let unclosed = [
+            Event::Text("before".into()),
+            Event::Start(Tag::Paragraph {
+                span: Span::default()..Span::default(),
+            }),
+            Event::Text("never ended".into()),
+        ];
+        assert_eq!(build_ast(unclosed), [Node::Text("before".into())]);
+    }
+
+    #[test]
+    fn lex_only_as_far_as_needed() {
+        let markdown = format!("# Title\n\n{}", "Some text\n\n".repeat(1000));
+        let mut events = EventIter::new(&markdown);
+        assert!(matches!(
+            events.next(),
+            Some(Event::Start(Tag::Header { .. }))
+        ));
+        assert!(events.tokens.len() < Lexer::new(&markdown).into_tokens().len());
+    }
+
+    #[test]
+    fn match_parsing_the_whole_document() {
+        // Long enough for the tokens to be lexed a few times over,
+        // with a link spanning a line of spaces
+        let markdown = "[a\n   \nb](url) *text*\n\n- item\n\n  \n  more\n\n".repeat(200);
+        let tokens = Lexer::new(&markdown).into_tokens();
+        let expected = Parser::new(&tokens, ParserOptions::default()).parse();
+        assert_eq!(build_ast(EventIter::new(&markdown)), expected);
+    }
+
+    fn tag_end(tag: &Tag) -> TagEnd {
+        match tag {
+            Tag::Header { .. } => TagEnd::Header,
+            Tag::Paragraph { .. } => TagEnd::Paragraph,
+            Tag::CodeBlock { .. } => TagEnd::CodeBlock,
+            Tag::BlockQuote { .. } => TagEnd::BlockQuote,
+            Tag::List { .. } => TagEnd::List,
+            Tag::ListItem { .. } => TagEnd::ListItem,
+            Tag::Link { .. } => TagEnd::Link,
+            Tag::Image { .. } => TagEnd::Image,
+            Tag::Bold { .. } => TagEnd::Bold,
+            Tag::Italic { .. } => TagEnd::Italic,
+        }
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use serde::de::DeserializeOwned;
+
+use crate::lexer::BOM;
+
+/// Serialization format of a front matter block,
+/// identified by the delimiters around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Delimited by `---` lines
+    Yaml,
+    /// Delimited by `+++` lines
+    Toml,
+}
+
+/// Raw front matter found at the very beginning of a document
+#[derive(Debug, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub format: Format,
+    /// Content in between the delimiters, without the delimiter lines
+    pub content: &'a str,
+}
+
+/// Splits the front matter from the markdown body that follows it.
+/// When the document has no front matter, the whole input is returned as the body.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::frontmatter::{self, Format};
+/// let markdown = "---\ntitle: Gohan\n---\n# Hello";
+/// let (front_matter, body) = frontmatter::split(markdown);
+/// let front_matter = front_matter.unwrap();
+/// assert_eq!(front_matter.format, Format::Yaml);
+/// assert_eq!(front_matter.content, "title: Gohan\n");
+/// assert_eq!(body, "# Hello");
+/// ```
+pub fn split(markdown: &str) -> (Option<FrontMatter<'_>>, &str) {
+    for (delimiter, format) in [("---", Format::Yaml), ("+++", Format::Toml)] {
+        let Some(rest) = strip_delimiter_line(markdown, delimiter) else {
+            continue;
+        };
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end_matches(['\r', '\n']) == delimiter {
+                let front_matter = FrontMatter {
+                    format,
+                    content: &rest[..offset],
+                };
+                return (Some(front_matter), &rest[offset + line.len()..]);
+            }
+            offset += line.len();
+        }
+    }
+
+    (None, markdown)
+}
+
+/// Splits the front matter from the markdown body and deserializes it
+/// according to its format. Front matter that can't be deserialized into `T`,
+/// or whose format feature isn't enabled, is returned as `None`.
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub fn extract<T: DeserializeOwned>(markdown: &str) -> (Option<T>, &str) {
+    let (front_matter, body) = split(markdown);
+    let value = front_matter.and_then(|front_matter| match front_matter.format {
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::from_str(front_matter.content).ok(),
+        #[cfg(feature = "toml")]
+        Format::Toml => toml::from_str(front_matter.content).ok(),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    });
+    (value, body)
+}
+
+/// Front matter delimiters must be on the very first line of the document,
+/// only preceded by a byte order mark if any
+fn strip_delimiter_line<'a>(markdown: &'a str, delimiter: &str) -> Option<&'a str> {
+    let markdown = markdown.strip_prefix(BOM).unwrap_or(markdown);
+    let rest = markdown.strip_prefix(delimiter)?;
+    rest.strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_toml_front_matter() {
+        let markdown = "+++\r\ntitle = \"Gohan\"\r\n+++\r\nBody";
+        let (front_matter, body) = split(markdown);
+        assert_eq!(
+            front_matter,
+            Some(FrontMatter {
+                format: Format::Toml,
+                content: "title = \"Gohan\"\r\n",
+            })
+        );
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn split_after_byte_order_mark() {
+        let (front_matter, body) = split("\u{feff}---\ntitle: Gohan\n---\n# Hello");
+        assert_eq!(front_matter.unwrap().content, "title: Gohan\n");
+        assert_eq!(body, "# Hello");
+    }
+
+    #[test]
+    fn split_without_front_matter() {
+        for markdown in [
+            "# Title\n---\n",
+            "---\nnever closed\n",
+            "--- \nnot a delimiter\n---\n",
+        ] {
+            assert_eq!(split(markdown), (None, markdown));
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn extract_yaml_front_matter() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Meta {
+            title: String,
+            tags: Vec<String>,
+        }
+
+        let markdown = "---\ntitle: Gohan\ntags: [rust, markdown]\n---\n# Hello";
+        let (meta, body) = extract::<Meta>(markdown);
+        assert_eq!(
+            meta,
+            Some(Meta {
+                title: "Gohan".to_string(),
+                tags: vec!["rust".to_string(), "markdown".to_string()],
+            })
+        );
+        assert_eq!(body, "# Hello");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn extract_toml_front_matter() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Meta {
+            draft: bool,
+        }
+
+        let (meta, body) = extract::<Meta>("+++\ndraft = true\n+++\nBody");
+        assert_eq!(meta, Some(Meta { draft: true }));
+        assert_eq!(body, "Body");
+    }
+}
@@ -0,0 +1,195 @@
+//! Syntax highlighting of fenced code blocks, built on syntect.
+
+use std::{fmt, sync::Arc};
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    html::{self, ClassStyle, ClassedHTMLGenerator, IncludeBackground},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// Theme used unless another one is set, among the ones bundled with syntect
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+/// How the highlighted tokens carry their colors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightStyle {
+    /// Colors of the theme written on each `<span>`, which needs no stylesheet
+    #[default]
+    Inline,
+    /// Scope names written as the classes of each `<span>`, colored by the
+    /// stylesheet of [`Highlighter::stylesheet`] or any other one
+    Classes,
+}
+
+/// Highlights code blocks whose language is known, rendering them
+/// with the language grammars and themes bundled with syntect by default.
+/// Cloning it shares the loaded grammars and theme.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{highlight::{HighlightStyle, Highlighter}, renderer::{self, HtmlOptions}};
+/// let options = HtmlOptions {
+///     highlighter: Some(Highlighter::new(HighlightStyle::Classes)),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("```rust\nfn main() {}\n```", &options).unwrap();
+/// assert!(html.starts_with(r#"<pre><code class="language-rust"><span class="source rust">"#));
+/// ```
+#[derive(Clone)]
+pub struct Highlighter {
+    syntaxes: Arc<SyntaxSet>,
+    theme: Arc<Theme>,
+    style: HighlightStyle,
+}
+
+impl Highlighter {
+    pub fn new(style: HighlightStyle) -> Self {
+        let mut themes = ThemeSet::load_defaults().themes;
+        let theme = themes
+            .remove(DEFAULT_THEME)
+            .expect("the default theme is bundled with syntect");
+        Self {
+            syntaxes: Arc::new(SyntaxSet::load_defaults_newlines()),
+            theme: Arc::new(theme),
+            style,
+        }
+    }
+
+    /// Uses the given theme, e.g. one of `ThemeSet::load_defaults()`
+    /// or one loaded from a `.tmTheme` file
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Arc::new(theme);
+        self
+    }
+
+    /// Uses the given grammars instead of the default ones,
+    /// which must be loaded with newlines, e.g. with `SyntaxSet::load_defaults_newlines()`
+    pub fn with_syntaxes(mut self, syntaxes: SyntaxSet) -> Self {
+        self.syntaxes = Arc::new(syntaxes);
+        self
+    }
+
+    /// Highlighted HTML of the code, to be wrapped into `<pre><code>`,
+    /// or `None` when the language is unknown
+    pub fn highlight(&self, code: &str, language: &str) -> Option<String> {
+        let syntax = self.syntax(language)?;
+        match self.style {
+            HighlightStyle::Inline => {
+                let mut highlighter = HighlightLines::new(syntax, &self.theme);
+                let mut output = String::new();
+                for line in LinesWithEndings::from(code) {
+                    let ranges = highlighter.highlight_line(line, &self.syntaxes).ok()?;
+                    let line =
+                        html::styled_line_to_highlighted_html(&ranges, IncludeBackground::No)
+                            .ok()?;
+                    output.push_str(&line);
+                }
+                Some(output)
+            }
+            HighlightStyle::Classes => {
+                let mut generator = ClassedHTMLGenerator::new_with_class_style(
+                    syntax,
+                    &self.syntaxes,
+                    ClassStyle::Spaced,
+                );
+                for line in LinesWithEndings::from(code) {
+                    generator
+                        .parse_html_for_line_which_includes_newline(line)
+                        .ok()?;
+                }
+                Some(generator.finalize())
+            }
+        }
+    }
+
+    /// Inline style of the `<pre>` element around highlighted code,
+    /// giving it the background color of the theme with inline colors
+    pub fn pre_style(&self) -> Option<String> {
+        if self.style != HighlightStyle::Inline {
+            return None;
+        }
+        let color = self.theme.settings.background?;
+        Some(format!(
+            "background-color:#{:02x}{:02x}{:02x};",
+            color.r, color.g, color.b
+        ))
+    }
+
+    /// CSS coloring the classes of [`HighlightStyle::Classes`] with the theme
+    pub fn stylesheet(&self) -> String {
+        html::css_for_theme_with_class_style(&self.theme, ClassStyle::Spaced).unwrap_or_default()
+    }
+
+    /// Grammar of the language of a code block, looked up by name or file extension
+    fn syntax(&self, language: &str) -> Option<&SyntaxReference> {
+        self.syntaxes.find_syntax_by_token(language)
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new(HighlightStyle::default())
+    }
+}
+
+impl fmt::Debug for Highlighter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Highlighter")
+            .field("theme", &self.theme.name)
+            .field("style", &self.style)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{render_html_with, HtmlOptions};
+
+    fn render(markdown: &str, highlighter: Highlighter) -> String {
+        let options = HtmlOptions {
+            highlighter: Some(highlighter),
+            ..Default::default()
+        };
+        render_html_with(markdown, &options).unwrap()
+    }
+
+    #[test]
+    fn highlight_with_inline_styles() {
+        let html = render("```rust\nlet a = 1 < 2;\n```", Highlighter::default());
+        assert!(html.starts_with(
+            r#"<pre style="background-color:#ffffff;"><code class="language-rust"><span style=""#
+        ));
+        // Styles may set the font weight as well, but always a color
+        let styles: Vec<&str> = html
+            .split(r#"<span style=""#)
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .collect();
+        assert!(!styles.is_empty());
+        assert!(styles.iter().all(|style| style.contains("color:#")));
+        assert!(html.contains("&lt;"));
+        assert!(html.ends_with("</code></pre>"));
+    }
+
+    #[test]
+    fn highlight_with_classes() {
+        let highlighter = Highlighter::new(HighlightStyle::Classes);
+        assert!(highlighter.stylesheet().contains(".source"));
+        let html = render("```rs\nfn main() {}\n```", highlighter);
+        assert!(html.starts_with(r#"<pre><code class="language-rs"><span class="source rust">"#));
+        assert!(html.contains(">fn</span>"));
+    }
+
+    #[test]
+    fn fall_back_to_plain_code() {
+        for markdown in ["```not-a-language\na < b\n```", "```\na < b\n```"] {
+            let plain = render_html_with(markdown, &HtmlOptions::default()).unwrap();
+            assert_eq!(render(markdown, Highlighter::default()), plain);
+        }
+    }
+}
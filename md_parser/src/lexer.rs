@@ -1,6 +1,38 @@
+use alloc::{vec, vec::Vec};
+
 use crate::token::{Span, Token};
 
-const SYMBOLS: &str = "#*!_[]().- \n\t\\";
+const SYMBOLS: &str = "#`*!_[]().-<>\" \n\r\t\\";
+const TAB_STOP: usize = 4;
+/// Byte order mark some Windows tools write at the start of UTF-8 files
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Whether each byte starts a token other than text,
+/// so runs of text are skipped without searching through `SYMBOLS`
+const TOKEN_STARTS: [bool; 256] = {
+    let mut table = [false; 256];
+    let symbols = SYMBOLS.as_bytes();
+    let mut idx = 0;
+    while idx < symbols.len() {
+        table[symbols[idx] as usize] = true;
+        idx += 1;
+    }
+    let mut digit = b'0';
+    while digit <= b'9' {
+        table[digit as usize] = true;
+        digit += 1;
+    }
+    table
+};
+
+/// Whether the character starts a token other than text
+fn starts_token(c: char) -> bool {
+    if c.is_ascii() {
+        TOKEN_STARTS[c as usize]
+    } else {
+        c.is_whitespace()
+    }
+}
 
 /// Tokenizes Markdown input
 pub struct Lexer<'a> {
@@ -10,17 +42,30 @@ pub struct Lexer<'a> {
     current_byte_offset: usize,
     col: usize,
     line: usize,
+    /// Offset a `-->` was last searched from, along with the offset of the first one after it.
+    /// Comment openers up to that `-->` reuse the result, so that the input is searched
+    /// only once however many unterminated openers there are.
+    comment_close: Option<(usize, Option<usize>)>,
 }
 
 impl<'a> Lexer<'a> {
+    /// A byte order mark at the start of the input is skipped,
+    /// so the first line starts at the column 0 like every other one.
+    /// Spans are still byte offsets of the whole input.
     pub fn new(input: &'a str) -> Self {
+        let start = if input.starts_with(BOM) {
+            BOM.len_utf8()
+        } else {
+            0
+        };
         Self {
             source: input,
             tokens: Vec::new(),
-            start_byte_offset: 0,
-            current_byte_offset: 0,
+            start_byte_offset: start,
+            current_byte_offset: start,
             line: 1,
             col: 0,
+            comment_close: None,
         }
     }
 
@@ -30,20 +75,44 @@ impl<'a> Lexer<'a> {
             self.scan_token();
         }
 
+        self.start_byte_offset = self.current_byte_offset;
         self.add_token(Token::EndOfFile);
         &self.tokens
     }
 
+    /// Scans the whole input, handing over the tokens
+    pub fn into_tokens(mut self) -> Vec<(Token<'a>, Span)> {
+        self.scan();
+        self.tokens
+    }
+
+    /// Lazily scans the input from its start, one token at a time,
+    /// ending with [`Token::EndOfFile`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use md_parser::{lexer::Lexer, token::Token};
+    /// let mut tokens = Lexer::new("# Gohan").iter();
+    /// assert_eq!(tokens.next().map(|(token, _)| token), Some(Token::Hash));
+    /// assert_eq!(tokens.count(), 3);
+    /// ```
+    pub fn iter(&self) -> Tokens<'a> {
+        Tokens {
+            lexer: Lexer::new(self.source),
+            next: 0,
+            done: false,
+        }
+    }
+
     fn scan_token(&mut self) {
         let Some(c) = self.advance() else {
-            panic!(
-                "Could not scan the next token. line={} byte_offset={}",
-                &self.line, self.current_byte_offset
-            );
+            return;
         };
 
         match c {
             b'#' => self.add_token(Token::Hash),
+            b'`' => self.add_token(Token::Backtick),
             b'*' => self.add_token(Token::Star),
             b'!' => self.add_token(Token::Bang),
             b' ' => self.add_token(Token::Space),
@@ -55,43 +124,127 @@ impl<'a> Lexer<'a> {
             b'[' => self.add_token(Token::LeftSquareBracket),
             b']' => self.add_token(Token::RightSquareBracket),
             b'\\' => self.add_token(Token::Backslash),
-            b'\t' => self.add_token(Token::Tab),
+            b'"' => self.add_token(Token::DoubleQuote),
+            b'<' => self.handle_less_than(),
+            b'>' => self.add_token(Token::GreaterThan),
+            b'\t' => self.expand_tab(),
             b'\n' => self.add_token(Token::Newline),
-            c if c.is_ascii_digit() => self.add_token(Token::Digit(
-                &self.source[self.current_byte_offset - 1..self.current_byte_offset],
-            )),
+            // Windows (`\r\n`) and old Mac (`\r`) line endings
+            // are normalized into a single newline token
+            b'\r' => {
+                if self.peek() == Some(b'\n') {
+                    self.advance();
+                } else {
+                    self.line += 1;
+                    self.col = 0;
+                }
+                self.add_token(Token::Newline)
+            }
+            c if c.is_ascii_digit() => self.handle_number(),
+            c if !c.is_ascii()
+                && self.source[self.start_byte_offset..].starts_with(char::is_whitespace) =>
+            {
+                self.handle_whitespace()
+            }
             _ => self.handle_string(),
         }
     }
 
-    /// A token can only be within the ASCII space
-    /// and must belong into our list of reserved symbols
-    fn is_token(&self, c: Option<u8>) -> bool {
-        match c {
-            Some(c) => {
-                if c.is_ascii() {
-                    c.is_ascii_digit() || SYMBOLS.contains(c as char)
-                } else {
-                    false
-                }
+    /// Tabs are expanded into spaces up to the next tab stop,
+    /// which are placed at every multiple of 4 columns.
+    fn expand_tab(&mut self) {
+        // `advance` already moved the column past the tab itself
+        let tab_col = self.col - 1;
+        let width = TAB_STOP - tab_col % TAB_STOP;
+        for offset in 1..=width {
+            self.col = tab_col + offset;
+            self.add_token(Token::Space);
+            if let Some((_, span)) = self.tokens.last_mut() {
+                span.tab = true;
             }
-            None => false,
         }
     }
 
-    fn handle_string(&mut self) {
+    /// Consecutive digits are grouped into a single number token
+    fn handle_number(&mut self) {
         let start_offset = self.current_byte_offset - 1;
-        let mut end_byte_offset = start_offset;
-        while !self.is_at_end() && !self.is_token(self.peek()) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
             self.advance();
-            end_byte_offset += 1;
         }
 
-        let value = &self.source[start_offset..end_byte_offset + 1];
+        let value = &self.source[start_offset..self.current_byte_offset];
+        self.add_token(Token::Number(value));
+    }
+
+    /// HTML comments are kept as a single token, so their content
+    /// is never interpreted as markdown. Unterminated comments are not comments.
+    fn handle_less_than(&mut self) {
+        let start_offset = self.current_byte_offset - 1;
+        let comment_length = match self.source[start_offset..].starts_with("<!--") {
+            true => {
+                let content_start = start_offset + "<!--".len();
+                self.comment_close_after(content_start)
+                    .map(|close| close + "-->".len() - start_offset)
+            }
+            false => None,
+        };
 
+        match comment_length {
+            Some(length) => {
+                while self.current_byte_offset < start_offset + length {
+                    self.advance();
+                }
+                let value = &self.source[start_offset..self.current_byte_offset];
+                self.add_token(Token::Comment(value));
+            }
+            None => self.add_token(Token::LessThan),
+        }
+    }
+
+    /// Offset of the first `-->` at or after the given offset
+    fn comment_close_after(&mut self, offset: usize) -> Option<usize> {
+        match self.comment_close {
+            Some((searched, close)) if searched <= offset && close.is_none_or(|c| c >= offset) => {
+                close
+            }
+            _ => {
+                let close = self.source[offset..].find("-->").map(|idx| offset + idx);
+                self.comment_close = Some((offset, close));
+                close
+            }
+        }
+    }
+
+    /// Text runs until the next character starting another token, never spanning a line break
+    fn handle_string(&mut self) {
+        let value = self.consume_run(|c| !starts_token(c));
         self.add_token(Token::Text(value));
     }
 
+    /// Consecutive Unicode whitespace is grouped into a single token,
+    /// keeping the characters as they were written
+    fn handle_whitespace(&mut self) {
+        let value = self.consume_run(|c| !c.is_ascii() && c.is_whitespace());
+        self.add_token(Token::Whitespace(value));
+    }
+
+    /// Consumes the characters of the current token for as long as they belong to the run.
+    /// `advance` only consumed the first byte of the token, which may be part of
+    /// a multi-byte character, so the run is measured from where the token starts.
+    fn consume_run(&mut self, in_run: impl Fn(char) -> bool) -> &'a str {
+        let start_offset = self.start_byte_offset;
+        let rest = &self.source[start_offset..];
+        let first = rest.chars().next().map_or(0, char::len_utf8);
+        let length = rest[first..]
+            .char_indices()
+            .find(|&(_, c)| !in_run(c))
+            .map_or(rest.len(), |(idx, _)| first + idx);
+        // The column already moved past the first byte
+        self.col += length - 1;
+        self.current_byte_offset = start_offset + length;
+        &rest[..length]
+    }
+
     fn is_at_end(&self) -> bool {
         self.current_byte_offset >= self.source.bytes().len()
     }
@@ -101,6 +254,9 @@ impl<'a> Lexer<'a> {
         let span = Span {
             line: self.line,
             col: self.col,
+            start: self.start_byte_offset,
+            end: self.current_byte_offset,
+            tab: false,
         };
         self.tokens.push((token, span));
     }
@@ -143,6 +299,62 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Number of consecutive spaces starting at each token position. At the start of a line,
+/// it's the indentation of the line in columns, since tabs are expanded into spaces.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::lexer::{self, Lexer};
+/// let tokens = Lexer::new("-   item\n\tcode").into_tokens();
+/// let indentation = lexer::indentation_table(&tokens);
+/// assert_eq!(indentation[1], 3);
+/// assert_eq!(indentation[6], 4);
+/// ```
+pub fn indentation_table(tokens: &[(Token, Span)]) -> Vec<usize> {
+    let mut table = vec![0; tokens.len()];
+    for position in (0..tokens.len()).rev() {
+        if tokens[position].0 == Token::Space {
+            table[position] = 1 + table.get(position + 1).copied().unwrap_or(0);
+        }
+    }
+    table
+}
+
+/// Iterator over the tokens of an input, see [`Lexer::iter`]
+pub struct Tokens<'a> {
+    /// Holds the few tokens scanned from the latest character, like the spaces of a tab
+    lexer: Lexer<'a>,
+    /// Index of the next scanned token to be yielded
+    next: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (Token<'a>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.lexer.tokens.len() {
+            if self.done {
+                return None;
+            }
+            self.lexer.tokens.clear();
+            self.next = 0;
+            self.lexer.start_byte_offset = self.lexer.current_byte_offset;
+            if self.lexer.is_at_end() {
+                self.lexer.add_token(Token::EndOfFile);
+                self.done = true;
+            } else {
+                self.lexer.scan_token();
+            }
+        }
+
+        let token = self.lexer.tokens[self.next].clone();
+        self.next += 1;
+        Some(token)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -158,6 +370,16 @@ mod tests {
         });
     }
 
+    #[test]
+    fn iterate_tokens_lazily() {
+        insta::glob!("snapshot_inputs/*.md", |path| {
+            let markdown = fs::read_to_string(path).unwrap();
+            let lexer = Lexer::new(&markdown);
+            let tokens: Vec<(Token, Span)> = lexer.iter().collect();
+            assert_eq!(tokens, lexer.into_tokens());
+        });
+    }
+
     #[test]
     fn accept_multi_byte_chars() {
         let markdown = r"
@@ -170,4 +392,440 @@ mod tests {
         let result = lexer.scan();
         assert_eq!(result.len(), 80);
     }
+
+    #[test]
+    fn scan_text_up_to_the_next_token() {
+        let mut lexer = Lexer::new("héllo wörld\n🎉*");
+        assert_eq!(
+            lexer.scan(),
+            &vec![
+                (
+                    Token::Text("héllo"),
+                    Span {
+                        line: 1,
+                        col: 6,
+                        start: 0,
+                        end: 6,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Space,
+                    Span {
+                        line: 1,
+                        col: 7,
+                        start: 6,
+                        end: 7,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Text("wörld"),
+                    Span {
+                        line: 1,
+                        col: 13,
+                        start: 7,
+                        end: 13,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Newline,
+                    Span {
+                        line: 2,
+                        col: 0,
+                        start: 13,
+                        end: 14,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Text("🎉"),
+                    Span {
+                        line: 2,
+                        col: 4,
+                        start: 14,
+                        end: 18,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Star,
+                    Span {
+                        line: 2,
+                        col: 5,
+                        start: 18,
+                        end: 19,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::EndOfFile,
+                    Span {
+                        line: 2,
+                        col: 5,
+                        start: 19,
+                        end: 19,
+                        tab: false,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn slice_multi_byte_chars_next_to_symbols() {
+        let samples = ["🎉", "日本語", "e\u{301}", "\u{301}", "👩\u{200d}💻"];
+        for symbol in SYMBOLS.chars() {
+            for sample in samples {
+                let markdown = format!("{sample}{symbol}{sample}");
+                let tokens = Lexer::new(&markdown).into_tokens();
+                let texts: Vec<&str> = tokens
+                    .iter()
+                    .filter_map(|(token, _)| match token {
+                        Token::Text(text) => Some(*text),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(texts, [sample, sample], "in {:?}", markdown);
+                for (token, span) in tokens.iter() {
+                    assert!(
+                        markdown.get(span.bytes()).is_some(),
+                        "{} sliced mid-character in {:?}",
+                        token,
+                        markdown
+                    );
+                }
+                assert_eq!(tokens[0].1.bytes(), 0..sample.len());
+                assert_eq!(tokens.last().unwrap().1.start, markdown.len());
+            }
+        }
+    }
+
+    #[test]
+    fn group_unicode_whitespace() {
+        let mut lexer = Lexer::new("a\u{a0}\u{3000}b\u{2009}*");
+        let tokens: Vec<&Token> = lexer.scan().iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                &Token::Text("a"),
+                &Token::Whitespace("\u{a0}\u{3000}"),
+                &Token::Text("b"),
+                &Token::Whitespace("\u{2009}"),
+                &Token::Star,
+                &Token::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_leading_byte_order_mark() {
+        let mut lexer = Lexer::new("\u{feff}# A\u{feff}");
+        let result = lexer.scan();
+        assert_eq!(
+            result[0],
+            (
+                Token::Hash,
+                Span {
+                    line: 1,
+                    col: 1,
+                    start: 3,
+                    end: 4,
+                    tab: false,
+                }
+            )
+        );
+        // Only a byte order mark starting the input is skipped
+        assert_eq!(result[2].0, Token::Text("A\u{feff}"));
+        assert_eq!(
+            Lexer::new("\u{feff}").scan(),
+            &vec![(
+                Token::EndOfFile,
+                Span {
+                    line: 1,
+                    col: 0,
+                    start: 3,
+                    end: 3,
+                    tab: false,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn keep_zero_width_joiners_next_to_markers() {
+        let mut lexer = Lexer::new("\u{200d}#\u{200d}*👩\u{200d}💻_");
+        let tokens: Vec<&Token> = lexer.scan().iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                &Token::Text("\u{200d}"),
+                &Token::Hash,
+                &Token::Text("\u{200d}"),
+                &Token::Star,
+                &Token::Text("👩\u{200d}💻"),
+                &Token::Underscore,
+                &Token::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn count_indentation_of_every_line() {
+        let tokens = Lexer::new("a\n  b\n\t c\n  \n").into_tokens();
+        let indentation = indentation_table(&tokens);
+        let line_starts: Vec<usize> = (0..tokens.len())
+            .filter(|&position| position == 0 || tokens[position - 1].0 == Token::Newline)
+            .map(|position| indentation[position])
+            .collect();
+        assert_eq!(line_starts, [0, 2, 5, 2, 0]);
+        assert_eq!(indentation.len(), tokens.len());
+    }
+
+    #[test]
+    fn expand_tabs_to_next_tab_stop() {
+        let mut lexer = Lexer::new("\ta\tb");
+        let result = lexer.scan();
+        let cols: Vec<(&Token, usize)> = result.iter().map(|(t, span)| (t, span.col)).collect();
+        assert_eq!(
+            cols,
+            vec![
+                (&Token::Space, 1),
+                (&Token::Space, 2),
+                (&Token::Space, 3),
+                (&Token::Space, 4),
+                (&Token::Text("a"), 5),
+                (&Token::Space, 6),
+                (&Token::Space, 7),
+                (&Token::Space, 8),
+                (&Token::Text("b"), 9),
+                (&Token::EndOfFile, 9),
+            ]
+        );
+        // Every space of a tab tells it came from one
+        let tabs: Vec<bool> = result.iter().map(|(_, span)| span.tab).collect();
+        assert_eq!(
+            tabs,
+            [true, true, true, true, false, true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn lex_unterminated_comment_openers_in_linear_time() {
+        let markdown = "<!--".repeat(50_000);
+        let mut lexer = Lexer::new(&markdown);
+        let tokens = lexer.scan();
+        assert_eq!(tokens.len(), markdown.len() + 1);
+        assert!(!tokens.iter().any(|(t, _)| matches!(t, Token::Comment(_))));
+        // The input was only searched from the first opener, whose result every other one reused
+        assert_eq!(lexer.comment_close, Some((4, None)));
+
+        let mut lexer = Lexer::new("<!-- <!-- a --> <!-- b --> <!--");
+        let comments: Vec<&Token> = lexer
+            .scan()
+            .iter()
+            .map(|(t, _)| t)
+            .filter(|t| matches!(t, Token::Comment(_)))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                &Token::Comment("<!-- <!-- a -->"),
+                &Token::Comment("<!-- b -->")
+            ]
+        );
+    }
+
+    #[test]
+    fn group_digits_into_numbers() {
+        let mut lexer = Lexer::new("In 2024, 3.14");
+        let tokens: Vec<&Token> = lexer.scan().iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                &Token::Text("In"),
+                &Token::Space,
+                &Token::Number("2024"),
+                &Token::Text(","),
+                &Token::Space,
+                &Token::Number("3"),
+                &Token::Dot,
+                &Token::Number("14"),
+                &Token::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_carriage_returns() {
+        let mut lexer = Lexer::new("a\r\nb\rc\n");
+        let result = lexer.scan();
+        assert_eq!(
+            result,
+            &vec![
+                (
+                    Token::Text("a"),
+                    Span {
+                        line: 1,
+                        col: 1,
+                        start: 0,
+                        end: 1,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Newline,
+                    Span {
+                        line: 2,
+                        col: 0,
+                        start: 1,
+                        end: 3,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Text("b"),
+                    Span {
+                        line: 2,
+                        col: 1,
+                        start: 3,
+                        end: 4,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Newline,
+                    Span {
+                        line: 3,
+                        col: 0,
+                        start: 4,
+                        end: 5,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Text("c"),
+                    Span {
+                        line: 3,
+                        col: 1,
+                        start: 5,
+                        end: 6,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Newline,
+                    Span {
+                        line: 4,
+                        col: 0,
+                        start: 6,
+                        end: 7,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::EndOfFile,
+                    Span {
+                        line: 4,
+                        col: 0,
+                        start: 7,
+                        end: 7,
+                        tab: false,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_html_comments() {
+        let mut lexer = Lexer::new("<!-- a\nb -->c <!--");
+        let result = lexer.scan();
+        assert_eq!(
+            result,
+            &vec![
+                (
+                    Token::Comment("<!-- a\nb -->"),
+                    Span {
+                        line: 2,
+                        col: 5,
+                        start: 0,
+                        end: 12,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Text("c"),
+                    Span {
+                        line: 2,
+                        col: 6,
+                        start: 12,
+                        end: 13,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Space,
+                    Span {
+                        line: 2,
+                        col: 7,
+                        start: 13,
+                        end: 14,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::LessThan,
+                    Span {
+                        line: 2,
+                        col: 8,
+                        start: 14,
+                        end: 15,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Bang,
+                    Span {
+                        line: 2,
+                        col: 9,
+                        start: 15,
+                        end: 16,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Dash,
+                    Span {
+                        line: 2,
+                        col: 10,
+                        start: 16,
+                        end: 17,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::Dash,
+                    Span {
+                        line: 2,
+                        col: 11,
+                        start: 17,
+                        end: 18,
+                        tab: false,
+                    }
+                ),
+                (
+                    Token::EndOfFile,
+                    Span {
+                        line: 2,
+                        col: 11,
+                        start: 18,
+                        end: 18,
+                        tab: false,
+                    }
+                ),
+            ]
+        );
+    }
 }
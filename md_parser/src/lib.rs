@@ -1,5 +1,32 @@
+//! Markdown parser and HTML renderer.
+//!
+//! Only `core` and `alloc` are needed without the default `std` feature,
+//! which adds rendering into `std::io::Write` sinks. Tests always link `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod ast;
+pub mod diagnostic;
+pub mod document;
+pub mod events;
+pub mod frontmatter;
+#[cfg(feature = "highlight")]
+pub mod highlight;
 pub mod lexer;
+pub mod links;
+pub mod lint;
+pub mod man;
+pub mod markdown;
 pub mod parser;
 pub mod renderer;
+pub mod sanitizer;
+pub mod stats;
+pub mod text;
+pub mod toc;
 pub mod token;
+pub mod transform;
+
+pub use stats::{ast_stats, stats, DocStats};
+pub use toc::{outline, title, Heading};
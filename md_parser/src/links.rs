@@ -0,0 +1,270 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{collect_text, Node},
+    frontmatter, parser,
+    token::{self, Span},
+};
+
+/// Element a destination was found in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LinkKind {
+    Link,
+    Image,
+}
+
+/// Destination of a link or an image of the document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Destination {
+    pub url: String,
+    pub kind: LinkKind,
+    /// Position of the whole link or image in the source, front matter included
+    pub span: Range<Span>,
+}
+
+impl Destination {
+    /// Whether the destination is a web page to request, rather than a local file
+    pub fn is_external(&self) -> bool {
+        scheme(&self.url).is_some_and(|scheme| {
+            scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+        })
+    }
+}
+
+/// Destination that couldn't be reached
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub destination: Destination,
+    pub reason: String,
+}
+
+/// Destinations of every link and image of the markdown, in source order.
+/// Empty destinations, like `[text]()`, are left out.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::links::{self, LinkKind};
+/// let markdown = "See [the docs](docs/index.md) and ![logo](https://bpaulino.com/logo.png)";
+/// let destinations = links::destinations(markdown);
+/// assert_eq!(destinations[0].url, "docs/index.md");
+/// assert_eq!(destinations[1].kind, LinkKind::Image);
+/// assert!(destinations[1].is_external());
+/// ```
+pub fn destinations(markdown: &str) -> Vec<Destination> {
+    let (_, body) = frontmatter::split(markdown);
+    let (ast, _) = parser::parse_with_diagnostics(body);
+    let offset = markdown.len() - body.len();
+    let mut destinations = Vec::new();
+    collect_destinations(&mut destinations, markdown, offset, &ast);
+    destinations
+}
+
+fn collect_destinations(
+    destinations: &mut Vec<Destination>,
+    source: &str,
+    offset: usize,
+    nodes: &[Node],
+) {
+    for node in nodes.iter() {
+        let (kind, url, span) = match node {
+            Node::Link(link) => {
+                collect_destinations(destinations, source, offset, &link.children);
+                (LinkKind::Link, &link.url, &link.span)
+            }
+            Node::Image(image) => (LinkKind::Image, &image.url, &image.span),
+            node => {
+                if let Some(children) = children(node) {
+                    collect_destinations(destinations, source, offset, children);
+                }
+                continue;
+            }
+        };
+        let mut destination = String::new();
+        collect_text(&mut destination, url);
+        if destination.is_empty() {
+            continue;
+        }
+        let bytes = token::byte_range(span);
+        destinations.push(Destination {
+            url: destination,
+            kind,
+            span: token::source_span(source, offset + bytes.start..offset + bytes.end),
+        });
+    }
+}
+
+fn children<'a, 's>(node: &'a Node<'s>) -> Option<&'a [Node<'s>]> {
+    match node {
+        Node::Header(header) => Some(&header.children),
+        Node::Paragraph(paragraph) => Some(&paragraph.children),
+        Node::BlockQuote(quote) => Some(&quote.children),
+        Node::List(list) => Some(&list.children),
+        Node::ListItem(item) => Some(&item.children),
+        Node::Bold(bold) => Some(&bold.children),
+        Node::Italic(italic) => Some(&italic.children),
+        _ => None,
+    }
+}
+
+/// Scheme of an absolute URL, e.g. `mailto` for `mailto:gohan@bpaulino.com`
+fn scheme(url: &str) -> Option<&str> {
+    let (scheme, _) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // A single letter is rather a Windows drive, like `C:\docs`
+    (valid && scheme.len() > 1).then_some(scheme)
+}
+
+/// File the destination points to, without its query or fragment.
+/// Relative paths are resolved from the directory of the document,
+/// and absolute ones from the root of the site.
+/// URLs with a scheme and links within the page, like `#usage`, aren't files.
+#[cfg(feature = "std")]
+pub fn local_path(url: &str, dir: &Path, root: &Path) -> Option<PathBuf> {
+    if scheme(url).is_some() || url.starts_with("//") {
+        return None;
+    }
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    // Escapes that don't decode are rather part of the file name
+    let path = percent_decode(path).unwrap_or_else(|| String::from(path));
+    Some(match path.strip_prefix('/') {
+        Some(path) => root.join(path),
+        None => dir.join(path),
+    })
+}
+
+/// Decodes the percent-encoded bytes of a URL, like `%20` for a space.
+/// Invalid escapes, or bytes that don't make up valid UTF-8, give `None`.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::links::percent_decode;
+/// assert_eq!(percent_decode("caf%C3%A9%20au%20lait").as_deref(), Some("café au lait"));
+/// assert_eq!(percent_decode("100%"), None);
+/// ```
+pub fn percent_decode(url: &str) -> Option<String> {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let byte = match bytes[idx] {
+            b'%' => {
+                let hex = url.get(idx + 1..idx + 3)?;
+                // `from_str_radix` would also take a leading `+`
+                if !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+                    return None;
+                }
+                idx += 2;
+                u8::from_str_radix(hex, 16).ok()?
+            }
+            byte => byte,
+        };
+        decoded.push(byte);
+        idx += 1;
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Destinations of the document pointing to local files that don't exist
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use md_parser::links;
+/// let destinations = links::destinations("[Manifest](Cargo.toml) and [missing](missing.md#usage)");
+/// let broken = links::check_local(&destinations, Path::new("."), Path::new("."));
+/// assert_eq!(broken.len(), 1);
+/// assert_eq!(broken[0].destination.url, "missing.md#usage");
+/// ```
+#[cfg(feature = "std")]
+pub fn check_local(destinations: &[Destination], dir: &Path, root: &Path) -> Vec<BrokenLink> {
+    destinations
+        .iter()
+        .filter_map(|destination| {
+            let path = local_path(&destination.url, dir, root)?;
+            (!path.exists()).then(|| BrokenLink {
+                destination: destination.clone(),
+                reason: alloc::format!("{} does not exist", path.display()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_destinations() {
+        let markdown = "+++\ntitle = \"Gohan\"\n+++\n# [Home](/)\n\n- **[Guide](guide.md#intro)**\n- ![](logo.png)\n\n> [Mail](mailto:gohan@bpaulino.com) and [nothing]()\n";
+        let destinations = destinations(markdown);
+        let found: Vec<(&str, LinkKind, &str)> = destinations
+            .iter()
+            .map(|destination| {
+                let bytes = token::byte_range(&destination.span);
+                (destination.url.as_str(), destination.kind, &markdown[bytes])
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/", LinkKind::Link, "[Home](/)"),
+                ("guide.md#intro", LinkKind::Link, "[Guide](guide.md#intro)"),
+                ("logo.png", LinkKind::Image, "![](logo.png)"),
+                (
+                    "mailto:gohan@bpaulino.com",
+                    LinkKind::Link,
+                    "[Mail](mailto:gohan@bpaulino.com)"
+                ),
+            ]
+        );
+        assert_eq!(destinations[1].span.start.line, 6);
+    }
+
+    #[test]
+    fn decode_percent_escapes() {
+        assert_eq!(
+            percent_decode("/guide/my%20notes.html").as_deref(),
+            Some("/guide/my notes.html")
+        );
+        assert_eq!(percent_decode("caf%c3%A9").as_deref(), Some("café"));
+        assert_eq!(percent_decode("%F0%9F%8D%9C").as_deref(), Some("🍜"));
+        assert_eq!(percent_decode("%zz"), None);
+        assert_eq!(percent_decode("%+1"), None);
+        assert_eq!(percent_decode("%ff"), None);
+        assert_eq!(percent_decode("%2"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn resolve_local_paths() {
+        let dir = Path::new("docs/guide");
+        let root = Path::new("docs");
+        let path = |url| local_path(url, dir, root);
+        assert_eq!(path("intro.md#usage"), Some(dir.join("intro.md")));
+        assert_eq!(
+            path("../img/logo%20dark.png?v=2"),
+            Some(dir.join("../img/logo dark.png"))
+        );
+        assert_eq!(path("/index.md"), Some(root.join("index.md")));
+        assert_eq!(path("caf%C3%A9.md"), Some(dir.join("café.md")));
+        assert_eq!(path("a%23b.md#top"), Some(dir.join("a#b.md")));
+        assert_eq!(path("100%.md"), Some(dir.join("100%.md")));
+        assert_eq!(path("#usage"), None);
+        assert_eq!(path("https://bpaulino.com"), None);
+        assert_eq!(path("//bpaulino.com/logo.png"), None);
+        assert_eq!(path("mailto:gohan@bpaulino.com"), None);
+    }
+}
@@ -0,0 +1,350 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::Node,
+    diagnostic::Severity,
+    frontmatter, parser,
+    token::{self, Span},
+};
+
+/// Check the linter runs over a document
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// Heading levels only go up one at a time, e.g. no `###` right under a `#`
+    HeadingIncrement,
+    /// URLs are written as links rather than as plain text
+    NoBareUrls,
+    /// Lines don't end with spaces or tabs
+    NoTrailingSpaces,
+    /// The document starts with a level 1 heading
+    FirstLineHeading,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 4] = [
+        Rule::HeadingIncrement,
+        Rule::NoBareUrls,
+        Rule::NoTrailingSpaces,
+        Rule::FirstLineHeading,
+    ];
+
+    /// Identifier of the rule in the configuration and in reports
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::HeadingIncrement => "heading-increment",
+            Rule::NoBareUrls => "no-bare-urls",
+            Rule::NoTrailingSpaces => "no-trailing-spaces",
+            Rule::FirstLineHeading => "first-line-heading",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// How a rule is enforced
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// The rule isn't checked
+    Off,
+    #[default]
+    Warning,
+    Error,
+}
+
+/// Level of each rule, all of them reporting warnings by default.
+///
+/// Deserializes from a table keyed by rule IDs, like the `[lint]` table of a `gohan.toml`:
+///
+/// ```toml
+/// [lint]
+/// no-bare-urls = "error"
+/// first-line-heading = "off"
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LintConfig {
+    pub heading_increment: Level,
+    pub no_bare_urls: Level,
+    pub no_trailing_spaces: Level,
+    pub first_line_heading: Level,
+}
+
+impl LintConfig {
+    pub fn level(&self, rule: Rule) -> Level {
+        match rule {
+            Rule::HeadingIncrement => self.heading_increment,
+            Rule::NoBareUrls => self.no_bare_urls,
+            Rule::NoTrailingSpaces => self.no_trailing_spaces,
+            Rule::FirstLineHeading => self.first_line_heading,
+        }
+    }
+}
+
+/// Problem found by one of the lint rules
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    /// Position of the offending source, whose byte range is given by [`token::byte_range`]
+    pub span: Range<Span>,
+}
+
+/// Checks the markdown against the rules enabled in the configuration.
+/// Lints are ordered by their position in the source, front matter included.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::lint::{self, LintConfig, Rule};
+/// let markdown = "# Gohan\n\n### Install\n\nSee https://gohan.bpaulino.com\n";
+/// let lints = lint::lint(markdown, &LintConfig::default());
+/// let rules: Vec<Rule> = lints.iter().map(|lint| lint.rule).collect();
+/// assert_eq!(rules, [Rule::HeadingIncrement, Rule::NoBareUrls]);
+/// ```
+pub fn lint(markdown: &str, config: &LintConfig) -> Vec<Lint> {
+    let (_, body) = frontmatter::split(markdown);
+    let (ast, _) = parser::parse_with_diagnostics(body);
+    let mut linter = Linter {
+        source: markdown,
+        offset: markdown.len() - body.len(),
+        config,
+        lints: Vec::new(),
+    };
+
+    linter.first_line_heading(body, &ast);
+    linter.heading_increment(&ast, &mut None);
+    linter.no_bare_urls(&ast);
+    linter.no_trailing_spaces();
+    linter
+        .lints
+        .sort_by_key(|lint| (lint.span.start.start, lint.rule));
+    linter.lints
+}
+
+/// Line and column, both starting at 1, of the given byte offset in the source.
+/// Columns count characters rather than bytes, as editors do.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(md_parser::lint::line_col("# Gohan\n\nhéllo world", 16), (3, 7));
+/// ```
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+struct Linter<'a> {
+    source: &'a str,
+    /// Where the markdown body starts after the front matter,
+    /// as AST spans are relative to the body
+    offset: usize,
+    config: &'a LintConfig,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, bytes: Range<usize>, message: String) {
+        let severity = match self.config.level(rule) {
+            Level::Off => return,
+            Level::Warning => Severity::Warning,
+            Level::Error => Severity::Error,
+        };
+        self.lints.push(Lint {
+            rule,
+            severity,
+            message,
+            span: token::source_span(self.source, bytes),
+        });
+    }
+
+    fn first_line_heading(&mut self, body: &str, ast: &[Node]) {
+        let first = ast.iter().find(|node| !matches!(node, Node::Comment(_)));
+        if first.is_none() || matches!(first, Some(Node::Header(header)) if header.level == 1) {
+            return;
+        }
+        let mut line_start = 0;
+        for line in body.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            if !content.trim().is_empty() {
+                let start = self.offset + line_start;
+                self.report(
+                    Rule::FirstLineHeading,
+                    start..start + content.len(),
+                    "the document should start with a level 1 heading".into(),
+                );
+                return;
+            }
+            line_start += line.len();
+        }
+    }
+
+    fn heading_increment(&mut self, nodes: &[Node], previous: &mut Option<u8>) {
+        for node in nodes.iter() {
+            match node {
+                Node::Header(header) => {
+                    if let Some(level) = *previous {
+                        if header.level > level + 1 {
+                            let message = format!(
+                                "heading level {} follows level {}, expected level {} at most",
+                                header.level,
+                                level,
+                                level + 1
+                            );
+                            self.report(Rule::HeadingIncrement, self.bytes(&header.span), message);
+                        }
+                    }
+                    *previous = Some(header.level);
+                }
+                Node::BlockQuote(quote) => self.heading_increment(&quote.children, previous),
+                Node::List(list) => self.heading_increment(&list.children, previous),
+                Node::ListItem(item) => self.heading_increment(&item.children, previous),
+                _ => {}
+            }
+        }
+    }
+
+    fn no_bare_urls(&mut self, nodes: &[Node]) {
+        for node in nodes.iter() {
+            match node {
+                Node::Header(header) => self.bare_urls(&header.children, &header.span),
+                Node::Paragraph(paragraph) => self.bare_urls(&paragraph.children, &paragraph.span),
+                Node::BlockQuote(quote) => self.no_bare_urls(&quote.children),
+                Node::List(list) => self.no_bare_urls(&list.children),
+                Node::ListItem(item) => self.no_bare_urls(&item.children),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reports the URLs written in the source of a block, outside of its links and images
+    fn bare_urls(&mut self, children: &[Node], span: &Range<Span>) {
+        let mut links = Vec::new();
+        link_ranges(&mut links, children);
+        let bytes = token::byte_range(span);
+        let block = &self.source[self.offset..][bytes.clone()];
+
+        let mut searched = 0;
+        while let Some(found) = ["http://", "https://"]
+            .iter()
+            .filter_map(|scheme| block[searched..].find(scheme))
+            .min()
+        {
+            let start = searched + found;
+            let length = block[start..]
+                .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+                .unwrap_or(block.len() - start);
+            let url =
+                block[start..start + length].trim_end_matches(['.', ',', ':', ';', '!', '?', ')']);
+            searched = start + length;
+
+            let in_link = links
+                .iter()
+                .any(|link| link.contains(&(bytes.start + start)));
+            // `<https://…>` is how an autolink is written, even where it isn't supported
+            if in_link || block[..start].ends_with('<') {
+                continue;
+            }
+            let start = self.offset + bytes.start + start;
+            self.report(
+                Rule::NoBareUrls,
+                start..start + url.len(),
+                format!("bare URL {}, write it as a link", url),
+            );
+        }
+    }
+
+    fn no_trailing_spaces(&mut self) {
+        let mut line_start = 0;
+        for line in self.source.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let trimmed = content.trim_end_matches([' ', '\t']);
+            if trimmed.len() < content.len() {
+                self.report(
+                    Rule::NoTrailingSpaces,
+                    line_start + trimmed.len()..line_start + content.len(),
+                    "trailing whitespace".into(),
+                );
+            }
+            line_start += line.len();
+        }
+    }
+
+    /// Byte range of an element of the body within the whole source
+    fn bytes(&self, span: &Range<Span>) -> Range<usize> {
+        let bytes = token::byte_range(span);
+        self.offset + bytes.start..self.offset + bytes.end
+    }
+}
+
+/// Byte ranges of the links and images among the nodes, relative to the body
+fn link_ranges(ranges: &mut Vec<Range<usize>>, nodes: &[Node]) {
+    for node in nodes.iter() {
+        match node {
+            Node::Link(link) => ranges.push(token::byte_range(&link.span)),
+            Node::Image(image) => ranges.push(token::byte_range(&image.span)),
+            Node::Bold(bold) => link_ranges(ranges, &bold.children),
+            Node::Italic(italic) => link_ranges(ranges, &italic.children),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_markdown() {
+        let markdown = "---\ntitle: Gohan\n---\nIntro\n\n# Title \n\n### Deep\n\nSee https://gohan.dev. or [docs](https://gohan.dev/docs)\n";
+        let found = |config: &LintConfig| -> Vec<(Rule, Severity, &str)> {
+            lint(markdown, config)
+                .into_iter()
+                .map(|lint| {
+                    let bytes = token::byte_range(&lint.span);
+                    (lint.rule, lint.severity, &markdown[bytes])
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            found(&LintConfig::default()),
+            [
+                (Rule::FirstLineHeading, Severity::Warning, "Intro"),
+                (Rule::NoTrailingSpaces, Severity::Warning, " "),
+                (Rule::HeadingIncrement, Severity::Warning, "### Deep"),
+                (Rule::NoBareUrls, Severity::Warning, "https://gohan.dev"),
+            ]
+        );
+
+        let config = LintConfig {
+            heading_increment: Level::Off,
+            no_bare_urls: Level::Error,
+            ..Default::default()
+        };
+        assert_eq!(
+            found(&config),
+            [
+                (Rule::FirstLineHeading, Severity::Warning, "Intro"),
+                (Rule::NoTrailingSpaces, Severity::Warning, " "),
+                (Rule::NoBareUrls, Severity::Error, "https://gohan.dev"),
+            ]
+        );
+
+        let lints = lint(markdown, &LintConfig::default());
+        assert_eq!(lints[0].span.start.line, 4);
+        assert_eq!(line_col(markdown, lints[3].span.start.start), (10, 5));
+    }
+}
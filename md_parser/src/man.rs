@@ -0,0 +1,256 @@
+use alloc::{format, string::String};
+
+use crate::{
+    ast::{collect_text, List, Node},
+    parser,
+    renderer::RenderError,
+};
+
+/// Header of the man page
+#[derive(Debug, Default, Clone)]
+pub struct ManOptions {
+    /// Manual section the page belongs to, e.g. `1` for commands
+    pub section: String,
+    /// Name of the page, usually the command name in uppercase
+    pub title: String,
+}
+
+/// Renders the given markdown as a man page written in roff
+///
+/// Level 1 headings start sections, level 2 headings start subsections,
+/// and deeper headings are rendered as bold paragraphs.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::man::{self, ManOptions};
+/// let options = ManOptions {
+///     section: "1".to_string(),
+///     title: "GOHAN".to_string(),
+/// };
+/// let page = man::render_man("# NAME\n\ngohan - **static** site generator", &options).unwrap();
+/// assert_eq!(
+///     page,
+///     ".TH \"GOHAN\" \"1\"\n.SH NAME\n.PP\ngohan \\- \\fBstatic\\fR site generator\n"
+/// );
+/// ```
+pub fn render_man(markdown: &str, options: &ManOptions) -> Result<String, RenderError> {
+    let ast = parser::parse(markdown)?;
+    let mut page = format!(
+        ".TH \"{}\" \"{}\"\n",
+        escape_quoted(&options.title),
+        escape_quoted(&options.section)
+    );
+    blocks(&mut page, &ast);
+    Ok(page)
+}
+
+fn blocks(page: &mut String, nodes: &[Node]) {
+    for node in nodes.iter() {
+        block(page, node);
+    }
+}
+
+fn block(page: &mut String, node: &Node) {
+    match node {
+        Node::Header(header) => {
+            let title = inline(&header.children).replace('\n', " ");
+            match header.level {
+                1 => page.push_str(&format!(".SH {}\n", title)),
+                2 => page.push_str(&format!(".SS {}\n", title)),
+                _ => page.push_str(&format!(".PP\n\\fB{}\\fR\n", title)),
+            }
+        }
+        Node::Paragraph(paragraph) => {
+            page.push_str(".PP\n");
+            text_lines(page, &inline(&paragraph.children));
+        }
+        Node::CodeBlock(code_block) => {
+            let mut code = String::new();
+            collect_text(&mut code, &code_block.code);
+            page.push_str(".PP\n.RS 4\n.nf\n");
+            text_lines(page, &escape(&code));
+            page.push_str(".fi\n.RE\n");
+        }
+        Node::BlockQuote(quote) => {
+            page.push_str(".RS\n");
+            blocks(page, &quote.children);
+            page.push_str(".RE\n");
+        }
+        Node::List(list) => list_items(page, list),
+        Node::ListItem(item) => blocks(page, &item.children),
+        Node::ThematicBreak => page.push_str(".sp\n"),
+        Node::Comment(_) => {}
+        node => text_lines(page, &inline(core::slice::from_ref(node))),
+    }
+}
+
+fn list_items(page: &mut String, list: &List) {
+    let start = list.start.unwrap_or(1) as usize;
+    for (idx, item) in list.children.iter().enumerate() {
+        let Node::ListItem(item) = item else {
+            block(page, item);
+            continue;
+        };
+
+        if list.ordered {
+            page.push_str(&format!(".IP {}. 4\n", start + idx));
+        } else {
+            page.push_str(".IP \\(bu 2\n");
+        }
+        for (idx, child) in item.children.iter().enumerate() {
+            match child {
+                // The first paragraph goes right next to the item marker
+                Node::Paragraph(paragraph) if idx == 0 => {
+                    text_lines(page, &inline(&paragraph.children))
+                }
+                Node::List(_) => {
+                    page.push_str(".RS\n");
+                    block(page, child);
+                    page.push_str(".RE\n");
+                }
+                child => block(page, child),
+            }
+        }
+    }
+}
+
+/// Adds the given text as lines of the page. Lines starting with
+/// a control character are escaped, so they are kept as text.
+fn text_lines(page: &mut String, text: &str) {
+    for line in text.trim_end_matches('\n').split('\n') {
+        if line.starts_with('.') || line.starts_with('\'') {
+            page.push_str("\\&");
+        }
+        page.push_str(line);
+        page.push('\n');
+    }
+}
+
+fn inline(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes.iter() {
+        match node {
+            Node::Text(value) | Node::Digit(value) => text.push_str(&escape(value)),
+            Node::LineBreak => text.push('\n'),
+            Node::Comment(_) => {}
+            Node::Bold(bold) => text.push_str(&format!("\\fB{}\\fR", inline(&bold.children))),
+            Node::Italic(italic) => text.push_str(&format!("\\fI{}\\fR", inline(&italic.children))),
+            Node::Link(link) => {
+                let label = inline(&link.children);
+                let mut url = String::new();
+                collect_text(&mut url, &link.url);
+                let url = escape(&url);
+                if label.is_empty() || label == url {
+                    text.push_str(&format!("\\fI{}\\fR", url));
+                } else {
+                    text.push_str(&format!("{} (\\fI{}\\fR)", label, url));
+                }
+            }
+            Node::Image(image) => text.push_str(&inline(&image.alt)),
+            node => {
+                let mut plain = String::new();
+                collect_text(&mut plain, core::slice::from_ref(node));
+                text.push_str(&escape(&plain));
+            }
+        }
+    }
+    text
+}
+
+/// Escapes the characters roff would read as escapes or special hyphens
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// Escapes text going into a quoted argument of a request
+fn escape_quoted(text: &str) -> String {
+    escape(text).replace('"', "\\(dq")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> ManOptions {
+        ManOptions {
+            section: "1".to_string(),
+            title: "GOHAN".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_man_page() {
+        let markdown = r"# NAME
+
+gohan - static site generator
+
+# SYNOPSIS
+
+**gohan** [*options*] <file>
+
+## Options
+
+- `--watch`: re-render on change
+- See [the docs](https://gohan.bpaulino.com)
+
+1. First
+2. Second
+
+```
+.hidden line
+C:\path
+```
+
+> Quoted *note*
+";
+        assert_eq!(
+            render_man(markdown, &options()).unwrap(),
+            r#".TH "GOHAN" "1"
+.SH NAME
+.PP
+gohan \- static site generator
+.SH SYNOPSIS
+.PP
+\fBgohan\fR [\fIoptions\fR] <file>
+.SS Options
+.IP \(bu 2
+`\-\-watch`: re\-render on change
+.IP \(bu 2
+See the docs (\fIhttps://gohan.bpaulino.com\fR)
+.IP 1. 4
+First
+.IP 2. 4
+Second
+.PP
+.RS 4
+.nf
+\&.hidden line
+C:\epath
+.fi
+.RE
+.RS
+.PP
+Quoted \fInote\fR
+.RE
+"#
+        );
+    }
+
+    #[test]
+    fn escape_control_lines() {
+        let page = render_man("A line\n.TH injected\n'quote", &options()).unwrap();
+        assert_eq!(
+            page,
+            ".TH \"GOHAN\" \"1\"\n.PP\nA line\n\\&.TH injected\n\\&'quote\n"
+        );
+
+        let options = ManOptions {
+            section: "1".to_string(),
+            title: "A \"B\"".to_string(),
+        };
+        assert!(render_man("", &options)
+            .unwrap()
+            .starts_with(".TH \"A \\(dqB\\(dq\" \"1\""));
+    }
+}
@@ -0,0 +1,387 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::ast::{collect_text, CodeBlock, List, Node};
+
+/// Renders the AST back into normalized markdown, using the default options
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{markdown, parser};
+/// let ast = parser::parse("Title\n\n__bold__ and _italic_\n\n* one\n* two").unwrap();
+/// assert_eq!(
+///     markdown::render_markdown(&ast),
+///     "Title\n\n**bold** and *italic*\n\n- one\n- two\n"
+/// );
+/// ```
+pub fn render_markdown(ast: &[Node]) -> String {
+    render_markdown_with(ast, &MarkdownOptions::default())
+}
+
+/// Renders the AST back into markdown written in the style set by the options
+pub fn render_markdown_with(ast: &[Node], options: &MarkdownOptions) -> String {
+    let mut markdown = blocks(options, ast, options.line_width);
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// Settings controlling the style of the markdown output
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// Delimiter of italic text, which is doubled for bold text
+    pub emphasis: char,
+    /// Marker of unordered list items
+    pub bullet: char,
+    /// Wrap paragraphs so their lines fit within the given width.
+    /// Without it, paragraph lines are broken where they were in the source.
+    pub line_width: Option<usize>,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            emphasis: '*',
+            bullet: '-',
+            line_width: None,
+        }
+    }
+}
+
+/// Blocks separated by blank lines, without a trailing newline
+fn blocks(options: &MarkdownOptions, nodes: &[Node], width: Option<usize>) -> String {
+    nodes
+        .iter()
+        .map(|node| block(options, node, width))
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn block(options: &MarkdownOptions, node: &Node, width: Option<usize>) -> String {
+    match node {
+        Node::Header(header) => {
+            let title = inline(options, &header.children).replace('\n', " ");
+            format!("{} {}", "#".repeat(header.level as usize), title.trim())
+        }
+        Node::Paragraph(paragraph) => paragraph_lines(options, &paragraph.children, width),
+        Node::CodeBlock(code_block) => fenced_code(code_block),
+        Node::BlockQuote(quote) => {
+            let content = blocks(options, &quote.children, width.map(|w| w.saturating_sub(2)));
+            prefix_lines(&content, "> ", "> ")
+        }
+        Node::List(list) => list_items(options, list, width),
+        Node::ListItem(item) => {
+            let mut content = String::new();
+            for (idx, child) in item.children.iter().enumerate() {
+                // Nested lists follow the item text right away, keeping the list tight
+                match child {
+                    _ if idx == 0 => {}
+                    Node::List(_) => content.push('\n'),
+                    _ => content.push_str("\n\n"),
+                }
+                content.push_str(&block(options, child, width));
+            }
+            content
+        }
+        Node::ThematicBreak => "---".to_string(),
+        Node::Comment(comment) => format!("<!--{}-->", comment),
+        node => inline(options, core::slice::from_ref(node)),
+    }
+}
+
+fn list_items(options: &MarkdownOptions, list: &List, width: Option<usize>) -> String {
+    let start = list.start.unwrap_or(1);
+    list.children
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let marker = if list.ordered {
+                format!("{}. ", start as usize + idx)
+            } else {
+                format!("{} ", options.bullet)
+            };
+            let indentation = " ".repeat(marker.len());
+            let width = width.map(|w| w.saturating_sub(marker.len()));
+            prefix_lines(&block(options, item, width), &marker, &indentation)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Prefixes the first line with `first` and the other lines with `rest`,
+/// leaving blank lines without any trailing whitespace
+fn prefix_lines(content: &str, first: &str, rest: &str) -> String {
+    content
+        .split('\n')
+        .enumerate()
+        .map(|(idx, line)| {
+            let prefix = if idx == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn fenced_code(code_block: &CodeBlock) -> String {
+    let mut code = String::new();
+    collect_text(&mut code, &code_block.code);
+    if !code.is_empty() && !code.ends_with('\n') {
+        code.push('\n');
+    }
+
+    // The fence must be longer than any run of backticks within the code
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let info = match (&code_block.language, &code_block.attrs) {
+        (Some(language), Some(attrs)) => format!("{} {{{}}}", language, attrs),
+        (Some(language), None) => language.clone(),
+        (None, Some(attrs)) => format!("{{{}}}", attrs),
+        (None, None) => String::new(),
+    };
+    format!("{}{}\n{}{}", fence, info, code, fence)
+}
+
+/// Lines of a paragraph, wrapped within the given width when there is one
+fn paragraph_lines(options: &MarkdownOptions, nodes: &[Node], width: Option<usize>) -> String {
+    let Some(width) = width else {
+        return escape_block_starts(inline(options, nodes).trim_end());
+    };
+
+    // Words are separated by spaces and line breaks,
+    // while inline elements are never split across lines
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for node in nodes.iter() {
+        match node {
+            Node::Text(text) => {
+                let text = escape_text(text);
+                let mut parts = text.split(char::is_whitespace);
+                word.push_str(parts.next().unwrap_or_default());
+                for part in parts {
+                    words.push(core::mem::take(&mut word));
+                    word.push_str(part);
+                }
+            }
+            Node::LineBreak => words.push(core::mem::take(&mut word)),
+            node => word.push_str(&inline(options, core::slice::from_ref(node))),
+        }
+    }
+    words.push(word);
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in words.into_iter().filter(|word| !word.is_empty()) {
+        let fits = line.len() + 1 + word.len() <= width;
+        if !line.is_empty() && (fits || starts_block(&word)) {
+            line.push(' ');
+        } else if !line.is_empty() {
+            lines.push(core::mem::take(&mut line));
+        }
+        line.push_str(&word);
+    }
+    lines.push(line);
+    escape_block_starts(&lines.join("\n"))
+}
+
+/// Escapes the first character of the lines which would start a new block,
+/// like the `#` of text written as `\# not a heading`
+fn escape_block_starts(lines: &str) -> String {
+    lines
+        .split('\n')
+        .map(|line| {
+            let word = line.split(' ').next().unwrap_or_default();
+            match starts_block(word) && line.starts_with(|c: char| c.is_ascii_punctuation()) {
+                true => format!("\\{}", line),
+                false => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escapes the characters of text which would be read as inline elements,
+/// like the `*` of text written as `\*not emphasis\*`
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (idx, c) in text.char_indices() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']') || text[idx..].starts_with("<!--") {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Words which would start a new block when moved to the start of a line
+fn starts_block(word: &str) -> bool {
+    let digits = word.chars().take_while(char::is_ascii_digit).count();
+    matches!(word, "-" | "+" | "*" | "=" | "---")
+        || word.starts_with('#')
+        || word.starts_with('>')
+        || word.starts_with("```")
+        || (digits > 0 && (word[digits..] == *"." || word[digits..] == *")"))
+}
+
+fn inline(options: &MarkdownOptions, nodes: &[Node]) -> String {
+    let mut markdown = String::new();
+    for node in nodes.iter() {
+        match node {
+            Node::Text(text) => markdown.push_str(&escape_text(text)),
+            Node::Digit(text) => markdown.push_str(text),
+            Node::LineBreak => markdown.push('\n'),
+            Node::Comment(comment) => markdown.push_str(&format!("<!--{}-->", comment)),
+            Node::Bold(bold) => {
+                let delimiter = options.emphasis.to_string().repeat(2);
+                let content = inline(options, &bold.children);
+                markdown.push_str(&format!("{}{}{}", delimiter, content, delimiter));
+            }
+            Node::Italic(italic) => {
+                let content = inline(options, &italic.children);
+                let delimiter = options.emphasis;
+                markdown.push_str(&format!("{}{}{}", delimiter, content, delimiter));
+            }
+            Node::Link(link) => {
+                let text = inline(options, &link.children);
+                let destination = destination(&link.url, &link.title);
+                markdown.push_str(&format!("[{}]({})", text, destination));
+            }
+            Node::Image(image) => {
+                let alt = inline(options, &image.alt);
+                let destination = destination(&image.url, &image.title);
+                markdown.push_str(&format!("![{}]({})", alt, destination));
+            }
+            node => markdown.push_str(&block(options, node, None)),
+        }
+    }
+    markdown
+}
+
+/// Destination of a link or image, along with its title
+fn destination(url: &[Node], title: &Option<Vec<Node>>) -> String {
+    let mut destination = String::new();
+    collect_text(&mut destination, url);
+    if destination.contains(' ') {
+        destination = format!("<{}>", destination);
+    }
+    if let Some(title) = title {
+        let mut text = String::new();
+        collect_text(&mut text, title);
+        destination.push_str(&format!(" \"{}\"", text));
+    }
+    destination
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{parser, renderer};
+
+    use super::*;
+
+    #[test]
+    fn format_markdown() {
+        insta::glob!("snapshot_inputs/*.md", |path| {
+            let markdown = fs::read_to_string(path).unwrap();
+            let ast = parser::parse(&markdown).unwrap();
+            insta::assert_snapshot!(render_markdown(&ast));
+        });
+    }
+
+    #[test]
+    fn keep_html_output_when_formatting() {
+        insta::glob!("snapshot_inputs/*.md", |path| {
+            let markdown = fs::read_to_string(path).unwrap();
+            let ast = parser::parse(&markdown).unwrap();
+            let formatted = render_markdown(&ast);
+            let reparsed = parser::parse(&formatted).unwrap();
+            assert_eq!(
+                renderer::render_ast(&reparsed, &Default::default()),
+                renderer::render_ast(&ast, &Default::default()),
+                "formatted:\n{}",
+                formatted
+            );
+            // Formatting is idempotent
+            assert_eq!(render_markdown(&reparsed), formatted);
+        });
+    }
+
+    #[test]
+    fn normalize_markdown_style() {
+        let options = MarkdownOptions {
+            emphasis: '_',
+            bullet: '*',
+            line_width: None,
+        };
+        let cases = [
+            ("**bold** *it*", "__bold__ _it_\n"),
+            ("- a\n- b", "* a\n* b\n"),
+            ("3. c\n4. d", "3. c\n4. d\n"),
+            ("> quote\n>\n> more", "> quote\n>\n> more\n"),
+            ("    indented code", "```\nindented code\n```\n"),
+            ("[a b](<my file.md> \"T\")", "[a b](<my file.md> \"T\")\n"),
+            ("***", "---\n"),
+        ];
+        for (markdown, expected) in cases {
+            let ast = parser::parse(markdown).unwrap();
+            assert_eq!(
+                render_markdown_with(&ast, &options),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn escape_text_when_formatting() {
+        let cases = [
+            (
+                "\\*not emphasis\\* and \\[brackets\\]",
+                "\\*not emphasis\\* and \\[brackets\\]\n",
+            ),
+            ("\\# not a heading", "\\# not a heading\n"),
+            ("\\- not a list", "\\- not a list\n"),
+            (
+                "snake\\_case and C:\\Users",
+                "snake\\_case and C:\\\\Users\n",
+            ),
+            ("\\<!-- not a comment -->", "\\<!-- not a comment -->\n"),
+        ];
+        for (markdown, expected) in cases {
+            let ast = parser::parse(markdown).unwrap();
+            let formatted = render_markdown(&ast);
+            assert_eq!(formatted, expected, "input: {}", markdown);
+            assert_eq!(
+                renderer::render_ast(&parser::parse(&formatted).unwrap(), &Default::default()),
+                renderer::render_ast(&ast, &Default::default()),
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn wrap_paragraph_lines() {
+        let options = MarkdownOptions {
+            line_width: Some(20),
+            ..Default::default()
+        };
+        let markdown = "A paragraph\nwith a **few words** and [a link](/link) that\nwraps - at 20 columns.\n\n> Quoted text wraps as well when it is long";
+        let ast = parser::parse(markdown).unwrap();
+        assert_eq!(
+            render_markdown_with(&ast, &options),
+            "A paragraph with a\n**few words** and\n[a link](/link) that\nwraps - at 20\ncolumns.\n\n> Quoted text wraps\n> as well when it is\n> long\n"
+        );
+    }
+}
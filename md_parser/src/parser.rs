@@ -1,8 +1,25 @@
-use crate::ast::{Bold, Header, Link, Node, Paragraph};
+use crate::ast::{BlockQuote, CodeBlock, Header, List, ListItem, Node, Paragraph};
+use crate::diagnostic::Diagnostic;
+use crate::events::{self, EventIter};
+use crate::lexer;
 use crate::token::{Span, Token};
 
-use std::cmp::max;
-use std::ops::Range;
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::cmp::max;
+use core::ops::Range;
+use core::{fmt, ops};
+
+/// Block quotes, lists and inline elements deeper than this are kept as text by default,
+/// as parsing each nesting level recursively could otherwise overflow the stack
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 128;
+
+mod inline;
 
 // Markdown Grammar
 // (* A document is a series of blocks *)
@@ -19,6 +36,7 @@ use std::ops::Range;
 
 // (* Blockquotes *)
 // blockquote = ">", { ">", text } ;
+// (* Lines without ">" lazily continue the paragraph of the quote *)
 
 // (* Lists can be unordered or ordered *)
 // list = unordered_list | ordered_list ;
@@ -26,10 +44,11 @@ use std::ops::Range;
 // ordered_list = digit, ".", " ", text, { newline, digit, ".", " ", text } ;
 
 // (* Code blocks *)
-// code_block = "```", newline*, { text }, newline*, "```" ;
+// code_block = "```", [ info_string ], newline*, { text }, newline*, "```" ;
+// info_string = language, [ "{", attributes, "}" ] | "{", ".", language, attributes, "}" ;
 
-// (* Horizontal rules *)
-// horizontal_rule = ( "---" | "***" | "___" ) ;
+// (* Horizontal rules, with optional spaces in between the characters *)
+// horizontal_rule = 3 * "-", { "-" } | 3 * "*", { "*" } | 3 * "_", { "_" } ;
 
 // (* Inline elements can be within other blocks like paragraphs and headers *)
 // text = { inline_element | chars } ;
@@ -43,10 +62,10 @@ use std::ops::Range;
 // strong = ( "**" | "__" ), text, ( "**" | "__" ) ;
 
 // (* Links *)
-// link = "[", text, "]", "(", url, ")" ;
+// link = "[", text, "]", "(", url, [ " ", '"', title, '"' ], ")" ;
 
 // (* Images *)
-// image = "!", "[", alt_text, "]", "(", url, ")" ;
+// image = "!", "[", text, "]", "(", url, [ " ", '"', title, '"' ], ")" ;
 
 // (* Helpers *)
 // newline = "\n" | "\r\n" ;
@@ -55,78 +74,757 @@ use std::ops::Range;
 // language = ? any string that represents a programming language name ? ;
 // url = ? any valid URL ? ;
 // title = ? any string ? ;
-// alt_text = ? any string ? ;
+
+/// Parses the given markdown into its AST. Elements nested deeper than
+/// [`ParserOptions::max_nesting_depth`] are kept as text.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::Node, parser};
+/// let ast = parser::parse("# Gohan").unwrap();
+/// assert!(matches!(ast[0], Node::Header(_)));
+///
+/// let deeply_nested = ">".repeat(1000);
+/// assert_eq!(parser::parse(&deeply_nested).unwrap().len(), 1);
+/// ```
+pub fn parse(markdown: &str) -> Result<Vec<Node<'_>>, ParseError> {
+    parse_with_options(markdown, ParserOptions::default())
+}
+
+/// Parses the given markdown into its AST, only recognizing
+/// the syntax extensions enabled in the given options
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::Node, parser::{self, Extensions, ParserOptions}};
+/// let options = ParserOptions {
+///     extensions: Extensions::empty(),
+///     ..Default::default()
+/// };
+/// let ast = parser::parse_with_options("<!-- draft -->", options).unwrap();
+/// assert!(matches!(ast[0], Node::Paragraph(_)));
+/// ```
+pub fn parse_with_options(
+    markdown: &str,
+    options: ParserOptions,
+) -> Result<Vec<Node<'_>>, ParseError> {
+    // Elements nested too deep are already degraded to text,
+    // and only reported through the diagnostics of the events
+    let mut events = EventIter::with_options(markdown, options);
+    Ok(events::build_ast(&mut events))
+}
+
+/// Parses the given markdown into its AST, recovering from any problem found
+/// along the way. Parts that can't be parsed are kept as text and reported
+/// as diagnostics next to the AST.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{diagnostic::Severity, parser};
+/// let (ast, diagnostics) = parser::parse_with_diagnostics("[Gohan](https://bpaulino.com");
+/// assert_eq!(ast.len(), 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Warning);
+/// assert_eq!(diagnostics[0].message, "link destination is never closed");
+/// ```
+pub fn parse_with_diagnostics(markdown: &str) -> (Vec<Node<'_>>, Vec<Diagnostic>) {
+    let mut events = EventIter::new(markdown);
+    let ast = events::build_ast(&mut events);
+    (ast, events.into_diagnostics())
+}
+
+/// Problem found while parsing markdown, as reported by [`EventIter::error`].
+/// Malformed constructs are not errors, they are parsed as text instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Elements are nested deeper than [`ParserOptions::max_nesting_depth`]
+    NestingTooDeep { span: Span, max_depth: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NestingTooDeep { span, max_depth } => write!(
+                f,
+                "elements nested deeper than {} levels at line {}, column {}",
+                max_depth, span.line, span.col
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Settings controlling which syntax the parser recognizes
+#[derive(Clone)]
+pub struct ParserOptions {
+    pub extensions: Extensions,
+    /// Custom inline syntax, tried in order before the built-in inline elements
+    pub inline_rules: Vec<Arc<dyn InlineRule>>,
+    /// Custom block syntax, tried in order before the built-in blocks
+    pub block_rules: Vec<Arc<dyn BlockRule>>,
+    /// Block quotes, lists and inline elements nested deeper than this are kept as text
+    /// and reported as a diagnostic. Each block level is parsed
+    /// recursively, so targets with a small stack, like WebAssembly, may want a lower limit.
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            extensions: Extensions::default(),
+            inline_rules: Vec::new(),
+            block_rules: Vec::new(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Appends an inline rule, tried after the ones already registered
+    pub fn with_inline_rule(mut self, rule: impl InlineRule + 'static) -> Self {
+        self.inline_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Appends a block rule, tried after the ones already registered
+    pub fn with_block_rule(mut self, rule: impl BlockRule + 'static) -> Self {
+        self.block_rules.push(Arc::new(rule));
+        self
+    }
+}
+
+impl fmt::Debug for ParserOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserOptions")
+            .field("extensions", &self.extensions)
+            .field("inline_rules", &self.inline_rules.len())
+            .field("block_rules", &self.block_rules.len())
+            .field("max_nesting_depth", &self.max_nesting_depth)
+            .finish()
+    }
+}
+
+/// Custom inline syntax, recognized within paragraphs and headings
+/// before any built-in inline element.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{
+///     ast::Node,
+///     parser::{self, InlineRule, ParserOptions},
+///     token::{Span, Token},
+/// };
+///
+/// /// Keyboard shortcuts like `{{kbd:Ctrl+C}}`, kept as their keys
+/// struct Kbd;
+///
+/// impl InlineRule for Kbd {
+///     fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)> {
+///         let Token::Text(text) = tokens.first()?.0 else {
+///             return None;
+///         };
+///         let keys = text.strip_prefix("{{kbd:")?.strip_suffix("}}")?;
+///         Some((Node::Text(keys.into()), 1))
+///     }
+/// }
+///
+/// let options = ParserOptions::default().with_inline_rule(Kbd);
+/// let ast = parser::parse_with_options("{{kbd:Ctrl+C}}", options).unwrap();
+/// let Node::Paragraph(paragraph) = &ast[0] else { unreachable!() };
+/// assert_eq!(paragraph.children, [Node::Text("Ctrl+C".into())]);
+/// ```
+pub trait InlineRule: Send + Sync {
+    /// Parses an element at the start of the given tokens, which run until
+    /// the end of the inline content. Returns the element along with the number
+    /// of tokens it spans, or `None` to leave the tokens to the other rules.
+    fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)>;
+}
+
+/// Custom block syntax, tried at the start of each line where a block may start,
+/// before any built-in block. Blocks don't interrupt a paragraph, like code fences do.
+///
+/// Rules may look at the rest of the document to decide where their block ends,
+/// so the document is lexed at once rather than on demand while parsing,
+/// and [`Document`](crate::document::Document) edits re-parse the blocks before them.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{
+///     ast::{CodeBlock, Node},
+///     parser::{self, BlockRule, ParserOptions},
+///     token::{Span, Token},
+/// };
+///
+/// /// Math blocks within `$$` lines, kept as code
+/// struct MathBlock;
+///
+/// impl BlockRule for MathBlock {
+///     fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)> {
+///         let is_fence = |idx: usize| {
+///             matches!(tokens.get(idx), Some((Token::Text("$$"), _)))
+///                 && matches!(tokens.get(idx + 1), Some((Token::Newline | Token::EndOfFile, _)))
+///         };
+///         if !is_fence(0) {
+///             return None;
+///         }
+///         let end = (2..tokens.len()).find(|&idx| tokens[idx - 1].0 == Token::Newline && is_fence(idx))?;
+///         let code = tokens[2..end]
+///             .iter()
+///             .map(|(token, _)| Node::Text(token.literal().into()))
+///             .collect();
+///         let block = CodeBlock {
+///             language: Some("math".to_string()),
+///             attrs: None,
+///             code,
+///             span: tokens[0].1..tokens[end].1,
+///         };
+///         Some((Node::CodeBlock(block), end + 1))
+///     }
+/// }
+///
+/// let options = ParserOptions::default().with_block_rule(MathBlock);
+/// let ast = parser::parse_with_options("$$\nx^2\n\ny\n$$\n\nAfter", options).unwrap();
+/// assert!(matches!(&ast[0], Node::CodeBlock(code) if code.language.as_deref() == Some("math")));
+/// assert!(matches!(ast[1], Node::Paragraph(_)));
+/// ```
+pub trait BlockRule: Send + Sync {
+    /// Parses a block at the start of the given tokens, which run from the start
+    /// of a line until the end of the enclosing element or document. Returns the block
+    /// along with the number of tokens it spans, or `None` to leave the tokens
+    /// to the other rules.
+    fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)>;
+}
+
+/// Set of syntax extensions on top of the core markdown syntax.
+/// Extensions are combined with `|` and removed with `-`.
+/// All of them are enabled by default.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::parser::Extensions;
+/// let extensions = Extensions::all() - Extensions::HTML_COMMENTS;
+/// assert!(!extensions.contains(Extensions::HTML_COMMENTS));
+/// assert_eq!(extensions | Extensions::HTML_COMMENTS, Extensions::all());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Extensions(u32);
+
+impl Extensions {
+    /// `<!-- -->` comments, parsed as text when disabled
+    pub const HTML_COMMENTS: Self = Self(1);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn all() -> Self {
+        Self(Self::HTML_COMMENTS.0)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl ops::BitOr for Extensions {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for Extensions {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl ops::Sub for Extensions {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
 
 /// Recursive Descent Parser for transforming
 /// the given list of tokens a DOM AST
-pub struct Parser<'source> {
+pub struct Parser<'tokens, 'source> {
     current: usize,
-    tokens: &'source [(Token<'source>, Span)],
+    tokens: &'tokens [(Token<'source>, Span)],
+    /// Markdown the tokens were lexed from, which text is borrowed from
+    /// as a whole when it spans several tokens. Empty when it isn't known,
+    /// and then text spanning several tokens is copied instead.
+    source: &'source str,
+    /// Spaces starting at each token position, see [`lexer::indentation_table`]
+    indentation: Cow<'tokens, [usize]>,
+    /// Nesting level of this parser within the parsers of its enclosing elements
+    depth: usize,
+    /// First error found while parsing, either by this parser or a nested one
+    error: Option<ParseError>,
+    /// Problems found while parsing, either by this parser or a nested one
+    diagnostics: Vec<Diagnostic>,
+    options: ParserOptions,
 }
 
-impl<'source> Parser<'source> {
-    pub fn new(tokens: &'source [(Token<'source>, Span)]) -> Self {
-        Self { tokens, current: 0 }
+impl<'tokens, 'source> Parser<'tokens, 'source> {
+    pub fn new(tokens: &'tokens [(Token<'source>, Span)], options: ParserOptions) -> Self {
+        Self {
+            tokens,
+            source: "",
+            indentation: Cow::Owned(lexer::indentation_table(tokens)),
+            current: 0,
+            depth: 0,
+            error: None,
+            diagnostics: Vec::new(),
+            options,
+        }
     }
 
     pub fn parse(&mut self) -> Vec<Node<'source>> {
         let mut nodes: Vec<Node<'source>> = Vec::new();
+        while let Some(node) = self.next_block() {
+            nodes.push(node);
+        }
+
+        nodes
+    }
+
+    /// Resumes parsing the blocks of a document from the given token position.
+    /// The indentation table of the tokens is kept by the caller,
+    /// rather than counted again for every block.
+    pub(crate) fn resume(
+        source: &'source str,
+        tokens: &'tokens [(Token<'source>, Span)],
+        indentation: &'tokens [usize],
+        position: usize,
+        options: ParserOptions,
+    ) -> Self {
+        Self {
+            tokens,
+            source,
+            indentation: Cow::Borrowed(indentation),
+            current: position,
+            depth: 0,
+            error: None,
+            diagnostics: Vec::new(),
+            options,
+        }
+    }
+
+    /// Parses the next block of the document, if there is any left
+    pub(crate) fn next_block(&mut self) -> Option<Node<'source>> {
         while !self.is_at_end() {
             if let Some(node) = self.block() {
-                nodes.push(node);
+                return Some(node);
             }
         }
 
+        None
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Errors and diagnostics found so far
+    pub(crate) fn into_problems(self) -> (Option<ParseError>, Vec<Diagnostic>) {
+        (self.error, self.diagnostics)
+    }
+
+    /// Parses the blocks within an element, like the content of a block quote
+    fn parse_nested_blocks(&mut self, tokens: &[(Token<'source>, Span)]) -> Vec<Node<'source>> {
+        let Some(mut parser) = self.nested_parser(tokens) else {
+            return verbatim_text(self.source, tokens);
+        };
+        let nodes = parser.parse();
+        self.absorb(parser);
         nodes
     }
 
-    /// Parser step for nested inline elements only.
-    /// Helpful for cases where we want to restrict parsing
-    /// for within a specific range of tokens within another inline element.
-    /// e.g. Links containing bold text and other allowed inline elements
-    fn parse_inline(&mut self) -> Vec<Node<'source>> {
+    /// Parser one nesting level deeper over the given tokens,
+    /// unless the maximum nesting depth has been reached.
+    fn nested_parser<'nested>(
+        &mut self,
+        tokens: &'nested [(Token<'source>, Span)],
+    ) -> Option<Parser<'nested, 'source>> {
+        if self.depth >= self.options.max_nesting_depth {
+            let start = tokens.first().map(|(_, span)| *span).unwrap_or_default();
+            let end = tokens.last().map(|(_, span)| *span).unwrap_or_default();
+            self.report_nesting_too_deep(start..end);
+            return None;
+        }
+
+        Some(Parser {
+            tokens,
+            source: self.source,
+            indentation: Cow::Owned(lexer::indentation_table(tokens)),
+            current: 0,
+            depth: self.depth + 1,
+            error: None,
+            diagnostics: Vec::new(),
+            options: self.options.clone(),
+        })
+    }
+
+    /// Reports elements nested too deep within the given token positions
+    fn nesting_too_deep(&mut self, range: Range<usize>) {
+        let span = self.span_between(range);
+        self.report_nesting_too_deep(span);
+    }
+
+    /// Only the first element nested too deep is reported,
+    /// since the ones within it are just as deep
+    fn report_nesting_too_deep(&mut self, span: Range<Span>) {
+        if self.error.is_none() {
+            let max_depth = self.options.max_nesting_depth;
+            self.diagnostics.push(Diagnostic::error(
+                format!("elements nested deeper than {} levels", max_depth),
+                span.clone(),
+            ));
+            self.error = Some(ParseError::NestingTooDeep {
+                span: span.start,
+                max_depth,
+            });
+        }
+    }
+
+    /// Keeps the errors and diagnostics found by a nested parser
+    fn absorb(&mut self, parser: Parser<'_, 'source>) {
+        self.error = self.error.take().or(parser.error);
+        self.diagnostics.extend(parser.diagnostics);
+    }
+
+    /// Reports a problem found in between the given token positions
+    fn warn(&mut self, message: &str, range: Range<usize>) {
+        let span = self.span_between(range);
+        self.diagnostics.push(Diagnostic::warning(message, span));
+    }
+
+    /// Source positions of the first and last tokens with content within
+    /// the given token positions, ignoring surrounding whitespace
+    fn span_between(&self, range: Range<usize>) -> Range<Span> {
+        let start = range.start.min(self.tokens.len());
+        let end = range.end.clamp(start, self.tokens.len());
+        let is_content = |(token, _): &&(Token, Span)| {
+            !matches!(token, Token::Space | Token::Newline | Token::EndOfFile)
+        };
+        let tokens = &self.tokens[start..end];
+        let first = tokens.iter().find(is_content).or(tokens.first());
+        let last = tokens.iter().rev().find(is_content).or(tokens.last());
+        match (first, last) {
+            (Some((_, first)), Some((_, last))) => *first..*last,
+            _ => {
+                let span = self.tokens.get(start).map(|(_, span)| *span);
+                let span = span.unwrap_or_default();
+                span..span
+            }
+        }
+    }
+
+    fn block(&mut self) -> Option<Node<'source>> {
+        self.skip_blank_lines();
+
+        // Block-level elements can only start as the very first token in a line
+        if self.is_at_line_start() {
+            if let Some(node) = self.custom_block() {
+                return Some(node);
+            }
+
+            // Four or more spaces of indentation are interpreted as code
+            let indentation = self.indentation_at(self.current);
+            if indentation >= 4 {
+                return self.indented_code_block();
+            }
+
+            // List markers take their indentation into account for the item content
+            if self.list_marker_at(self.current).is_some() {
+                return self.list();
+            }
+
+            // Otherwise, block-level elements can be indented by up to three spaces
+            self.current += indentation;
+            // Comments standing on their own line are block-level
+            let tokens = self.tokens;
+            if let Some((Token::Comment(comment), _)) = tokens.get(self.current) {
+                if self.has_extension(Extensions::HTML_COMMENTS)
+                    && self.is_blank_line_at(self.current + 1)
+                {
+                    self.rewind(self.line_end(self.current));
+                    return Some(Node::Comment(comment_content(comment).into()));
+                }
+            }
+            match self.peek_token() {
+                Some(Token::Hash) => return self.maybe_heading(),
+                Some(Token::GreaterThan) => return self.blockquote(),
+                _ if self.is_thematic_break_at(self.current) => return self.thematic_break(),
+                Some(Token::Backtick) if self.fence_at(self.current).is_some() => {
+                    return self.code_block(indentation)
+                }
+                _ => {}
+            }
+        }
+
+        self.maybe_paragraph()
+    }
+
+    /// Block parsed by the first custom block rule matching at the current position
+    fn custom_block(&mut self) -> Option<Node<'source>> {
+        let tokens = &self.tokens[self.current..];
+        let (node, length) = self.options.block_rules.iter().find_map(|rule| {
+            rule.parse(tokens)
+                .filter(|(_, length)| (1..=tokens.len()).contains(length))
+        })?;
+        self.rewind(self.current + length);
+        Some(node)
+    }
+
+    fn thematic_break(&mut self) -> Option<Node<'source>> {
+        self.rewind(self.line_end(self.current));
+        Some(Node::ThematicBreak)
+    }
+
+    fn indented_code_block(&mut self) -> Option<Node<'source>> {
+        let start = self.current;
+        let mut code = Vec::new();
+        while !self.is_end_of_content(self.current) {
+            if self.is_blank_line_at(self.current) {
+                // Blank lines only belong to the code when followed by more indented code
+                let next_line = self.next_non_blank_line(self.current);
+                if self.indentation_at(next_line) < 4 || self.is_end_of_content(next_line) {
+                    break;
+                }
+                while self.current < next_line {
+                    self.current += self.indentation_at(self.current).min(4);
+                    self.copy_line(&mut code);
+                }
+            } else if self.indentation_at(self.current) >= 4 {
+                self.current += 4;
+                self.copy_line(&mut code);
+            } else {
+                break;
+            }
+        }
+
+        Some(Node::CodeBlock(CodeBlock {
+            language: None,
+            attrs: None,
+            code: self.code_text(&code),
+            span: self.span_between(start..self.current),
+        }))
+    }
+
+    /// Fenced code blocks strip the indentation of the opening fence from their content
+    fn code_block(&mut self, fence_indentation: usize) -> Option<Node<'source>> {
+        let fence_start = self.current;
+        let fence_length = self.fence_at(self.current)?;
+        self.current += fence_length;
+
+        let info_end = self.line_end(self.current);
+        let info: String = self.tokens[self.current..info_end]
+            .iter()
+            .map(|(t, _)| t.literal())
+            .collect();
+        let (language, attrs) = parse_info_string(&info);
+        self.rewind(info_end);
+        self.match_token(Token::Newline);
+
+        let mut code = Vec::new();
+        let mut is_closed = false;
+        while !self.is_end_of_content(self.current) {
+            if self.is_closing_fence_at(self.current, fence_length) {
+                self.rewind(self.line_end(self.current));
+                is_closed = true;
+                break;
+            }
+            self.current += self.indentation_at(self.current).min(fence_indentation);
+            self.copy_line(&mut code);
+        }
+
+        // The code block carries on until the end of its container
+        if !is_closed {
+            self.warn("code block is never closed", fence_start..self.current);
+        }
+
+        Some(Node::CodeBlock(CodeBlock {
+            language,
+            attrs,
+            code: self.code_text(&code),
+            span: self.span_between(fence_start..self.current),
+        }))
+    }
+
+    /// Text of the tokens of a code block. Tabs are kept as they were written
+    /// rather than as the spaces they were expanded into, unless some of those spaces
+    /// were taken as indentation.
+    fn code_text(&self, code: &[(Token<'source>, Span)]) -> Vec<Node<'source>> {
         let mut nodes = Vec::new();
+        let mut idx = 0;
+        while idx < code.len() {
+            let (token, span) = &code[idx];
+            if token != &Token::Space || !span.tab {
+                nodes.push(Node::Text(token.literal().into()));
+                idx += 1;
+                continue;
+            }
+            // The spaces of a tab all span the tab itself
+            let tab_spaces = |tokens: &[(Token, Span)]| {
+                tokens
+                    .iter()
+                    .take_while(|(t, s)| t == &Token::Space && s.start == span.start)
+                    .count()
+            };
+            let spaces = tab_spaces(&code[idx..]);
+            let first = self.tokens.partition_point(|(_, s)| s.start < span.start);
+            match spaces == tab_spaces(&self.tokens[first..]) {
+                true => nodes.push(Node::Text("\t".into())),
+                false => nodes.extend((0..spaces).map(|_| Node::Text(" ".into()))),
+            }
+            idx += spaces;
+        }
+        nodes
+    }
+
+    fn blockquote(&mut self) -> Option<Node<'source>> {
+        let start = self.current;
+        let mut content = Vec::new();
+        let mut lazy = false;
         while !self.is_at_end() {
-            if let Some(node) = self.inline() {
-                nodes.push(node);
+            let indentation = self.indentation_at(self.current);
+            let marker = self.tokens.get(self.current + indentation);
+            if indentation <= 3 && marker.is_some_and(|(t, _)| t == &Token::GreaterThan) {
+                self.current += indentation + 1;
+                self.match_token(Token::Space);
+                lazy = self.copy_line(&mut content);
+            } else if lazy
+                && !self.is_blank_line_at(self.current)
+                && !self.starts_block_at(self.current)
+            {
+                // Lazy continuation: a paragraph within the quote
+                // can carry on in the next line without the `>` prefix
+                self.copy_line(&mut content);
+            } else {
+                break;
             }
         }
 
-        nodes
+        Some(Node::BlockQuote(BlockQuote {
+            children: self.parse_nested_blocks(&content),
+            span: self.span_between(start..self.current),
+        }))
     }
 
-    fn block(&mut self) -> Option<Node<'source>> {
-        while self.check(&Token::Newline) {
-            self.consume(&Token::Newline);
+    fn list(&mut self) -> Option<Node<'source>> {
+        let start = self.current;
+        let first_marker = self.list_marker_at(self.current)?;
+        let mut items = Vec::new();
+
+        while let Some(marker) = self.list_marker_at(self.current) {
+            if !marker.is_same_list(&first_marker) {
+                break;
+            }
+
+            let item_start = self.current;
+            self.current += marker.length;
+            let content = self.list_item_content(marker.width);
+            items.push(Node::ListItem(ListItem {
+                children: self.parse_nested_blocks(&content),
+                span: self.span_between(item_start..self.current),
+            }));
+
+            // Blank lines in between items of the same list are allowed
+            let rewind_position = self.current;
+            self.skip_blank_lines();
+            if !self
+                .list_marker_at(self.current)
+                .is_some_and(|next| next.is_same_list(&first_marker))
+            {
+                self.rewind(rewind_position);
+                break;
+            }
         }
 
-        // Headings can only start as the very first token in a line
-        if let Some(&(Token::Hash, span)) = self.peek() {
-            if span.col == 1 {
-                return self.maybe_heading();
+        Some(Node::List(List {
+            ordered: first_marker.ordered,
+            // Only the number of the first item matters, the following ones are ignored
+            start: first_marker.start,
+            children: items,
+            span: self.span_between(start..self.current),
+        }))
+    }
+
+    /// Collects the tokens belonging to a list item, with the item
+    /// indentation stripped so they can be parsed as standalone blocks.
+    fn list_item_content(&mut self, width: usize) -> Vec<(Token<'source>, Span)> {
+        let mut content = Vec::new();
+        let mut lazy = self.copy_line(&mut content);
+
+        while !self.is_at_end() && !self.check(&Token::EndOfFile) {
+            if self.is_blank_line_at(self.current) {
+                // Blank lines only belong to the item when
+                // followed by more indented content
+                let next_line = self.next_non_blank_line(self.current);
+                if self.indentation_at(next_line) < width || self.is_end_of_content(next_line) {
+                    break;
+                }
+                while self.current < next_line {
+                    self.copy_line(&mut content);
+                }
+                lazy = false;
+            } else if self.indentation_at(self.current) >= width {
+                self.current += width;
+                lazy = self.copy_line(&mut content);
+            } else if lazy
+                && !self.starts_block_at(self.current)
+                && self.list_marker_at(self.current).is_none()
+            {
+                // Lazy continuation of the item's paragraph
+                self.copy_line(&mut content);
+            } else {
+                break;
             }
         }
 
-        self.maybe_paragraph()
+        content
     }
 
     fn maybe_heading(&mut self) -> Option<Node<'source>> {
-        let mut heading_level: u8 = 0;
+        let start = self.current;
+        // Counted as `usize`, since nothing bounds the length of a run of hashes
+        let mut heading_level: usize = 0;
         while self.match_token(Token::Hash) {
             heading_level += 1;
         }
 
         if heading_level > 0 && heading_level <= 6 && self.match_token(Token::Space) {
-            let mut inline_elements = Vec::new();
-            while let Some(inline) = self.inline() {
-                if inline == Node::LineBreak {
-                    break;
-                }
-                inline_elements.push(inline)
-            }
+            let line_end = self.line_end(self.current);
+            let content_range = self.heading_content_range(self.current, line_end);
+            let inline_elements = self.inline_elements(
+                content_range,
+                self.options
+                    .max_nesting_depth
+                    .saturating_sub(self.depth + 1),
+            );
+            self.rewind(line_end);
+
             return Some(Node::Header(Header {
-                level: heading_level,
+                level: heading_level as u8,
                 children: inline_elements,
+                span: self.span_between(start..line_end),
             }));
         }
 
@@ -134,25 +832,61 @@ impl<'source> Parser<'source> {
         // we know they are not valid header levels
         // so let's rewind and let them be handled as normal text
         if heading_level > 0 {
-            self.step_back(heading_level as usize);
+            self.step_back(heading_level);
         }
 
         self.maybe_paragraph()
     }
 
+    /// Trims the spaces around the heading content as well as the
+    /// optional closing sequence of hashes, e.g. `## Title ##`
+    fn heading_content_range(&self, start: usize, end: usize) -> Range<usize> {
+        let is_token_at = |position: usize, token: &Token| &self.tokens[position].0 == token;
+        let is_space_at = |position: usize| is_token_at(position, &Token::Space);
+
+        let mut start = start;
+        while start < end && is_space_at(start) {
+            start += 1;
+        }
+        let mut end = end;
+        while end > start && is_space_at(end - 1) {
+            end -= 1;
+        }
+
+        // The closing sequence must be preceded by a space
+        let mut closing_start = end;
+        while closing_start > start && is_token_at(closing_start - 1, &Token::Hash) {
+            closing_start -= 1;
+        }
+        if closing_start < end && (closing_start == start || is_space_at(closing_start - 1)) {
+            end = closing_start;
+            while end > start && is_space_at(end - 1) {
+                end -= 1;
+            }
+        }
+
+        start..end
+    }
+
     fn maybe_paragraph(&mut self) -> Option<Node<'source>> {
         // A paragraph might or might not start with a newline
         // @TODO: Add newlines before paragraphs as linebreak nodes?
         // So we just consume newlines outside of a paragraph and discard them.
         // I might need to revisit this and add Linebreak as a inline node?
         while self.check(&Token::Newline) && !self.is_at_end() {
-            self.consume(&Token::Newline);
+            self.match_token(Token::Newline);
         }
 
-        let mut inline_elements = Vec::new();
-
-        while let Some(inline) = self.inline() {
-            inline_elements.push(inline);
+        let start = self.current;
+        let end = self.paragraph_end(start);
+        let inline_elements = self.inline_elements(
+            start..end,
+            self.options.max_nesting_depth.saturating_sub(self.depth),
+        );
+        self.rewind(end);
+        // The end of the file belongs to the last paragraph
+        if self.check(&Token::EndOfFile) {
+            self.advance();
         }
 
         if inline_elements.is_empty() {
@@ -161,203 +895,262 @@ impl<'source> Parser<'source> {
 
         Some(Node::Paragraph(Paragraph {
             children: inline_elements,
+            span: self.span_between(start..self.current),
         }))
     }
 
-    fn inline(&mut self) -> Option<Node<'source>> {
-        if self.is_at_end() {
-            return None;
-        }
-
-        if let Some((token, _)) = self.peek() {
-            let node = match token {
-                // Hitting end of the file, just advance and halt
-                Token::EndOfFile => {
-                    self.advance();
-                    return None;
+    /// Position where the paragraph starting at the given position ends:
+    /// the end of the file, a blank line, or the line break before
+    /// a block element interrupting the paragraph
+    fn paragraph_end(&self, start: usize) -> usize {
+        let mut position = start;
+        while let Some((token, _)) = self.tokens.get(position) {
+            match token {
+                Token::EndOfFile => break,
+                Token::Newline
+                    if self
+                        .tokens
+                        .get(position + 1)
+                        .is_some_and(|(t, _)| t == &Token::Newline)
+                        || self.starts_block_at(position + 1) =>
+                {
+                    break
                 }
-                // Two consecutive newlines should break off from any inline elements
-                // and give it a chance to a new block or inline element to be constructed
-                Token::Newline if self.check_next(Token::Newline) => {
-                    return None;
-                }
-                Token::Newline => Node::LineBreak,
-                Token::Star => return self.maybe_bold(),
-                Token::LeftSquareBracket => return self.maybe_link(),
-                Token::Text(_)
-                | Token::Digit(_)
-                | Token::Space
-                | Token::Dash
-                | Token::Dot
-                | Token::Underscore
-                | Token::Bang
-                | Token::Hash
-                | Token::LeftParen
-                | Token::RightParen
-                | Token::RightSquareBracket
-                | Token::Backslash => Node::Text(token.literal()),
-                // block-level tokens should be interpreted outside of the inline loop
-                // to give them a chance of being interpreted as block-level elements
-                t if t.is_block_level_token() => return None,
-                t => todo!("Token not handled yet: {}", t),
-            };
-            self.advance();
-            return Some(node);
+                _ => position += 1,
+            }
         }
+        position
+    }
 
-        None
+    fn has_extension(&self, extension: Extensions) -> bool {
+        self.options.extensions.contains(extension)
     }
 
-    fn maybe_link(&mut self) -> Option<Node<'source>> {
-        let mut marker = LinkMarker::new();
-        let rewind_position = self.current;
-        let mut steps = 0;
-        // Any inline element can partially show-up and should be represented as text,
-        // but if we find the right token makers that can complete a link, we should
-        // rewind and structure it as a Link inline node instead.
-        while !marker.is_link() && !self.is_at_end() {
-            if let Some((next, _)) = self.advance() {
-                steps += 1;
-                match next {
-                    Token::LeftSquareBracket if marker.is_empty() => {
-                        marker.set_start_text(self.current)
-                    }
-                    // The closing text of a link must be followed by "]("
-                    Token::RightSquareBracket if marker.has_open_text() => {
-                        if self.peek_token().is_some_and(|t| t == &Token::LeftParen) {
-                            marker.set_end_text(self.current - 1);
-                            marker.set_start_url(self.current + 1);
-                        }
-                    }
-                    Token::RightParen if marker.has_open_url() => {
-                        marker.set_end_url(self.current - 1)
-                    }
-                    token if token == &Token::Newline => {
-                        if let Some(&(Token::Newline, _)) = self.peek() {
-                            break;
-                        }
-                    }
-                    _ => {}
-                };
-            }
+    /// Number of consecutive delimiter tokens starting at the given position
+    fn delimiter_run_at(&self, position: usize) -> usize {
+        match self.tokens.get(position) {
+            Some((delimiter, _)) => self.tokens[position..]
+                .iter()
+                .take_while(|(t, _)| t == delimiter)
+                .count(),
+            None => 0,
         }
+    }
 
-        self.rewind(rewind_position);
-
-        // We are guaranteed to have a well-structured link here
-        // lets force-consume all the special tokens
-        if let Some((text_range, url_range)) = marker.ranges() {
-            let mut text_parser = Self::new(&self.tokens[text_range]);
-            let text_nodes = text_parser.parse_inline();
+    /// Whether a block element other than a paragraph
+    /// starts at the given token position
+    fn starts_block_at(&self, position: usize) -> bool {
+        // Indented code blocks can't interrupt a paragraph
+        let indentation = self.indentation_at(position);
+        if indentation > 3 {
+            return false;
+        }
 
-            let mut url_parser = Self::new(&self.tokens[url_range]);
-            let url_nodes = url_parser.parse_inline();
-            self.current += steps;
+        let position = position + indentation;
+        match self.tokens.get(position) {
+            Some((Token::GreaterThan, _)) => true,
+            Some((Token::Backtick, _)) => self.fence_at(position).is_some(),
+            _ if self.is_thematic_break_at(position) => true,
+            Some((Token::Hash, _)) => {
+                let mut level = 0;
+                while self
+                    .tokens
+                    .get(position + level)
+                    .is_some_and(|(t, _)| t == &Token::Hash)
+                {
+                    level += 1;
+                }
+                level <= 6
+                    && self
+                        .tokens
+                        .get(position + level)
+                        .is_some_and(|(t, _)| t == &Token::Space)
+            }
+            // Only ordered lists starting at 1 can interrupt a paragraph
+            _ => self
+                .list_marker_at(position)
+                .is_some_and(|marker| !marker.ordered || marker.start == Some(1)),
+        }
+    }
 
-            let link = Node::Link(Link {
-                children: text_nodes,
-                url: url_nodes,
-            });
+    /// Detects an opening code fence of at least three backticks,
+    /// returning the length of the fence
+    fn fence_at(&self, position: usize) -> Option<usize> {
+        if self
+            .tokens
+            .get(position)
+            .is_none_or(|(t, _)| t != &Token::Backtick)
+        {
+            return None;
+        }
+        let fence_length = self.delimiter_run_at(position);
+        // Backticks are not allowed in the info string
+        let has_backtick_info = self.tokens[position + fence_length..self.line_end(position)]
+            .iter()
+            .any(|(t, _)| t == &Token::Backtick);
+        if fence_length < 3 || has_backtick_info {
+            return None;
+        }
+        Some(fence_length)
+    }
 
-            return Some(link);
-        }
-
-        // Otherwise we bail, rewind and let the next loop handle
-        // each token as as normal text or other inline elements
-        self.consume(&Token::LeftSquareBracket);
-        Some(Node::Text(Token::LeftSquareBracket.literal()))
-    }
-
-    fn maybe_bold(&mut self) -> Option<Node<'source>> {
-        let rewind_position = self.current;
-        let mut marker = InlineMarker::new();
-        let mut steps = 0;
-
-        while !marker.is_closed() && !self.is_at_end() {
-            steps += 1;
-            if let Some((next, _)) = self.advance() {
-                match next {
-                    Token::Star => {
-                        if self.check(&Token::Star) {
-                            if marker.is_empty()
-                                && !self.peek_next_token().is_some_and(|t| {
-                                    t == &Token::Space
-                                        || t == &Token::Newline
-                                        || t == &Token::EndOfFile
-                                })
-                            {
-                                marker.open(self.current + 1);
-                            } else if marker.is_open()
-                                && !self.tokens.get(self.current - 2).is_some_and(|(t, _)| {
-                                    t == &Token::Space
-                                        || t == &Token::Newline
-                                        || t == &Token::EndOfFile
-                                })
-                            {
-                                marker.close(self.current - 1);
-                                steps += 1;
-                                break;
-                            } else {
-                                break;
-                            }
-                        }
-                    }
-                    // Two consecutive newlines should break out from the inline element
-                    Token::Newline => {
-                        if self.check_next(Token::Newline) {
-                            break;
-                        }
-                    }
+    /// A closing fence has at least as many backticks as
+    /// the opening one and nothing else but spaces after it
+    fn is_closing_fence_at(&self, position: usize, fence_length: usize) -> bool {
+        let indentation = self.indentation_at(position);
+        if indentation > 3 {
+            return false;
+        }
+        let position = position + indentation;
+        let length = self.delimiter_run_at(position);
+        let rest = position + length;
+        self.tokens
+            .get(position)
+            .is_some_and(|(t, _)| t == &Token::Backtick)
+            && length >= fence_length
+            && self.line_end(rest) == rest + self.indentation_at(rest)
+    }
 
-                    // If we enter the potential inner elements of bold element
-                    // and they are not following a `**`, this is not a bold element.
-                    _t if marker.is_empty() => break,
+    /// Detects a line made of three or more matching `*`, `-` or `_`,
+    /// optionally separated by spaces, e.g. `* * *`
+    fn is_thematic_break_at(&self, position: usize) -> bool {
+        let indentation = self.indentation_at(position);
+        if indentation > 3 {
+            return false;
+        }
+        let line = &self.tokens[position.min(self.tokens.len())..self.line_end(position)];
+        let Some((marker, _)) = line.get(indentation) else {
+            return false;
+        };
+        if !matches!(marker, Token::Star | Token::Dash | Token::Underscore) {
+            return false;
+        }
 
-                    // Any other token should move along as they can be nested within
-                    // the bold text as just text or inner inline elements
-                    _t => {}
-                };
+        let mut count = 0;
+        for (token, _) in line.iter() {
+            if token == marker {
+                count += 1;
+            } else if token != &Token::Space {
+                return false;
             }
         }
+        count >= 3
+    }
 
-        self.rewind(rewind_position);
-
-        match marker.range() {
-            Some(bold_text_range) if !bold_text_range.is_empty() => {
-                let t = &self.tokens[bold_text_range];
-                let mut text_parser = Self::new(t);
-                let text_nodes = text_parser.parse_inline();
-
-                self.current += steps;
+    /// Detects list item markers like `- `, `* ` or `1. `
+    fn list_marker_at(&self, position: usize) -> Option<ListMarker<'source>> {
+        // Lines like `* * *` are thematic breaks rather than list items
+        if self.is_thematic_break_at(position) {
+            return None;
+        }
+        let indentation = self.indentation_at(position);
+        if indentation > 3 {
+            return None;
+        }
+        let position = position + indentation;
+        let token_at = |offset: usize| self.tokens.get(position + offset).map(|(t, _)| t);
+        match token_at(0)? {
+            bullet @ (Token::Dash | Token::Star) if token_at(1) == Some(&Token::Space) => {
+                Some(ListMarker {
+                    ordered: false,
+                    delimiter: bullet.literal(),
+                    start: None,
+                    width: indentation + 1 + self.marker_spacing_at(position + 1),
+                    length: indentation + 1 + self.marker_spacing_at(position + 1),
+                })
+            }
+            Token::Number(number) => {
+                if number.len() > 9
+                    || token_at(1) != Some(&Token::Dot)
+                    || token_at(2) != Some(&Token::Space)
+                {
+                    return None;
+                }
+                let spacing = self.marker_spacing_at(position + 2);
+                Some(ListMarker {
+                    ordered: true,
+                    delimiter: Token::Dot.literal(),
+                    start: number.parse().ok(),
+                    width: indentation + number.len() + 1 + spacing,
+                    length: indentation + 2 + spacing,
+                })
+            }
+            _ => None,
+        }
+    }
 
-                let bold = Node::Bold(Bold {
-                    children: text_nodes,
-                });
+    /// Spaces between a list marker and the item content. Content indented
+    /// by more than 4 spaces only takes a single space as part of the marker.
+    fn marker_spacing_at(&self, position: usize) -> usize {
+        match self.indentation_at(position) {
+            spaces @ 1..=4 => spaces,
+            _ => 1,
+        }
+    }
 
-                Some(bold)
+    /// Copies the tokens from the current position until the end of the line,
+    /// including the line ending, and returns whether the line had any content.
+    fn copy_line(&mut self, buffer: &mut Vec<(Token<'source>, Span)>) -> bool {
+        let tokens = self.tokens;
+        let mut has_content = false;
+        while let Some((token, span)) = tokens.get(self.current) {
+            if token == &Token::EndOfFile {
+                break;
             }
-            _ => {
-                // Otherwise we bail, rewind and let the next loop handle each token
-                // be handled as normal text or other inline elements
-                self.consume(&Token::Star);
-                Some(Node::Text(Token::Star.literal()))
+            has_content = has_content || !matches!(token, Token::Space | Token::Newline);
+            buffer.push((token.clone(), *span));
+            self.current += 1;
+            if token == &Token::Newline {
+                break;
             }
         }
+        has_content
     }
 
-    fn consume(&mut self, kind: &Token) -> &Token {
-        if let Some(token) = self.advance() {
-            if token.0 == *kind {
-                return &token.0;
-            }
+    /// Position of the newline token ending the line that contains the given position
+    fn line_end(&self, position: usize) -> usize {
+        // Lookahead may step past the end of the tokens
+        let mut end = position.min(self.tokens.len());
+        while self
+            .tokens
+            .get(end)
+            .is_some_and(|(t, _)| t != &Token::Newline && t != &Token::EndOfFile)
+        {
+            end += 1;
+        }
+        end
+    }
 
-            panic!(
-                "Invalid next token to consume. expected={:#?} found={:#?} span={:#?}",
-                kind, token.0, token.1
-            );
+    fn is_blank_line_at(&self, position: usize) -> bool {
+        self.line_end(position) == position.min(self.tokens.len()) + self.indentation_at(position)
+    }
+
+    /// Position of the first line with content from the given position on
+    fn next_non_blank_line(&self, position: usize) -> usize {
+        let mut next_line = position;
+        while self.is_blank_line_at(next_line) && !self.is_end_of_content(next_line) {
+            next_line = self.line_end(next_line) + 1;
         }
+        next_line
+    }
 
-        panic!("Could not consume next token kind={}", kind)
+    fn skip_blank_lines(&mut self) {
+        self.rewind(self.next_non_blank_line(self.current));
+    }
+
+    fn is_end_of_content(&self, position: usize) -> bool {
+        self.tokens
+            .get(position)
+            .is_none_or(|(t, _)| t == &Token::EndOfFile)
+    }
+
+    /// Number of leading spaces of the line starting at the given position
+    fn indentation_at(&self, position: usize) -> usize {
+        self.indentation.get(position).copied().unwrap_or(0)
+    }
+
+    fn is_at_line_start(&self) -> bool {
+        self.current == 0 || self.previous().is_some_and(|(t, _)| t == &Token::Newline)
     }
 
     fn advance(&mut self) -> Option<&(Token<'source>, Span)> {
@@ -397,29 +1190,11 @@ impl<'source> Parser<'source> {
         }
     }
 
-    fn peek_next_token(&self) -> Option<&Token> {
-        match self.peek_next() {
-            Some((token, _)) => Some(token),
-            None => None,
-        }
-    }
-
-    /// Get the next token in line, but do not consume it
-    fn peek_next(&self) -> Option<&(Token<'source>, Span)> {
-        self.tokens.get(self.current + 1)
-    }
-
     /// Compare the current token, but do not consume it.
     fn check(&self, token: &Token) -> bool {
         self.peek().is_some_and(|t| t.0 == *token)
     }
 
-    /// Compare the given token to the next one in line
-    /// but do not consume it.
-    fn check_next(&self, token: Token) -> bool {
-        self.peek_next().is_some_and(|t| t.0 == token)
-    }
-
     /// Compare the given token to the next one in line
     /// and consume it
     fn match_token(&mut self, expected: Token) -> bool {
@@ -440,129 +1215,470 @@ impl<'source> Parser<'source> {
     }
 }
 
+/// Describes the marker of a list item, e.g. `-` or `2.`
 #[derive(Debug)]
-struct LinkMarker {
-    start_text: Option<usize>,
-    end_text: Option<usize>,
-    start_url: Option<usize>,
-    end_url: Option<usize>,
+struct ListMarker<'source> {
+    ordered: bool,
+    delimiter: &'source str,
+    /// Number of an ordered list item
+    start: Option<u32>,
+    /// Number of columns taken by the marker, including the spaces after it
+    width: usize,
+    /// Number of tokens taken by the marker, including the spaces after it
+    length: usize,
 }
 
-/// helful for holding the boundaries of a Link element during parsing
-impl LinkMarker {
-    fn new() -> Self {
-        Self {
-            start_text: None,
-            end_text: None,
-            start_url: None,
-            end_url: None,
-        }
+impl<'source> ListMarker<'source> {
+    /// Items only belong to the same list when their markers are alike
+    fn is_same_list(&self, other: &ListMarker) -> bool {
+        self.ordered == other.ordered && self.delimiter == other.delimiter
     }
+}
 
-    fn set_start_text(&mut self, index: usize) {
-        self.start_text = Some(index);
+/// Splits the info string of a code fence into its language and raw attributes.
+/// Both ` ```rust {linenos} ` and ` ```{.rust linenos} ` are supported.
+fn parse_info_string(info: &str) -> (Option<String>, Option<String>) {
+    let info = info.trim();
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+    if let Some(inner) = info.strip_prefix('{').and_then(|i| i.strip_suffix('}')) {
+        let inner = inner.trim();
+        let language = inner
+            .split_whitespace()
+            .find_map(|part| part.strip_prefix('.'));
+        let attrs = match language {
+            Some(language) => inner.replacen(&format!(".{}", language), "", 1),
+            None => inner.to_string(),
+        };
+        return (language.and_then(non_empty), non_empty(attrs.trim()));
     }
 
-    fn set_end_text(&mut self, index: usize) {
-        self.end_text = Some(index);
+    let (language, rest) = info.split_once(char::is_whitespace).unwrap_or((info, ""));
+    let rest = rest.trim();
+    let attrs = rest
+        .strip_prefix('{')
+        .and_then(|r| r.strip_suffix('}'))
+        .unwrap_or(rest);
+    (non_empty(language), non_empty(attrs.trim()))
+}
+
+/// Tokens taken as they are in the source, without any markdown structure
+fn verbatim_text<'source>(
+    source: &'source str,
+    tokens: &[(Token<'source>, Span)],
+) -> Vec<Node<'source>> {
+    let mut nodes = Vec::new();
+    for token in tokens {
+        let text = source_text(source, token);
+        push_inline(&mut nodes, Node::Text(text.into()), source);
     }
+    nodes
+}
 
-    fn set_start_url(&mut self, index: usize) {
-        self.start_url = Some(index);
+/// Text of the token, borrowed from the source when it's known rather than
+/// from the literal of the token, so that it can be joined with the text around it
+fn source_text<'source>(
+    source: &'source str,
+    (token, span): &(Token<'source>, Span),
+) -> &'source str {
+    let literal = token.literal();
+    // Line endings are lexed as newlines whatever they are in the source
+    match source.get(span.bytes()) {
+        Some(text) if text == literal => text,
+        _ => literal,
     }
+}
 
-    fn set_end_url(&mut self, index: usize) {
-        self.end_url = Some(index);
+/// Appends an inline element, merging consecutive text into a single node
+/// instead of keeping one node per token. Text that directly follows
+/// the previous one in the source stays borrowed from it, while escaped
+/// characters and text around them are copied into an owned string.
+fn push_inline<'source>(nodes: &mut Vec<Node<'source>>, node: Node<'source>, source: &'source str) {
+    if let (Some(Node::Text(text)), Node::Text(next)) = (nodes.last_mut(), &node) {
+        let joined = match (&*text, next) {
+            (Cow::Borrowed(first), Cow::Borrowed(second)) => join_in_source(source, first, second),
+            _ => None,
+        };
+        match joined {
+            Some(joined) => *text = Cow::Borrowed(joined),
+            None => text.to_mut().push_str(next),
+        }
+        return;
     }
+    nodes.push(node);
+}
 
-    fn is_link(&self) -> bool {
-        self.start_text.is_some()
-            && self.end_text.is_some()
-            && self.start_url.is_some()
-            && self.end_url.is_some()
+/// Slice of the source spanning both texts, when they're both within it
+/// and the second one starts right where the first one ends
+fn join_in_source<'source>(
+    source: &'source str,
+    first: &str,
+    second: &str,
+) -> Option<&'source str> {
+    let start = (first.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    let end = start + first.len();
+    if second.as_ptr() as usize != source.as_ptr() as usize + end {
+        return None;
     }
+    source.get(start..end + second.len())
+}
+
+/// Strips the `<!--` and `-->` delimiters from a comment token
+fn comment_content(comment: &str) -> &str {
+    &comment["<!--".len()..comment.len() - "-->".len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
 
-    fn is_empty(&self) -> bool {
-        self.start_text.is_none()
-            && self.end_text.is_none()
-            && self.start_url.is_none()
-            && self.end_url.is_none()
+    use crate::{
+        ast::{collect_text, Link},
+        diagnostic::Severity,
+        lexer::Lexer,
+        token,
+    };
+
+    use super::*;
+
+    #[test]
+    fn parse_code_fence_info_string() {
+        let cases = [
+            ("rust", (Some("rust"), None)),
+            (
+                "rust {linenos startline=5}",
+                (Some("rust"), Some("linenos startline=5")),
+            ),
+            (
+                "{.rust linenos startline=5}",
+                (Some("rust"), Some("linenos startline=5")),
+            ),
+            ("{linenos}", (None, Some("linenos"))),
+            ("", (None, None)),
+        ];
+        for (info, (language, attrs)) in cases {
+            assert_eq!(
+                parse_info_string(info),
+                (language.map(String::from), attrs.map(String::from)),
+                "info: {}",
+                info
+            );
+        }
     }
 
-    fn has_open_text(&self) -> bool {
-        self.start_text.is_some()
-            && self.end_text.is_none()
-            && self.start_url.is_none()
-            && self.end_url.is_none()
+    #[test]
+    fn parse_only_enabled_extensions() {
+        let markdown = "<!-- block -->\n\nSome <!-- inline --> text <!-- open";
+        let without_comments = ParserOptions {
+            extensions: Extensions::all() - Extensions::HTML_COMMENTS,
+            ..Default::default()
+        };
+        let (ast, diagnostics) = {
+            let mut events = EventIter::with_options(markdown, without_comments);
+            (events::build_ast(&mut events), events.into_diagnostics())
+        };
+        assert!(diagnostics.is_empty());
+
+        let mut text = String::new();
+        collect_text(&mut text, &ast);
+        assert_eq!(text, "<!-- block -->Some <!-- inline --> text <!-- open");
+
+        let ast = parse(markdown).unwrap();
+        assert!(matches!(&ast[0], Node::Comment(comment) if comment == " block "));
     }
 
-    fn has_open_url(&self) -> bool {
-        self.start_text.is_some()
-            && self.end_text.is_some()
-            && self.start_url.is_some()
-            && self.end_url.is_none()
+    #[test]
+    fn try_custom_inline_rules_first() {
+        /// Wiki links like `[[Page]]`, which would otherwise be bracketed text
+        struct WikiLink;
+
+        impl InlineRule for WikiLink {
+            fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)> {
+                use Token::{LeftSquareBracket as Open, RightSquareBracket as Close, Text};
+                let (page, start, end) = match tokens {
+                    [(Open, start), (Open, _), (Text(page), _), (Close, _), (Close, end), ..] => {
+                        (page, start, end)
+                    }
+                    _ => return None,
+                };
+                let link = Link {
+                    children: vec![Node::Text((*page).into())],
+                    url: vec![Node::Text(format!("/{}", page).into())],
+                    title: None,
+                    span: *start..*end,
+                };
+                Some((Node::Link(link), 5))
+            }
+        }
+
+        let options = ParserOptions::default().with_inline_rule(WikiLink);
+        let ast = parse_with_options("See [[Gohan]] and *[[Docs]]*", options).unwrap();
+        let Node::Paragraph(paragraph) = &ast[0] else {
+            panic!("expected a paragraph, found {:?}", ast[0]);
+        };
+        let Node::Link(link) = &paragraph.children[1] else {
+            panic!("expected a link, found {:?}", paragraph.children[1]);
+        };
+        assert_eq!(link.url, [Node::Text("/Gohan".into())]);
+        let Node::Italic(italic) = &paragraph.children[3] else {
+            panic!("expected italic text, found {:?}", paragraph.children[3]);
+        };
+        assert!(matches!(italic.children[0], Node::Link(_)));
     }
 
-    /// given a complete link, extract the ranges of its inner components
-    fn ranges(&self) -> Option<(Range<usize>, Range<usize>)> {
-        match (self.start_text, self.end_text, self.start_url, self.end_url) {
-            (Some(text_start), Some(text_end), Some(url_start), Some(url_end)) => {
-                Some((text_start..text_end, url_start..url_end))
+    #[test]
+    fn try_custom_block_rules_first() {
+        /// Blocks within `:::` lines, parsed as a block quote
+        struct Directive;
+
+        impl BlockRule for Directive {
+            fn parse<'s>(&self, tokens: &[(Token<'s>, Span)]) -> Option<(Node<'s>, usize)> {
+                let is_fence = |idx: usize| {
+                    matches!(tokens.get(idx), Some((Token::Text(":::"), _)))
+                        && matches!(
+                            tokens.get(idx + 1),
+                            Some((Token::Newline | Token::EndOfFile, _))
+                        )
+                };
+                if !is_fence(0) {
+                    return None;
+                }
+                let end = (2..tokens.len())
+                    .find(|&idx| tokens[idx - 1].0 == Token::Newline && is_fence(idx))?;
+                let children = Parser::new(&tokens[2..end], ParserOptions::default()).parse();
+                let quote = BlockQuote {
+                    children,
+                    span: tokens[0].1..tokens[end].1,
+                };
+                Some((Node::BlockQuote(quote), end + 1))
             }
-            _ => None,
         }
+
+        let options = ParserOptions::default().with_block_rule(Directive);
+        // The block runs past the tokens lexed at first when yielding events
+        let markdown = format!(":::\n{}:::\nAfter", "- item\n\n".repeat(200));
+        let ast = parse_with_options(&markdown, options.clone()).unwrap();
+        assert_eq!(ast.len(), 2);
+        let Node::BlockQuote(quote) = &ast[0] else {
+            panic!("expected a block quote, found {:?}", ast[0]);
+        };
+        assert_eq!(quote.children.len(), 1);
+        assert!(matches!(ast[1], Node::Paragraph(_)));
+
+        // Without its closing line, the block is left to the other rules
+        let ast = parse_with_options(":::\n# Title", options).unwrap();
+        assert!(matches!(ast[1], Node::Header(_)));
     }
-}
 
-#[derive(Debug)]
-struct InlineMarker {
-    start: Option<usize>,
-    end: Option<usize>,
-}
+    #[test]
+    fn report_diagnostics() {
+        let markdown =
+            "# Title\n\nSee [docs](/a b) and [more](/c\n\n<!-- draft\n\n```rust\nfn main() {}\n";
+        let (ast, diagnostics) = parse_with_diagnostics(markdown);
+        assert_eq!(ast.len(), 4);
+
+        let messages: Vec<(&str, usize)> = diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.span.start.line))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "link destinations with spaces must be wrapped in `<` and `>`",
+                    3
+                ),
+                ("link destination is never closed", 3),
+                ("HTML comment is never closed", 5),
+                ("code block is never closed", 7),
+            ]
+        );
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+    }
 
-impl InlineMarker {
-    fn new() -> Self {
-        Self {
-            start: None,
-            end: None,
+    #[test]
+    fn recover_from_deep_nesting() {
+        let delimiters = "*".repeat(300);
+        let markdown = format!("{}deep{}\n\nafter", delimiters, delimiters);
+        let (ast, diagnostics) = parse_with_diagnostics(&markdown);
+        assert_eq!(ast.len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(parse(&markdown).unwrap(), ast);
+
+        for markdown in [">".repeat(200), "- ".repeat(300) + "item"] {
+            let (ast, diagnostics) = parse_with_diagnostics(&markdown);
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(parse(&markdown).unwrap(), ast);
         }
     }
 
-    fn is_empty(&self) -> bool {
-        self.start.is_none() && self.end.is_none()
+    #[test]
+    fn keep_tabs_of_code_without_the_source() {
+        let markdown = "```\n\tx\n```";
+        let tokens = Lexer::new(markdown).into_tokens();
+        let ast = Parser::new(&tokens, ParserOptions::default()).parse();
+        assert_eq!(ast, parse(markdown).unwrap());
+        let Node::CodeBlock(block) = &ast[0] else {
+            panic!("expected a code block, found {:?}", ast);
+        };
+        let mut code = String::new();
+        collect_text(&mut code, &block.code);
+        assert_eq!(code, "\tx\n");
     }
 
-    fn is_open(&self) -> bool {
-        self.start.is_some() && self.end.is_none()
+    #[test]
+    fn keep_long_runs_of_hashes_as_text() {
+        for count in [7, 255, 256, 257, 1000] {
+            let markdown = format!("{} x", "#".repeat(count));
+            let ast = parse(&markdown).unwrap();
+            let Node::Paragraph(paragraph) = &ast[0] else {
+                panic!("expected a paragraph for {} hashes, found {:?}", count, ast);
+            };
+            let mut text = String::new();
+            collect_text(&mut text, &paragraph.children);
+            assert_eq!(text, markdown);
+        }
     }
 
-    fn is_closed(&self) -> bool {
-        self.start.is_some() && self.end.is_some()
+    #[test]
+    fn limit_nesting_depth() {
+        let markdown = "> > > quote\n\n*a **b** c*";
+        let options = |max_nesting_depth| ParserOptions {
+            max_nesting_depth,
+            ..Default::default()
+        };
+        assert!(parse_with_options(markdown, options(DEFAULT_MAX_NESTING_DEPTH)).is_ok());
+
+        let mut events = EventIter::with_options(markdown, options(2));
+        let ast = events::build_ast(&mut events);
+        assert_eq!(ast.len(), 2);
+        assert_eq!(
+            events.error().unwrap().to_string(),
+            "elements nested deeper than 2 levels at line 1, column 11"
+        );
+        assert_eq!(parse_with_options(markdown, options(2)).unwrap(), ast);
+        let diagnostics = events.into_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "elements nested deeper than 2 levels"
+        );
     }
 
-    fn open(&mut self, index: usize) {
-        self.start = Some(index);
+    #[test]
+    fn borrow_text_from_source() {
+        let children = |markdown| match parse(markdown).unwrap().remove(0) {
+            Node::Paragraph(paragraph) => paragraph.children,
+            node => panic!("expected a paragraph, found {:?}", node),
+        };
+        // Text spanning several tokens is a single slice of the source
+        assert!(matches!(
+            &children("Plain text, with [some] punctuation.")[..],
+            [Node::Text(Cow::Borrowed(
+                "Plain text, with [some] punctuation."
+            ))]
+        ));
+        // Escaped characters aren't next to the text before them in the source
+        assert!(matches!(
+            &children(r"\*not\* emphasis")[..],
+            [Node::Text(Cow::Owned(text))] if text == "*not* emphasis"
+        ));
+        assert!(matches!(
+            &children(r"a\b \[c\]")[..],
+            [Node::Text(text)] if text == r"a\b [c]"
+        ));
     }
 
-    fn close(&mut self, index: usize) {
-        self.end = Some(index);
+    #[test]
+    fn match_delimiters_and_brackets() {
+        let children = |markdown| match parse(markdown).unwrap().remove(0) {
+            Node::Paragraph(paragraph) => paragraph.children,
+            node => panic!("expected a paragraph, found {:?}", node),
+        };
+        // Links never contain other links, so the outer brackets stay text
+        let links: Vec<_> = children("[a [b](c)](d)")
+            .into_iter()
+            .filter_map(|node| match node {
+                Node::Link(link) => Some(link.url),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(links, vec![vec![Node::Text("c".into())]]);
+        // Unmatched delimiters are kept as text
+        assert!(children("**a *b _c")
+            .iter()
+            .all(|node| matches!(node, Node::Text(_))));
+        // Emphasis is closed from the innermost opener outwards
+        let Node::Bold(bold) = &children("**a *b* c**")[0] else {
+            panic!("expected bold text");
+        };
+        assert!(matches!(bold.children[1], Node::Italic(_)));
     }
 
-    fn range(&self) -> Option<Range<usize>> {
-        match (self.start, self.end) {
-            (Some(start), Some(end)) => Some(start..end),
-            _ => None,
-        }
+    #[test]
+    fn merge_adjacent_text() {
+        let ast = parse("hello-world. foo *bar*\n").unwrap();
+        let Node::Paragraph(paragraph) = &ast[0] else {
+            panic!("expected a paragraph, found {:?}", ast[0]);
+        };
+        assert_eq!(
+            paragraph.children[0],
+            Node::Text("hello-world. foo ".into())
+        );
+        assert!(matches!(paragraph.children[1], Node::Italic(_)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
+    #[test]
+    fn keep_line_breaks_within_nested_inline_elements() {
+        // Within the emphasis, the last line looks like a thematic break
+        let ast = parse("*a\n---*").unwrap();
+        let Node::Paragraph(paragraph) = &ast[0] else {
+            panic!("expected a paragraph, found {:?}", ast[0]);
+        };
+        let Node::Italic(italic) = &paragraph.children[0] else {
+            panic!("expected italic text, found {:?}", paragraph.children[0]);
+        };
+        assert_eq!(italic.children[1], Node::LineBreak);
+    }
 
-    use crate::lexer::Lexer;
+    #[test]
+    fn look_ahead_past_the_end_of_the_tokens() {
+        // The list within the quote looks for a marker on the line after the last one
+        assert!(parse(">- \r>  ").is_ok());
+    }
 
-    use super::*;
+    #[test]
+    fn attach_spans_to_nodes() {
+        let span = |line, col, start, end| Span {
+            line,
+            col,
+            start,
+            end,
+            tab: false,
+        };
+        let markdown = "## Title\n\nSome **bold** text\nand [a link](/a)";
+        let ast = parse(markdown).unwrap();
+
+        let Node::Header(header) = &ast[0] else {
+            panic!("expected a header, found {:?}", ast[0]);
+        };
+        assert_eq!(header.span, span(1, 1, 0, 1)..span(1, 8, 3, 8));
+
+        let Node::Paragraph(paragraph) = &ast[1] else {
+            panic!("expected a paragraph, found {:?}", ast[1]);
+        };
+        assert_eq!(paragraph.span, span(3, 4, 10, 14)..span(4, 16, 44, 45));
+        let Node::Bold(bold) = &paragraph.children[1] else {
+            panic!("expected bold text, found {:?}", paragraph.children[1]);
+        };
+        assert_eq!(bold.span, span(3, 6, 15, 16)..span(3, 13, 22, 23));
+        let Node::Link(link) = &paragraph.children[5] else {
+            panic!("expected a link, found {:?}", paragraph.children[5]);
+        };
+        assert_eq!(link.span, span(4, 5, 33, 34)..span(4, 16, 44, 45));
+        assert_eq!(&markdown[token::byte_range(&link.span)], "[a link](/a)");
+    }
 
     #[test]
     fn parse_markdown() {
@@ -570,7 +1686,7 @@ mod tests {
             let markdown = fs::read_to_string(path).unwrap();
             let mut lexer = Lexer::new(&markdown);
             let tokens = lexer.scan();
-            let mut parser = Parser::new(tokens);
+            let mut parser = Parser::new(tokens, ParserOptions::default());
             let ast = parser.parse();
             insta::assert_json_snapshot!(ast);
         });
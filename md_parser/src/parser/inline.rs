@@ -0,0 +1,789 @@
+//! Inline elements of paragraphs and headings.
+//!
+//! Emphasis and links are matched with the delimiter stack described by the
+//! CommonMark spec: tokens are visited once, delimiters which can't be matched
+//! anymore are dropped as soon as that's known, and emphasis wraps the elements
+//! in between its delimiters without moving the ones around them.
+//! Inline content is parsed in linear time, even for adversarial input
+//! like thousands of unmatched `**` or `[`.
+
+use alloc::{vec, vec::Vec};
+use core::{mem, ops::Range};
+
+use super::{comment_content, push_inline, source_text, Extensions, Parser};
+use crate::{
+    ast::{Bold, Image, Italic, Link, Node},
+    token::Token,
+};
+
+/// Parentheses within a link destination can't be nested deeper than this,
+/// which bounds how far a destination is ever scanned
+const MAX_DESTINATION_PARENS: usize = 32;
+
+/// Inline element parsed so far, linked to its siblings. Elements wrapping
+/// the ones in between their delimiters only unlink those.
+struct Entry<'source> {
+    node: Node<'source>,
+    prev: Option<usize>,
+    next: Option<usize>,
+    /// How deep inline elements are nested within the node, counting itself
+    depth: usize,
+}
+
+/// Sequence of consecutive `*` or `_` delimiters
+struct DelimiterRun {
+    start: usize,
+    length: usize,
+}
+
+/// Delimiter run on the stack, which may still open or close emphasis.
+/// Closers use up their delimiters from the start of the run,
+/// and openers from the end of it.
+struct Delimiter {
+    underscore: bool,
+    /// The run as it shows up in the source, before any delimiters were used up
+    run: DelimiterRun,
+    /// Entry of the first delimiter not used up yet, followed by the other ones
+    entry: usize,
+    /// Token position of the first delimiter not used up yet
+    position: usize,
+    /// Number of delimiters not used up yet
+    count: usize,
+    can_open: bool,
+    can_close: bool,
+    /// Delimiter below this one on the stack
+    prev: Option<usize>,
+    /// Delimiter above this one on the stack
+    next: Option<usize>,
+}
+
+/// `[` or `![` waiting for the `]` closing the text of a link or an image
+struct Bracket {
+    /// Entry of the `[`
+    entry: usize,
+    /// Entry of the `!` of an image
+    bang: Option<usize>,
+    /// Token position of the `[`, or of the `!` of an image
+    position: usize,
+    /// Top of the delimiter stack when the bracket was found,
+    /// below the delimiters within the text of the link
+    delimiters_bottom: Option<usize>,
+    /// Links can't contain other links, so brackets before a link are
+    /// deactivated once the link is found
+    active: bool,
+}
+
+/// Inline elements of the content being parsed, along with the delimiters
+/// and brackets which may still turn into emphasis, links and images
+struct Inlines<'source> {
+    /// Markdown the text of the elements is borrowed from, see [`push_inline`]
+    source: &'source str,
+    /// Entries of all elements, starting with one standing before the first element
+    entries: Vec<Entry<'source>>,
+    last: usize,
+    delimiters: Vec<Delimiter>,
+    /// Top of the delimiter stack
+    top: Option<usize>,
+    brackets: Vec<Bracket>,
+    /// Position right after the next `)` at each token position of the content,
+    /// only computed once a link destination turns out not to be well-formed
+    next_paren_ends: Option<Vec<Option<usize>>>,
+    /// Whether an element was nested too deep already. The rest of the
+    /// delimiters and brackets are left as text, which the error is about anyway.
+    too_deep: bool,
+}
+
+impl<'source> Inlines<'source> {
+    fn new(source: &'source str) -> Self {
+        Self {
+            source,
+            entries: vec![Entry {
+                node: Node::LineBreak,
+                prev: None,
+                next: None,
+                depth: 0,
+            }],
+            last: 0,
+            delimiters: Vec::new(),
+            top: None,
+            brackets: Vec::new(),
+            next_paren_ends: None,
+            too_deep: false,
+        }
+    }
+
+    fn push(&mut self, node: Node<'source>) -> usize {
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            node,
+            prev: Some(self.last),
+            next: None,
+            depth: 0,
+        });
+        self.entries[self.last].next = Some(index);
+        self.last = index;
+        index
+    }
+
+    /// Unlinks the entries in between the given ones, both excluded,
+    /// and puts the given node in their place
+    fn replace_between(&mut self, before: usize, after: Option<usize>, node: Node<'source>) {
+        let children_depth = self.depth_between(before, after);
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            node,
+            prev: Some(before),
+            next: after,
+            depth: children_depth + 1,
+        });
+        self.entries[before].next = Some(index);
+        match after {
+            Some(after) => self.entries[after].prev = Some(index),
+            None => self.last = index,
+        }
+    }
+
+    /// Deepest nesting of the elements in between the given entries, both excluded
+    fn depth_between(&self, before: usize, after: Option<usize>) -> usize {
+        let mut depth = 0;
+        let mut entry = self.entries[before].next;
+        while entry != after {
+            let Some(index) = entry else { break };
+            depth = depth.max(self.entries[index].depth);
+            entry = self.entries[index].next;
+        }
+        depth
+    }
+
+    /// Takes out the nodes in between the given entries, both excluded
+    fn take_between(&mut self, before: usize, after: Option<usize>) -> Vec<Node<'source>> {
+        let mut nodes = Vec::new();
+        let mut entry = self.entries[before].next;
+        while entry != after {
+            let Some(index) = entry else { break };
+            push_inline(
+                &mut nodes,
+                mem::replace(&mut self.entries[index].node, Node::LineBreak),
+                self.source,
+            );
+            entry = self.entries[index].next;
+        }
+        nodes
+    }
+
+    fn push_delimiter(&mut self, mut delimiter: Delimiter) {
+        let index = self.delimiters.len();
+        delimiter.prev = self.top;
+        if let Some(top) = self.top {
+            self.delimiters[top].next = Some(index);
+        }
+        self.delimiters.push(delimiter);
+        self.top = Some(index);
+    }
+
+    fn remove_delimiter(&mut self, index: usize) {
+        let (prev, next) = (self.delimiters[index].prev, self.delimiters[index].next);
+        if let Some(prev) = prev {
+            self.delimiters[prev].next = next;
+        }
+        match next {
+            Some(next) => self.delimiters[next].prev = prev,
+            None => self.top = prev,
+        }
+    }
+
+    /// Every node in order, once all elements are parsed
+    fn into_nodes(mut self) -> Vec<Node<'source>> {
+        self.take_between(0, None)
+    }
+}
+
+/// Destination and title of a link, found after the text of the link
+struct LinkTail {
+    destination: Range<usize>,
+    title: Option<Range<usize>>,
+    /// Position right after the closing `)`
+    end: usize,
+}
+
+/// Reasons why a construct starting with `[` can't be turned into a link
+enum LinkError {
+    /// Not even shaped like a link, e.g. `[text]` or `[text] (url)`
+    NotALink,
+    /// The opening parenthesis of the destination is never matched,
+    /// with the token range scanned looking for it
+    UnclosedDestination(Range<usize>),
+    /// The destination contains spaces or its title isn't quoted properly,
+    /// with the token range of the whole link
+    InvalidDestination(Range<usize>),
+}
+
+impl<'tokens, 'source> Parser<'tokens, 'source> {
+    /// Parses the inline elements within the given token positions,
+    /// where newlines are line breaks. The enclosing element already
+    /// decided where its content ends, and allows inline elements
+    /// to be nested up to the given depth.
+    pub(super) fn inline_elements(
+        &mut self,
+        range: Range<usize>,
+        max_depth: usize,
+    ) -> Vec<Node<'source>> {
+        let mut inlines = Inlines::new(self.source);
+        let tokens = self.tokens;
+        let mut position = range.start;
+
+        while position < range.end {
+            if let Some((node, length)) = self.custom_inline(position..range.end) {
+                inlines.push(node);
+                position += length;
+                continue;
+            }
+
+            let node = match &tokens[position].0 {
+                token @ (Token::Star | Token::Underscore) => {
+                    let length = self.delimiter_run_at(position).min(range.end - position);
+                    let run = DelimiterRun {
+                        start: position,
+                        length,
+                    };
+                    let mut entry = 0;
+                    for offset in 0..length {
+                        entry = inlines.push(self.text_at(position + offset));
+                    }
+                    inlines.push_delimiter(Delimiter {
+                        underscore: token == &Token::Underscore,
+                        entry: entry + 1 - length,
+                        position,
+                        count: length,
+                        can_open: self.can_open_emphasis(&run),
+                        can_close: self.can_close_emphasis(&run),
+                        run,
+                        prev: None,
+                        next: None,
+                    });
+                    position += length;
+                    continue;
+                }
+                Token::Bang
+                    if position + 1 < range.end
+                        && tokens[position + 1].0 == Token::LeftSquareBracket =>
+                {
+                    let bang = inlines.push(self.text_at(position));
+                    let entry = inlines.push(self.text_at(position + 1));
+                    inlines.brackets.push(Bracket {
+                        entry,
+                        bang: Some(bang),
+                        position,
+                        delimiters_bottom: inlines.top,
+                        active: true,
+                    });
+                    position += 2;
+                    continue;
+                }
+                Token::LeftSquareBracket => {
+                    let entry = inlines.push(self.text_at(position));
+                    inlines.brackets.push(Bracket {
+                        entry,
+                        bang: None,
+                        position,
+                        delimiters_bottom: inlines.top,
+                        active: true,
+                    });
+                    position += 1;
+                    continue;
+                }
+                Token::RightSquareBracket => {
+                    position = self.close_bracket(&mut inlines, position, range.clone(), max_depth);
+                    continue;
+                }
+                Token::Backslash if self.is_escape_at(position, range.end) => {
+                    // Escaped characters are plain text, while a backslash
+                    // at the end of a line only leaves the line break
+                    inlines.push(match &tokens[position + 1].0 {
+                        Token::Newline => Node::LineBreak,
+                        _ => self.text_at(position + 1),
+                    });
+                    position += 2;
+                    continue;
+                }
+                Token::Newline => Node::LineBreak,
+                Token::Comment(comment) if self.has_extension(Extensions::HTML_COMMENTS) => {
+                    Node::Comment(comment_content(comment).into())
+                }
+                // Comments without their closing `-->` aren't tokenized as comments
+                Token::LessThan
+                    if self.has_extension(Extensions::HTML_COMMENTS)
+                        && self.is_comment_opener_at(position) =>
+                {
+                    self.warn("HTML comment is never closed", position..position + 3);
+                    self.text_at(position)
+                }
+                _ => self.text_at(position),
+            };
+            inlines.push(node);
+            position += 1;
+        }
+
+        self.process_emphasis(&mut inlines, None, max_depth);
+        inlines.into_nodes()
+    }
+
+    /// Turns the text before the `]` at the given position into a link or an image
+    /// when it's followed by a destination, returning the position to continue from
+    fn close_bracket(
+        &mut self,
+        inlines: &mut Inlines<'source>,
+        position: usize,
+        range: Range<usize>,
+        max_depth: usize,
+    ) -> usize {
+        let Some(bracket) = inlines.brackets.last() else {
+            inlines.push(self.text_at(position));
+            return position + 1;
+        };
+        let is_image = bracket.bang.is_some();
+        let tail = match bracket.active && !inlines.too_deep {
+            true => self.link_tail(inlines, position + 1, range.clone()),
+            false => Err(LinkError::NotALink),
+        };
+        let bracket = inlines.brackets.pop().expect("bracket was just looked at");
+
+        let tail = match tail {
+            Ok(tail) => tail,
+            Err(error) => {
+                // Images without a destination are plain text as well,
+                // but aren't worth a warning
+                if !is_image {
+                    match error {
+                        LinkError::UnclosedDestination(range) => {
+                            self.warn("link destination is never closed", range)
+                        }
+                        LinkError::InvalidDestination(range) => self.warn(
+                            "link destinations with spaces must be wrapped in `<` and `>`",
+                            range,
+                        ),
+                        LinkError::NotALink => {}
+                    }
+                }
+                inlines.push(self.text_at(position));
+                return position + 1;
+            }
+        };
+
+        self.process_emphasis(inlines, bracket.delimiters_bottom, max_depth);
+        if inlines.too_deep || inlines.depth_between(bracket.entry, None) + 1 > max_depth {
+            inlines.too_deep = true;
+            self.nesting_too_deep(bracket.position..tail.end);
+            inlines.push(self.text_at(position));
+            return position + 1;
+        }
+
+        let children = inlines.take_between(bracket.entry, None);
+        let url = self.text_nodes(tail.destination.clone());
+        let title = tail.title.clone().map(|range| self.text_nodes(range));
+        let span = self.span_between(bracket.position..tail.end);
+        let node = match is_image {
+            true => Node::Image(Image {
+                alt: children,
+                url,
+                title,
+                span,
+            }),
+            false => Node::Link(Link {
+                children,
+                url,
+                title,
+                span,
+            }),
+        };
+        let before = inlines.entries[bracket.bang.unwrap_or(bracket.entry)]
+            .prev
+            .unwrap_or(0);
+        inlines.replace_between(before, None, node);
+
+        // Links can't contain other links, though they may be within the text of an image
+        if !is_image {
+            for bracket in inlines.brackets.iter_mut().rev() {
+                if bracket.bang.is_none() {
+                    if !bracket.active {
+                        break;
+                    }
+                    bracket.active = false;
+                }
+            }
+        }
+        tail.end
+    }
+
+    /// Scans the `(destination "title")` right after the text of a link
+    fn link_tail(
+        &self,
+        inlines: &mut Inlines,
+        start: usize,
+        range: Range<usize>,
+    ) -> Result<LinkTail, LinkError> {
+        let end = range.end;
+        let token_at = |position: usize| (position < end).then(|| &self.tokens[position].0);
+        let is_space =
+            |position: usize| matches!(token_at(position), Some(Token::Space | Token::Newline));
+        if token_at(start) != Some(&Token::LeftParen) {
+            return Err(LinkError::NotALink);
+        }
+
+        let mut position = start + 1;
+        while is_space(position) {
+            position += 1;
+        }
+        // Destinations wrapped in `<` and `>` are allowed to contain spaces
+        let destination = if token_at(position) == Some(&Token::LessThan) {
+            let destination_start = position + 1;
+            position = destination_start;
+            while !matches!(
+                token_at(position),
+                None | Some(Token::GreaterThan | Token::LessThan | Token::Newline)
+            ) {
+                position += 1;
+            }
+            if token_at(position) != Some(&Token::GreaterThan) {
+                return Err(self.malformed_link_tail(inlines, start, position, range));
+            }
+            position += 1;
+            destination_start..position - 1
+        } else {
+            // Destinations can contain balanced parentheses, e.g. `(https://a.com/b_(c))`
+            let destination_start = position;
+            let mut depth = 0;
+            loop {
+                match token_at(position) {
+                    None | Some(Token::Space | Token::Newline) => break,
+                    Some(Token::LeftParen) if depth == MAX_DESTINATION_PARENS => {
+                        return Err(LinkError::NotALink)
+                    }
+                    Some(Token::LeftParen) => depth += 1,
+                    Some(Token::RightParen) if depth == 0 => break,
+                    Some(Token::RightParen) => depth -= 1,
+                    _ => {}
+                }
+                position += 1;
+            }
+            destination_start..position
+        };
+
+        let destination_end = position;
+        while is_space(position) {
+            position += 1;
+        }
+        // Titles must be separated from the destination and wrapped in double quotes
+        let mut title = None;
+        if position > destination_end && token_at(position) == Some(&Token::DoubleQuote) {
+            let title_start = position + 1;
+            let mut title_end = title_start;
+            while token_at(title_end).is_some_and(|t| t != &Token::DoubleQuote) {
+                title_end += 1;
+            }
+            if title_end < end {
+                title = Some(title_start..title_end);
+                position = title_end + 1;
+                while is_space(position) {
+                    position += 1;
+                }
+            }
+        }
+
+        if token_at(position) != Some(&Token::RightParen) {
+            return Err(self.malformed_link_tail(inlines, start, position, range));
+        }
+        Ok(LinkTail {
+            destination,
+            title,
+            end: position + 1,
+        })
+    }
+
+    /// Tells apart destinations which are never closed from
+    /// the ones which are closed but aren't shaped like a destination
+    fn malformed_link_tail(
+        &self,
+        inlines: &mut Inlines,
+        start: usize,
+        position: usize,
+        range: Range<usize>,
+    ) -> LinkError {
+        let next_paren_ends = inlines.next_paren_ends.get_or_insert_with(|| {
+            let mut ends = vec![None; range.len() + 1];
+            for position in range.clone().rev() {
+                ends[position - range.start] = match self.tokens[position].0 {
+                    Token::RightParen => Some(position + 1),
+                    _ => ends[position - range.start + 1],
+                };
+            }
+            ends
+        });
+        // The whole link is reported, starting from its text
+        let link_start = inlines
+            .brackets
+            .last()
+            .map_or(start, |bracket| bracket.position);
+        match next_paren_ends[position.min(range.end) - range.start] {
+            Some(end) => LinkError::InvalidDestination(link_start..end),
+            None => LinkError::UnclosedDestination(link_start..position),
+        }
+    }
+
+    /// Matches the delimiters above the given one on the stack into emphasis,
+    /// then removes them from the stack
+    fn process_emphasis(
+        &mut self,
+        inlines: &mut Inlines<'source>,
+        bottom: Option<usize>,
+        max_depth: usize,
+    ) {
+        let is_above = |index: usize, floor: Option<usize>| floor.is_none_or(|floor| index > floor);
+        let mut closer = inlines.top;
+        let mut first = None;
+        while let Some(index) = closer.filter(|&index| is_above(index, bottom)) {
+            first = Some(index);
+            closer = inlines.delimiters[index].prev;
+        }
+
+        // Openers are never looked for again below the delimiters where
+        // looking for an opener for the same kind of closer failed before
+        let mut openers_bottom = [[bottom; 3]; 4];
+        let mut closer = first.filter(|_| !inlines.too_deep);
+        while let Some(closer_index) = closer {
+            let delimiter = &inlines.delimiters[closer_index];
+            if !delimiter.can_close {
+                closer = delimiter.next;
+                continue;
+            }
+            let kind = usize::from(delimiter.underscore) * 2 + usize::from(delimiter.can_open);
+            let floor = &mut openers_bottom[kind][delimiter.run.length % 3];
+
+            let mut opener = delimiter.prev;
+            while let Some(index) = opener.filter(|&index| is_above(index, *floor)) {
+                let candidate = &inlines.delimiters[index];
+                if candidate.underscore == delimiter.underscore
+                    && candidate.can_open
+                    && can_pair(candidate, delimiter)
+                {
+                    break;
+                }
+                opener = candidate.prev;
+            }
+            let opener = opener.filter(|&index| is_above(index, *floor));
+
+            let Some(opener_index) = opener else {
+                *floor = delimiter.prev.or(bottom);
+                let next = delimiter.next;
+                if !delimiter.can_open {
+                    inlines.remove_delimiter(closer_index);
+                }
+                closer = next;
+                continue;
+            };
+
+            if !self.emphasize(inlines, opener_index, closer_index, max_depth) {
+                inlines.too_deep = true;
+                break;
+            }
+            if inlines.delimiters[opener_index].count == 0 {
+                inlines.remove_delimiter(opener_index);
+            }
+            if inlines.delimiters[closer_index].count == 0 {
+                closer = inlines.delimiters[closer_index].next;
+                inlines.remove_delimiter(closer_index);
+            }
+        }
+
+        // Unmatched delimiters above the bottom are left as text
+        match bottom {
+            Some(bottom) => {
+                inlines.delimiters[bottom].next = None;
+                inlines.top = Some(bottom);
+            }
+            None => inlines.top = None,
+        }
+    }
+
+    /// Wraps the elements in between the given opener and closer into emphasis,
+    /// strong when both have at least two delimiters left.
+    /// Returns whether the emphasis could be nested that deep.
+    fn emphasize(
+        &mut self,
+        inlines: &mut Inlines<'source>,
+        opener_index: usize,
+        closer_index: usize,
+        max_depth: usize,
+    ) -> bool {
+        let opener = &inlines.delimiters[opener_index];
+        let closer = &inlines.delimiters[closer_index];
+        let size = if opener.count >= 2 && closer.count >= 2 {
+            2
+        } else {
+            1
+        };
+        // Delimiters used up by the emphasis, at the end of the opener and the start of the closer
+        let opener_entry = opener.entry + opener.count - size;
+        let closer_entry = closer.entry;
+        let tokens = opener.position + opener.count - size..closer.position + size;
+
+        let content_start = opener.entry + opener.count - 1;
+        if inlines.depth_between(content_start, Some(closer_entry)) + 1 > max_depth {
+            self.nesting_too_deep(tokens);
+            return false;
+        }
+
+        let children = inlines.take_between(content_start, Some(closer_entry));
+        let span = self.span_between(tokens);
+        let node = match size {
+            2 => Node::Bold(Bold { children, span }),
+            _ => Node::Italic(Italic { children, span }),
+        };
+        let before = inlines.entries[opener_entry].prev.unwrap_or(0);
+        let after = inlines.entries[closer_entry + size - 1].next;
+        inlines.replace_between(before, after, node);
+
+        // Delimiters in between can't be matched with anything outside of the emphasis
+        inlines.delimiters[opener_index].next = Some(closer_index);
+        inlines.delimiters[closer_index].prev = Some(opener_index);
+        inlines.delimiters[opener_index].count -= size;
+        let closer = &mut inlines.delimiters[closer_index];
+        closer.count -= size;
+        closer.entry += size;
+        closer.position += size;
+        true
+    }
+
+    /// Element parsed by the first custom inline rule matching at the start of the range,
+    /// along with the number of tokens it spans
+    fn custom_inline(&self, range: Range<usize>) -> Option<(Node<'source>, usize)> {
+        let tokens = &self.tokens[range];
+        self.options.inline_rules.iter().find_map(|rule| {
+            rule.parse(tokens)
+                .filter(|(_, length)| (1..=tokens.len()).contains(length))
+        })
+    }
+
+    fn text_nodes(&self, range: Range<usize>) -> Vec<Node<'source>> {
+        let mut nodes = Vec::new();
+        for position in range {
+            push_inline(&mut nodes, self.text_at(position), self.source);
+        }
+        nodes
+    }
+
+    /// Token at the given position as plain text
+    fn text_at(&self, position: usize) -> Node<'source> {
+        Node::Text(source_text(self.source, &self.tokens[position]).into())
+    }
+
+    /// Whether the backslash at the given position escapes the token after it,
+    /// which is either ASCII punctuation or the end of the line
+    fn is_escape_at(&self, position: usize, end: usize) -> bool {
+        position + 1 < end
+            && match &self.tokens[position + 1].0 {
+                Token::Newline => true,
+                token => token
+                    .literal()
+                    .starts_with(|c: char| c.is_ascii_punctuation()),
+            }
+    }
+
+    pub(super) fn is_comment_opener_at(&self, position: usize) -> bool {
+        let opener = [Token::LessThan, Token::Bang, Token::Dash, Token::Dash];
+        self.tokens
+            .get(position..position + opener.len())
+            .is_some_and(|tokens| tokens.iter().map(|(t, _)| t).eq(opener.iter()))
+    }
+
+    /// Any left-flanking run of `*` can open emphasis, but underscores
+    /// within a word, like in `snake_case`, must not.
+    fn can_open_emphasis(&self, run: &DelimiterRun) -> bool {
+        let left_flanking = self.is_left_flanking(run);
+        match self.tokens.get(run.start) {
+            Some((Token::Underscore, _)) => {
+                left_flanking
+                    && (!self.is_right_flanking(run)
+                        || is_punctuation_char(self.char_before(run.start)))
+            }
+            _ => left_flanking,
+        }
+    }
+
+    /// Any right-flanking run of `*` can close emphasis, but underscores
+    /// within a word must not.
+    fn can_close_emphasis(&self, run: &DelimiterRun) -> bool {
+        let right_flanking = self.is_right_flanking(run);
+        match self.tokens.get(run.start) {
+            Some((Token::Underscore, _)) => {
+                right_flanking
+                    && (!self.is_left_flanking(run)
+                        || is_punctuation_char(self.char_at(run.start + run.length)))
+            }
+            _ => right_flanking,
+        }
+    }
+
+    /// A delimiter run is left-flanking when it is
+    /// not followed by whitespace, and either not followed by punctuation
+    /// or followed by punctuation and preceded by whitespace or punctuation.
+    fn is_left_flanking(&self, run: &DelimiterRun) -> bool {
+        let before = self.char_before(run.start);
+        let after = self.char_at(run.start + run.length);
+        !is_whitespace_char(after)
+            && (!is_punctuation_char(after)
+                || is_whitespace_char(before)
+                || is_punctuation_char(before))
+    }
+
+    /// A delimiter run is right-flanking when it is
+    /// not preceded by whitespace, and either not preceded by punctuation
+    /// or preceded by punctuation and followed by whitespace or punctuation.
+    fn is_right_flanking(&self, run: &DelimiterRun) -> bool {
+        let before = self.char_before(run.start);
+        let after = self.char_at(run.start + run.length);
+        !is_whitespace_char(before)
+            && (!is_punctuation_char(before)
+                || is_whitespace_char(after)
+                || is_punctuation_char(after))
+    }
+
+    /// Last character of the token right before the given position
+    fn char_before(&self, position: usize) -> Option<char> {
+        if position == 0 {
+            return None;
+        }
+        self.tokens
+            .get(position - 1)
+            .and_then(|(t, _)| t.literal().chars().last())
+    }
+
+    /// First character of the token at the given position
+    fn char_at(&self, position: usize) -> Option<char> {
+        self.tokens
+            .get(position)
+            .and_then(|(t, _)| t.literal().chars().next())
+    }
+}
+
+/// When either run could both open and close emphasis, their
+/// combined length can't be a multiple of 3, unless both lengths are.
+fn can_pair(opener: &Delimiter, closer: &Delimiter) -> bool {
+    let (opener_length, closer_length) = (opener.run.length, closer.run.length);
+    let both_multiple_of_3 = opener_length.is_multiple_of(3) && closer_length.is_multiple_of(3);
+    !((opener.can_close || closer.can_open)
+        && (opener_length + closer_length).is_multiple_of(3)
+        && !both_multiple_of_3)
+}
+
+/// The start and end of a line count as whitespace
+fn is_whitespace_char(c: Option<char>) -> bool {
+    c.is_none_or(char::is_whitespace)
+}
+
+/// ASCII punctuation as well as any other Unicode punctuation or symbol
+fn is_punctuation_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| {
+        c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+    })
+}
@@ -1,4 +1,30 @@
-use crate::{ast::Node, lexer::Lexer, parser::Parser};
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Write},
+    ops::Range,
+};
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "highlight")]
+use crate::highlight::Highlighter;
+use crate::{
+    ast::{
+        collect_text, BlockQuote, Bold, CodeBlock, Header, Image, Italic, Link, List, ListItem,
+        Node, Paragraph,
+    },
+    parser::{self, ParseError, ParserOptions},
+    sanitizer::Sanitizer,
+    toc::{outline, Slugger},
+    token::Span,
+};
 
 /// Renders an HTML string from the given AST
 ///
@@ -7,113 +33,2350 @@ use crate::{ast::Node, lexer::Lexer, parser::Parser};
 /// ```
 /// use md_parser::renderer;
 /// let markdown = r"I'm a **paragraph**.";
-/// let html = renderer::render_html(markdown);
+/// let html = renderer::render_html(markdown).unwrap();
 /// assert_eq!(html, "<p>I'm a <strong>paragraph</strong>.</p>");
 /// ```
-pub fn render_html(markdown: &str) -> String {
-    let mut lexer = Lexer::new(markdown);
-    let mut parser = Parser::new(lexer.scan());
-    let ast = parser.parse();
-    render(ast)
+pub fn render_html(markdown: &str) -> Result<String, RenderError> {
+    render_html_with(markdown, &HtmlOptions::default())
+}
+
+/// Settings controlling how the HTML output is produced
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// Pass HTML comments through to the output instead of dropping them.
+    /// Unless `unsafe_html` is set, dashes that could end a comment early are spaced out.
+    pub keep_comments: bool,
+    /// Write text as is, without escaping `<`, `>` and `&`, and keep
+    /// link destinations whatever their scheme is.
+    /// Only meant for trusted input, as it lets markdown inject any HTML.
+    pub unsafe_html: bool,
+    /// Let the raw HTML tags allowed by the sanitizer through text,
+    /// instead of escaping them. Ignored when `unsafe_html` is set.
+    pub sanitizer: Option<Sanitizer>,
+    /// Schemes, in lowercase, that link and image destinations may use.
+    /// Destinations with any other scheme, like `javascript:` or `data:`,
+    /// are rendered empty. Relative destinations are always allowed.
+    pub allowed_schemes: Vec<String>,
+    /// Close void elements XHTML style, e.g. `<br />` instead of `<br>`
+    pub self_closing_tags: bool,
+    /// End the output with a newline
+    pub trailing_newline: bool,
+    /// Wrap the output into a complete HTML document instead of a fragment
+    pub full_document: bool,
+    pub attribute_order: AttributeOrder,
+    /// Rewrites the destination of every link and image before it's checked
+    /// against `allowed_schemes`, e.g. to serve images from a CDN
+    pub link_rewriter: Option<LinkRewriter>,
+    /// Adds `rel` and `target` attributes to links leaving the site
+    pub external_links: Option<ExternalLinks>,
+    /// Let browsers defer loading and decoding images until they're needed,
+    /// with `loading="lazy"` and `decoding="async"`
+    pub lazy_images: bool,
+    /// Looks up the size of images, written as their `width` and `height`
+    /// so pages don't shift around while images load
+    pub image_dimensions: Option<ImageDimensions>,
+    /// Adds attributes to the elements written for each kind of node,
+    /// e.g. utility classes styling headings and links
+    pub element_attributes: Option<ElementAttributes>,
+    /// Gives headings an `id` derived from their text,
+    /// and optionally a permalink pointing to it
+    pub heading_anchors: HeadingAnchors,
+    /// Levels added to every heading, clamped at `<h6>`, e.g. 1 to render
+    /// `#` as `<h2>` when embedding documents in a page having its own `<h1>`
+    pub heading_offset: u8,
+    /// Writes where each block element comes from in the markdown source,
+    /// as `data-sourcepos="3:1-5:20"` attributes like cmark does, e.g. to scroll
+    /// a preview along with the editor. Lines and columns start at 1.
+    pub source_positions: bool,
+    /// Replaces paragraphs made of a `[TOC]` or `{:toc}` marker with a table
+    /// of contents. Headings then get an `id`, even without `heading_anchors`.
+    pub toc: Option<TocOptions>,
+    /// Highlights code blocks tagged with a language it knows,
+    /// leaving the other ones as plain code
+    #[cfg(feature = "highlight")]
+    pub highlighter: Option<Highlighter>,
+    /// Syntax recognized when parsing the markdown to render
+    pub parser: ParserOptions,
 }
 
-fn render(ast: Vec<Node>) -> String {
-    let mut text = String::from("");
-    for node in ast.iter() {
-        visit(&mut text, node);
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            keep_comments: false,
+            unsafe_html: false,
+            sanitizer: None,
+            allowed_schemes: ["http", "https", "mailto"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            self_closing_tags: false,
+            trailing_newline: false,
+            full_document: false,
+            attribute_order: AttributeOrder::default(),
+            link_rewriter: None,
+            external_links: None,
+            lazy_images: false,
+            image_dimensions: None,
+            element_attributes: None,
+            heading_anchors: HeadingAnchors::default(),
+            heading_offset: 0,
+            source_positions: false,
+            toc: None,
+            #[cfg(feature = "highlight")]
+            highlighter: None,
+            parser: ParserOptions::default(),
+        }
     }
-    text
 }
 
-fn visit(buffer: &mut String, node: &Node) {
-    match node {
-        Node::Header(_) | Node::Paragraph(_) => visit_block(buffer, node),
-        node => visit_inline(buffer, node),
+/// Callback rewriting link and image destinations at render time
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use md_parser::renderer::{self, HtmlOptions, LinkRewriter};
+/// let options = HtmlOptions {
+///     link_rewriter: Some(LinkRewriter::new(|url| match url.strip_prefix("/images/") {
+///         Some(path) => Cow::Owned(format!("https://cdn.bpaulino.com/{}", path)),
+///         None => Cow::Borrowed(url),
+///     })),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("![Gohan](/images/gohan.png)", &options).unwrap();
+/// assert_eq!(
+///     html,
+///     r#"<p><img src="https://cdn.bpaulino.com/gohan.png" alt="Gohan"></p>"#
+/// );
+/// ```
+#[derive(Clone)]
+pub struct LinkRewriter(Arc<RewriteFn>);
+
+type RewriteFn = dyn for<'u> Fn(&'u str) -> Cow<'u, str> + Send + Sync;
+
+impl LinkRewriter {
+    pub fn new(rewrite: impl Fn(&str) -> Cow<'_, str> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(rewrite))
+    }
+
+    pub fn rewrite<'u>(&self, url: &'u str) -> Cow<'u, str> {
+        (self.0)(url)
+    }
+}
+
+impl fmt::Debug for LinkRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LinkRewriter")
+    }
+}
+
+/// Tells apart links to other sites, so they can be marked as untrusted
+/// and opened in a new tab
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, ExternalLinks, HtmlOptions};
+/// let options = HtmlOptions {
+///     external_links: Some(ExternalLinks {
+///         internal_hosts: vec!["bpaulino.com".to_string()],
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with(
+///     "[blog](https://blog.bpaulino.com) [docs](https://docs.rs)",
+///     &options,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         r#"<p><a href="https://blog.bpaulino.com">blog</a> "#,
+///         r#"<a href="https://docs.rs" rel="nofollow noopener" target="_blank">docs</a></p>"#
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExternalLinks {
+    /// Hosts, along with their subdomains, whose links are internal.
+    /// Relative links are always internal.
+    pub internal_hosts: Vec<String>,
+    /// `rel` attribute of external links, left out when empty
+    pub rel: String,
+    /// Open external links in a new tab, with `target="_blank"`
+    pub new_tab: bool,
+}
+
+impl Default for ExternalLinks {
+    fn default() -> Self {
+        Self {
+            internal_hosts: Vec::new(),
+            rel: "nofollow noopener".to_string(),
+            new_tab: true,
+        }
+    }
+}
+
+impl ExternalLinks {
+    /// Whether the link points to a host other than the internal ones
+    pub fn is_external(&self, url: &str) -> bool {
+        match url_host(url) {
+            Some(host) => !self.internal_hosts.iter().any(|internal| {
+                host.strip_suffix(internal.to_ascii_lowercase().as_str())
+                    .is_some_and(|subdomain| subdomain.is_empty() || subdomain.ends_with('.'))
+            }),
+            None => false,
+        }
+    }
+}
+
+/// Callback looking up the width and height of an image from its rendered destination
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, HtmlOptions, ImageDimensions};
+/// let options = HtmlOptions {
+///     lazy_images: true,
+///     image_dimensions: Some(ImageDimensions::new(|src| (src == "logo.png").then_some((64, 32)))),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("![Logo](logo.png)", &options).unwrap();
+/// assert_eq!(
+///     html,
+///     r#"<p><img src="logo.png" alt="Logo" width="64" height="32" loading="lazy" decoding="async"></p>"#
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ImageDimensions(Arc<LookupFn>);
+
+type LookupFn = dyn Fn(&str) -> Option<(u32, u32)> + Send + Sync;
+
+impl ImageDimensions {
+    pub fn new(lookup: impl Fn(&str) -> Option<(u32, u32)> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(lookup))
+    }
+
+    /// Width and height of the image, when they're known
+    pub fn lookup(&self, src: &str) -> Option<(u32, u32)> {
+        (self.0)(src)
+    }
+}
+
+impl fmt::Debug for ImageDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImageDimensions")
+    }
+}
+
+/// Callback listing the extra attributes of the elements of a given kind.
+///
+/// Classes are appended to the ones the renderer already writes, like the
+/// language of code blocks. Any other attribute the renderer already writes,
+/// like the destination of links, is kept as is.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, Element, ElementAttributes, HtmlOptions};
+/// let options = HtmlOptions {
+///     element_attributes: Some(ElementAttributes::new(|element| match element {
+///         Element::Heading(1) => vec![("class", "text-3xl font-bold".to_string())],
+///         Element::Link => vec![("class", "underline".to_string())],
+///         _ => Vec::new(),
+///     })),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("# [Gohan](/gohan)", &options).unwrap();
+/// assert_eq!(
+///     html,
+///     r#"<h1 class="text-3xl font-bold"><a href="/gohan" class="underline">Gohan</a></h1>"#
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ElementAttributes(Arc<AttributesFn>);
+
+type AttributesFn = dyn Fn(Element) -> Vec<(&'static str, String)> + Send + Sync;
+
+impl ElementAttributes {
+    pub fn new(
+        attributes: impl Fn(Element) -> Vec<(&'static str, String)> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(attributes))
+    }
+
+    /// Extra attributes of the given element
+    pub fn attributes(&self, element: Element) -> Vec<(&'static str, String)> {
+        (self.0)(element)
+    }
+}
+
+impl fmt::Debug for ElementAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ElementAttributes")
+    }
+}
+
+/// HTML element written for a kind of node, displayed as its tag name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element {
+    /// `<h1>` to `<h6>`, with the level of the heading
+    Heading(u8),
+    Paragraph,
+    /// The `<pre>` element wrapping the code of a code block
+    CodeBlock,
+    BlockQuote,
+    OrderedList,
+    UnorderedList,
+    ListItem,
+    ThematicBreak,
+    Bold,
+    Italic,
+    Link,
+    Image,
+    LineBreak,
+    /// The `<nav>` element replacing a `[TOC]` marker
+    TableOfContents,
+}
+
+impl Element {
+    /// Elements without content nor closing tag
+    pub fn is_void(self) -> bool {
+        matches!(self, Self::ThematicBreak | Self::Image | Self::LineBreak)
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tag = match self {
+            Self::Heading(level) => return write!(f, "h{}", level),
+            Self::Paragraph => "p",
+            Self::TableOfContents => "nav",
+            Self::CodeBlock => "pre",
+            Self::BlockQuote => "blockquote",
+            Self::OrderedList => "ol",
+            Self::UnorderedList => "ul",
+            Self::ListItem => "li",
+            Self::ThematicBreak => "hr",
+            Self::Bold => "strong",
+            Self::Italic => "em",
+            Self::Link => "a",
+            Self::Image => "img",
+            Self::LineBreak => "br",
+        };
+        f.write_str(tag)
+    }
+}
+
+/// How headings can be linked to. IDs are slugs of the heading text,
+/// numbered when a heading repeats so they're unique across the document.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, HeadingAnchors, HtmlOptions};
+/// let options = HtmlOptions {
+///     heading_anchors: HeadingAnchors::Append,
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("## Usage\n\n## Usage", &options).unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         r##"<h2 id="usage">Usage<a class="anchor" href="#usage" aria-hidden="true">#</a></h2>"##,
+///         r##"<h2 id="usage-1">Usage<a class="anchor" href="#usage-1" aria-hidden="true">#</a></h2>"##,
+///     )
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HeadingAnchors {
+    /// Headings have no `id`
+    #[default]
+    None,
+    /// Headings have an `id`, without any permalink
+    Id,
+    /// A `#` permalink follows the text of headings
+    Append,
+    /// The text of headings is wrapped into a permalink
+    Wrap,
+}
+
+/// Levels of the headings listed by a table of contents
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, HtmlOptions, TocOptions};
+/// let options = HtmlOptions {
+///     toc: Some(TocOptions { min_depth: 2, max_depth: 3 }),
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with("# Gohan\n\n[TOC]\n\n## Usage\n\n### CLI\n\n#### Flags", &options).unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         r#"<h1 id="gohan">Gohan</h1>"#,
+///         r##"<nav class="toc"><ul><li><a href="#usage">Usage</a><ul><li><a href="#cli">CLI</a></li></ul></li></ul></nav>"##,
+///         r#"<h2 id="usage">Usage</h2><h3 id="cli">CLI</h3><h4 id="flags">Flags</h4>"#,
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TocOptions {
+    /// Level of the shallowest headings listed, 1 for `#`
+    pub min_depth: u8,
+    /// Level of the deepest headings listed, 6 for `######`
+    pub max_depth: u8,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            min_depth: 1,
+            max_depth: 6,
+        }
+    }
+}
+
+/// Order in which the attributes of an element are written
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOrder {
+    /// Most relevant attribute first, e.g. `src` before `alt` on images
+    #[default]
+    Conventional,
+    /// Sorted by name, which keeps the output stable for diffing
+    Alphabetical,
+}
+
+/// Renders an HTML string from the given AST using the given options
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{self, HtmlOptions};
+/// let markdown = "Hello <!-- editorial note -->";
+/// let options = HtmlOptions {
+///     keep_comments: true,
+///     self_closing_tags: true,
+///     ..Default::default()
+/// };
+/// let html = renderer::render_html_with(markdown, &options).unwrap();
+/// assert_eq!(html, "<p>Hello <!-- editorial note --></p>");
+///
+/// let html = renderer::render_html_with("a\nb", &options).unwrap();
+/// assert_eq!(html, "<p>a<br />b</p>");
+/// ```
+pub fn render_html_with(markdown: &str, options: &HtmlOptions) -> Result<String, RenderError> {
+    let ast = parser::parse_with_options(markdown, options.parser.clone())?;
+    Ok(render_ast(&ast, options))
+}
+
+/// Markdown that can't be rendered as HTML
+#[derive(Debug)]
+pub enum RenderError {
+    Parse(ParseError),
+    /// The sink the HTML was written into failed
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The formatter the HTML was written into failed
+    Fmt(fmt::Error),
+}
+
+impl From<ParseError> for RenderError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "could not parse markdown: {}", error),
+            #[cfg(feature = "std")]
+            Self::Io(error) => write!(f, "could not write HTML: {}", error),
+            Self::Fmt(error) => write!(f, "could not write HTML: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(error) => Some(error),
+            Self::Io(error) => Some(error),
+            Self::Fmt(error) => Some(error),
+        }
+    }
+}
+
+/// Renders an HTML string from an already parsed, and possibly transformed, AST
+pub fn render_ast(ast: &[Node], options: &HtmlOptions) -> String {
+    HtmlRenderer::new(options.clone()).render(ast)
+}
+
+/// Renders the given markdown straight into a byte sink, e.g. a file or a socket,
+/// without holding the whole HTML output in memory.
+/// Rendering issues many small writes, so unbuffered sinks are best wrapped in
+/// a [`std::io::BufWriter`].
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer;
+/// let mut output = Vec::new();
+/// renderer::render_html_to("# Gohan", &mut output).unwrap();
+/// assert_eq!(output, b"<h1>Gohan</h1>");
+/// ```
+#[cfg(feature = "std")]
+pub fn render_html_to<W: io::Write>(markdown: &str, writer: &mut W) -> Result<(), RenderError> {
+    let ast = parser::parse(markdown)?;
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: None,
+    };
+    write_ast(&mut adapter, &ast, &HtmlOptions::default()).map_err(|error| {
+        match adapter.error.take() {
+            Some(io_error) => RenderError::Io(io_error),
+            None => RenderError::Fmt(error),
+        }
+    })
+}
+
+/// Renders the given markdown straight into a text sink, like a
+/// [`std::fmt::Formatter`] or an existing `String`
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer;
+/// let mut page = String::from("<body>");
+/// renderer::render_html_fmt("# Gohan", &mut page).unwrap();
+/// assert_eq!(page, "<body><h1>Gohan</h1>");
+/// ```
+pub fn render_html_fmt<W: fmt::Write>(markdown: &str, writer: &mut W) -> Result<(), RenderError> {
+    let ast = parser::parse(markdown)?;
+    write_ast(writer, &ast, &HtmlOptions::default()).map_err(RenderError::Fmt)
+}
+
+fn write_ast(out: &mut dyn Write, ast: &[Node], options: &HtmlOptions) -> fmt::Result {
+    HtmlRenderer::new(options.clone()).render_document(out, ast)
+}
+
+/// Lets the renderer write into an `io::Write` sink, keeping
+/// the underlying IO error which `fmt::Error` can't carry.
+#[cfg(feature = "std")]
+struct IoAdapter<'w, W: io::Write> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
     }
 }
 
-fn visit_block(buffer: &mut String, node: &Node) {
-    match node {
-        Node::Header(header) => {
-            buffer.push_str(&format!("<h{}>", header.level));
-            visit_inline_nodes(buffer, &header.children);
-            buffer.push_str(&format!("</h{}>", header.level));
+/// Turns AST nodes into output, one callback per kind of node.
+///
+/// Every callback defaults to the HTML output of [`HtmlRenderer`],
+/// so custom renderers only override the nodes they care about.
+/// Children are always rendered through `self`, which means overrides
+/// also apply to nodes nested deep inside other elements.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::{self, Write};
+/// use md_parser::{ast::Bold, parser, renderer::{HtmlOptions, HtmlRenderer, Renderer}};
+///
+/// /// Uses `<b>` instead of `<strong>`
+/// struct Bolder(HtmlRenderer);
+///
+/// impl Renderer for Bolder {
+///     fn options(&self) -> &HtmlOptions {
+///         self.0.options()
+///     }
+///
+///     fn bold(&mut self, out: &mut dyn Write, bold: &Bold) -> fmt::Result {
+///         out.write_str("<b>")?;
+///         self.render_nodes(out, &bold.children)?;
+///         out.write_str("</b>")
+///     }
+/// }
+///
+/// let ast = parser::parse("Some **bold** text").unwrap();
+/// let html = Bolder(HtmlRenderer::default()).render(&ast);
+/// assert_eq!(html, "<p>Some <b>bold</b> text</p>");
+/// ```
+pub trait Renderer {
+    fn options(&self) -> &HtmlOptions;
+
+    /// Renders the whole AST into a string
+    fn render(&mut self, ast: &[Node]) -> String {
+        let mut output = String::new();
+        self.render_document(&mut output, ast)
+            .expect("writing into a String never fails");
+        output
+    }
+
+    /// Renders the whole AST, wrapped into a complete document
+    /// and followed by a newline when the options ask for it.
+    fn render_document(&mut self, out: &mut dyn Write, ast: &[Node]) -> fmt::Result {
+        if let Some(slugger) = self.slugger() {
+            slugger.reset();
+        }
+        let options = self.options();
+        let (full_document, trailing_newline) = (options.full_document, options.trailing_newline);
+        let has_toc = options.toc.is_some();
+        if full_document {
+            write!(
+                out,
+                r#"<!DOCTYPE html><html><head><meta charset="utf-8"{}</head><body>"#,
+                void_end(self.options())
+            )?;
+        }
+        let mut rest = ast;
+        while let Some(position) = rest.iter().position(|node| has_toc && is_toc_marker(node)) {
+            self.render_nodes(out, &rest[..position])?;
+            self.table_of_contents(out, ast)?;
+            rest = &rest[position + 1..];
+        }
+        self.render_nodes(out, rest)?;
+        if full_document {
+            out.write_str("</body></html>")?;
+        }
+        if trailing_newline {
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// Renders the nodes one after the other. Consecutive text nodes
+    /// are rendered at once, so raw HTML tags spanning several of them
+    /// can be sanitized as a whole.
+    fn render_nodes(&mut self, out: &mut dyn Write, nodes: &[Node]) -> fmt::Result {
+        let mut rest = nodes;
+        while let Some((node, tail)) = rest.split_first() {
+            let text_length = rest
+                .iter()
+                .take_while(|node| matches!(node, Node::Text(_) | Node::Digit(_)))
+                .count();
+            if text_length > 1 {
+                self.text(out, &plain_text(&rest[..text_length]))?;
+                rest = &rest[text_length..];
+            } else {
+                self.render_node(out, node)?;
+                rest = tail;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands the node over to the callback for its kind
+    fn render_node(&mut self, out: &mut dyn Write, node: &Node) -> fmt::Result {
+        match node {
+            Node::Header(header) => self.header(out, header),
+            Node::Paragraph(paragraph) => self.paragraph(out, paragraph),
+            Node::CodeBlock(code_block) => self.code_block(out, code_block),
+            Node::BlockQuote(quote) => self.block_quote(out, quote),
+            Node::List(list) => self.list(out, list),
+            Node::ListItem(item) => self.list_item(out, item),
+            Node::ThematicBreak => self.thematic_break(out),
+            Node::Bold(bold) => self.bold(out, bold),
+            Node::Italic(italic) => self.italic(out, italic),
+            Node::Link(link) => self.link(out, link),
+            Node::Image(image) => self.image(out, image),
+            Node::Text(text) => self.text(out, text),
+            Node::Digit(digit) => self.text(out, digit),
+            Node::LineBreak => self.line_break(out),
+            Node::Comment(comment) => self.comment(out, comment),
+        }
+    }
+
+    /// Renders the inline children of a paragraph,
+    /// without the element wrapping them.
+    fn paragraph_content(&mut self, out: &mut dyn Write, nodes: &[Node]) -> fmt::Result {
+        // Within a paragraph, whenever the last node is a newline,
+        // we can just discard it as the paragraph element
+        // behaves itself as a block.
+        let nodes = match nodes.split_last() {
+            Some((Node::LineBreak, init)) => init,
+            _ => nodes,
+        };
+        self.render_nodes(out, nodes)
+    }
+
+    fn header(&mut self, out: &mut dyn Write, header: &Header) -> fmt::Result {
+        let level = header.level.saturating_add(self.options().heading_offset);
+        let element = Element::Heading(level.min(6));
+        let anchors = match self.options().heading_anchors {
+            // The table of contents links to the headings
+            HeadingAnchors::None if self.options().toc.is_some() => HeadingAnchors::Id,
+            anchors => anchors,
+        };
+        let position = source_position(self.options(), &header.span);
+        if anchors == HeadingAnchors::None {
+            self.start_tag(out, element, position)?;
+            self.render_nodes(out, &header.children)?;
+            return write!(out, "</{}>", element);
+        }
+
+        let id = self.heading_id(&plain_text(&header.children));
+        let href = format!("#{}", id);
+        let mut attributes = vec![("id", id)];
+        attributes.extend(position);
+        self.start_tag(out, element, attributes)?;
+        match anchors {
+            HeadingAnchors::Wrap => {
+                out.write_str("<a")?;
+                self.attributes(out, &mut [("class", "anchor".to_string()), ("href", href)])?;
+                out.write_char('>')?;
+                self.render_nodes(out, &header.children)?;
+                out.write_str("</a>")?;
+            }
+            HeadingAnchors::Append => {
+                self.render_nodes(out, &header.children)?;
+                out.write_str("<a")?;
+                self.attributes(
+                    out,
+                    &mut [
+                        ("class", "anchor".to_string()),
+                        ("href", href),
+                        ("aria-hidden", "true".to_string()),
+                    ],
+                )?;
+                out.write_str(">#</a>")?;
+            }
+            _ => self.render_nodes(out, &header.children)?,
+        }
+        write!(out, "</{}>", element)
+    }
+
+    /// Keeps track of the heading IDs given out while rendering a document.
+    /// Renderers wrapping another one should hand over its slugger,
+    /// as without one repeated headings get the same ID.
+    fn slugger(&mut self) -> Option<&mut Slugger> {
+        None
+    }
+
+    /// ID of a heading with the given text, unique across the document
+    /// as long as the renderer has a [`slugger`](Renderer::slugger).
+    fn heading_id(&mut self, text: &str) -> String {
+        match self.slugger() {
+            Some(slugger) => slugger.slug(text),
+            None => Slugger::default().slug(text),
         }
-        Node::Paragraph(paragraph) => {
-            buffer.push_str("<p>");
-            for (idx, node) in paragraph.children.iter().enumerate() {
-                // Within a paragraph, whenever we hit the last node
-                // and it's a newline, we can just discard it as the
-                // paragraph element behaves itself as a block.
-                if idx >= paragraph.children.len() - 1 && node == &Node::LineBreak {
-                    continue;
+    }
+
+    /// Writes the table of contents replacing a `[TOC]` marker, as nested lists
+    /// of links to the headings of the document at the depths of [`TocOptions`]
+    fn table_of_contents(&mut self, out: &mut dyn Write, ast: &[Node]) -> fmt::Result {
+        let Some(toc) = self.options().toc else {
+            return Ok(());
+        };
+        // Every heading takes an ID in document order, as when rendering them
+        let mut slugger = Slugger::default();
+        let entries: Vec<(u8, String, String)> = outline(ast)
+            .into_iter()
+            .map(|heading| (heading.level, slugger.slug(&heading.text), heading.text))
+            .filter(|(level, _, _)| (toc.min_depth..=toc.max_depth).contains(level))
+            .collect();
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        self.start_tag(
+            out,
+            Element::TableOfContents,
+            vec![("class", "toc".to_string())],
+        )?;
+        // Levels of the lists still open. Headings deeper than the previous one
+        // open a nested list, and shallower ones close lists down to their level.
+        let mut levels: Vec<u8> = Vec::new();
+        for (level, id, text) in entries {
+            match levels.last() {
+                Some(&last) if level <= last => {
+                    out.write_str("</li>")?;
+                    while levels.len() > 1 && level <= levels[levels.len() - 2] {
+                        out.write_str("</ul></li>")?;
+                        levels.pop();
+                    }
+                }
+                _ => {
+                    out.write_str("<ul>")?;
+                    levels.push(level);
                 }
-                visit_inline(buffer, node);
             }
-            buffer.push_str("</p>");
+            out.write_str("<li><a")?;
+            self.attributes(out, &mut [("href", format!("#{}", id))])?;
+            out.write_char('>')?;
+            escape_html(out, &text)?;
+            out.write_str("</a>")?;
+        }
+        for _ in levels {
+            out.write_str("</li></ul>")?;
+        }
+        write!(out, "</{}>", Element::TableOfContents)
+    }
+
+    fn paragraph(&mut self, out: &mut dyn Write, paragraph: &Paragraph) -> fmt::Result {
+        let position = source_position(self.options(), &paragraph.span);
+        self.start_tag(out, Element::Paragraph, position)?;
+        self.paragraph_content(out, &paragraph.children)?;
+        out.write_str("</p>")
+    }
+
+    fn code_block(&mut self, out: &mut dyn Write, code_block: &CodeBlock) -> fmt::Result {
+        #[cfg(feature = "highlight")]
+        if let (Some(highlighter), Some(language)) =
+            (&self.options().highlighter, &code_block.language)
+        {
+            if let Some(html) = highlighter.highlight(&plain_text(&code_block.code), language) {
+                let mut attributes: Vec<_> = highlighter
+                    .pre_style()
+                    .map(|style| ("style", style))
+                    .into_iter()
+                    .collect();
+                attributes.extend(source_position(self.options(), &code_block.span));
+                self.start_tag(out, Element::CodeBlock, attributes)?;
+                out.write_str("<code")?;
+                self.attributes(out, &mut [("class", format!("language-{}", language))])?;
+                out.write_char('>')?;
+                out.write_str(&html)?;
+                return out.write_str("</code></pre>");
+            }
+        }
+
+        let position = source_position(self.options(), &code_block.span);
+        self.start_tag(out, Element::CodeBlock, position)?;
+        out.write_str("<code")?;
+        if let Some(language) = &code_block.language {
+            self.attributes(out, &mut [("class", format!("language-{}", language))])?;
+        }
+        out.write_char('>')?;
+        self.render_nodes(out, &code_block.code)?;
+        out.write_str("</code></pre>")
+    }
+
+    fn block_quote(&mut self, out: &mut dyn Write, quote: &BlockQuote) -> fmt::Result {
+        let position = source_position(self.options(), &quote.span);
+        self.start_tag(out, Element::BlockQuote, position)?;
+        self.render_nodes(out, &quote.children)?;
+        out.write_str("</blockquote>")
+    }
+
+    fn list(&mut self, out: &mut dyn Write, list: &List) -> fmt::Result {
+        let element = if list.ordered {
+            Element::OrderedList
+        } else {
+            Element::UnorderedList
+        };
+        let mut attributes = match list.start {
+            Some(start) if start != 1 => vec![("start", start.to_string())],
+            _ => Vec::new(),
+        };
+        attributes.extend(source_position(self.options(), &list.span));
+        self.start_tag(out, element, attributes)?;
+        self.render_nodes(out, &list.children)?;
+        write!(out, "</{}>", element)
+    }
+
+    fn list_item(&mut self, out: &mut dyn Write, item: &ListItem) -> fmt::Result {
+        let position = source_position(self.options(), &item.span);
+        self.start_tag(out, Element::ListItem, position)?;
+        for node in item.children.iter() {
+            match node {
+                // List items render their paragraphs tightly,
+                // without wrapping them in a paragraph element.
+                Node::Paragraph(paragraph) => self.paragraph_content(out, &paragraph.children)?,
+                node => self.render_node(out, node)?,
+            }
+        }
+        out.write_str("</li>")
+    }
+
+    fn thematic_break(&mut self, out: &mut dyn Write) -> fmt::Result {
+        self.start_tag(out, Element::ThematicBreak, Vec::new())
+    }
+
+    fn bold(&mut self, out: &mut dyn Write, bold: &Bold) -> fmt::Result {
+        self.start_tag(out, Element::Bold, Vec::new())?;
+        self.render_nodes(out, &bold.children)?;
+        out.write_str("</strong>")
+    }
+
+    fn italic(&mut self, out: &mut dyn Write, italic: &Italic) -> fmt::Result {
+        self.start_tag(out, Element::Italic, Vec::new())?;
+        self.render_nodes(out, &italic.children)?;
+        out.write_str("</em>")
+    }
+
+    fn link(&mut self, out: &mut dyn Write, link: &Link) -> fmt::Result {
+        let options = self.options();
+        let href = safe_url(options, &link.url);
+        let external = options
+            .external_links
+            .as_ref()
+            .filter(|external| external.is_external(&href));
+        let mut attributes = vec![("href", href)];
+        if let Some(title) = &link.title {
+            attributes.push(("title", plain_text(title)));
+        }
+        if let Some(external) = external {
+            if !external.rel.is_empty() {
+                attributes.push(("rel", external.rel.clone()));
+            }
+            if external.new_tab {
+                attributes.push(("target", "_blank".to_string()));
+            }
+        }
+        self.start_tag(out, Element::Link, attributes)?;
+        self.render_nodes(out, &link.children)?;
+        out.write_str("</a>")
+    }
+
+    fn image(&mut self, out: &mut dyn Write, image: &Image) -> fmt::Result {
+        let attributes = self.image_attributes(image);
+        self.start_tag(out, Element::Image, attributes)
+    }
+
+    /// Attributes of the `<img>` element of an image, as set up by the options.
+    /// Overrides of [`image`](Renderer::image) writing other markup,
+    /// like a `<picture>` element, can start from them.
+    fn image_attributes(&self, image: &Image) -> Vec<(&'static str, String)> {
+        let options = self.options();
+        let src = safe_url(options, &image.url);
+        let dimensions = options
+            .image_dimensions
+            .as_ref()
+            .filter(|_| !src.is_empty())
+            .and_then(|dimensions| dimensions.lookup(&src));
+
+        let mut attributes = vec![("src", src), ("alt", plain_text(&image.alt))];
+        if let Some(title) = &image.title {
+            attributes.push(("title", plain_text(title)));
+        }
+        if let Some((width, height)) = dimensions {
+            attributes.push(("width", width.to_string()));
+            attributes.push(("height", height.to_string()));
+        }
+        if options.lazy_images {
+            attributes.push(("loading", "lazy".to_string()));
+            attributes.push(("decoding", "async".to_string()));
+        }
+        attributes
+    }
+
+    /// Writes the opening tag of an element with the given attributes,
+    /// followed by the extra ones the options add to this kind of element.
+    fn start_tag(
+        &mut self,
+        out: &mut dyn Write,
+        element: Element,
+        mut attributes: Vec<(&'static str, String)>,
+    ) -> fmt::Result {
+        if let Some(hook) = &self.options().element_attributes {
+            for (name, value) in hook.attributes(element) {
+                let existing = attributes.iter_mut().find(|(other, _)| *other == name);
+                match existing {
+                    Some((_, classes)) if name == "class" => {
+                        classes.push(' ');
+                        classes.push_str(&value);
+                    }
+                    Some(_) => {}
+                    None => attributes.push((name, value)),
+                }
+            }
+        }
+        write!(out, "<{}", element)?;
+        self.attributes(out, &mut attributes)?;
+        if element.is_void() {
+            out.write_str(void_end(self.options()))
+        } else {
+            out.write_char('>')
         }
-        _ => panic!("Node {:#?} not supported as a block node type", node),
     }
-}
 
-fn visit_inline(buffer: &mut String, node: &Node) {
-    match node {
-        Node::Text(txt) => buffer.push_str(txt),
-        Node::Bold(bold) => {
-            buffer.push_str("<strong>");
-            visit_inline_nodes(buffer, &bold.children);
-            buffer.push_str("</strong>");
+    /// Writes the attributes of an element, in the order set by the options.
+    /// Values are escaped, so they can't break out of their quotes.
+    fn attributes(
+        &mut self,
+        out: &mut dyn Write,
+        attributes: &mut [(&str, String)],
+    ) -> fmt::Result {
+        if self.options().attribute_order == AttributeOrder::Alphabetical {
+            attributes.sort_by_key(|(name, _)| *name);
+        }
+        for (name, value) in attributes.iter() {
+            write!(out, r#" {}=""#, name)?;
+            escape_attribute(out, value)?;
+            out.write_char('"')?;
         }
-        Node::Digit(d) => buffer.push_str(d),
-        Node::LineBreak => buffer.push_str("<br>"),
-        Node::Italic(italic) => {
-            buffer.push_str("<em>");
-            visit_inline_nodes(buffer, &italic.children);
-            buffer.push_str("</em>");
+        Ok(())
+    }
+
+    /// Plain text, which includes digits
+    fn text(&mut self, out: &mut dyn Write, text: &str) -> fmt::Result {
+        let options = self.options();
+        match &options.sanitizer {
+            _ if options.unsafe_html => out.write_str(text),
+            Some(sanitizer) => sanitizer.write_clean(out, text, options),
+            None => escape_html(out, text),
         }
-        Node::Link(link) => {
-            buffer.push_str(r#"<a href=""#);
-            visit_inline_nodes(buffer, &link.url);
-            buffer.push_str(r#"">"#);
-            visit_inline_nodes(buffer, &link.children);
-            buffer.push_str("</a>");
+    }
+
+    fn line_break(&mut self, out: &mut dyn Write) -> fmt::Result {
+        self.start_tag(out, Element::LineBreak, Vec::new())
+    }
+
+    fn comment(&mut self, out: &mut dyn Write, comment: &str) -> fmt::Result {
+        let options = self.options();
+        match options.keep_comments {
+            true if options.unsafe_html => write!(out, "<!--{}-->", comment),
+            true => write!(out, "<!--{}-->", safe_comment(comment)),
+            false => Ok(()),
         }
-        _ => panic!("Node {:#?} not supported as a inline node type", node),
     }
 }
 
-fn visit_inline_nodes(buffer: &mut String, nodes: &[Node]) {
-    for inline in nodes.iter() {
-        visit_inline(buffer, inline);
+/// Content of a comment which can't close it before its `-->`, as `--!>`
+/// or a leading `>` would in HTML5, letting the rest be read as markup
+fn safe_comment(comment: &str) -> String {
+    let mut safe = String::with_capacity(comment.len());
+    if comment.starts_with('>') || comment.starts_with("->") {
+        safe.push(' ');
     }
+    for c in comment.chars() {
+        if c == '-' && safe.ends_with('-') {
+            safe.push(' ');
+        }
+        safe.push(c);
+    }
+    if safe.ends_with('-') {
+        safe.push(' ');
+    }
+    safe
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
+/// Writes the text with the characters that would otherwise
+/// be read as markup replaced by their entities
+pub(crate) fn escape_html(out: &mut dyn Write, text: &str) -> fmt::Result {
+    escape_chars(out, text, &['<', '>', '&'])
+}
 
-    use crate::renderer::render_html;
+/// Same as [`escape_html`], also escaping the quotes delimiting attribute values
+pub(crate) fn escape_attribute(out: &mut dyn Write, text: &str) -> fmt::Result {
+    escape_chars(out, text, &['<', '>', '&', '"'])
+}
 
-    #[test]
-    fn render_html_string() {
-        insta::glob!("snapshot_inputs/*.md", |path| {
-            let markdown = fs::read_to_string(path).unwrap();
-            let result = render_html(&markdown);
-            insta::assert_json_snapshot!(result);
-        });
+fn escape_chars(out: &mut dyn Write, text: &str, special: &[char]) -> fmt::Result {
+    let mut rest = text;
+    while let Some(idx) = rest.find(special) {
+        out.write_str(&rest[..idx])?;
+        let entity = match rest.as_bytes()[idx] {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => "&amp;",
+        };
+        out.write_str(entity)?;
+        rest = &rest[idx + 1..];
     }
+    out.write_str(rest)
+}
 
-    #[test]
-    fn render_plan() {
-        let markdown = r"
-## Title
+/// `data-sourcepos` attribute of a block element, when the options ask for it
+fn source_position(options: &HtmlOptions, span: &Range<Span>) -> Vec<(&'static str, String)> {
+    if !options.source_positions {
+        return Vec::new();
+    }
+    vec![("data-sourcepos", sourcepos(span))]
+}
 
-I'm a **paragraph**.
-";
-        let html = render_html(markdown);
-        assert_eq!(
-            html,
+/// Value of the `data-sourcepos` attribute of an element spanning the tokens,
+/// like `1:1-2:5`. The range goes from the first character of the first token
+/// to the last character of the last one, both included.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::Node, parser, renderer};
+/// let ast = parser::parse("Intro\n\n## Usage").unwrap();
+/// let Node::Header(header) = &ast[1] else { unreachable!() };
+/// assert_eq!(renderer::sourcepos(&header.span), "3:1-3:8");
+/// ```
+pub fn sourcepos(span: &Range<Span>) -> String {
+    // Token columns point right after the token
+    let (start, end) = (&span.start, &span.end);
+    let length = start.end.saturating_sub(start.start);
+    let start_col = start.col.saturating_sub(length) + 1;
+    format!("{}:{}-{}:{}", start.line, start_col, end.line, end.col)
+}
+
+/// Whether the node is a paragraph made of a `[TOC]` or `{:toc}` marker only
+fn is_toc_marker(node: &Node) -> bool {
+    let Node::Paragraph(paragraph) = node else {
+        return false;
+    };
+    let is_text = |node: &Node| matches!(node, Node::Text(_) | Node::LineBreak);
+    paragraph.children.iter().all(is_text)
+        && matches!(plain_text(&paragraph.children).trim(), "[TOC]" | "{:toc}")
+}
+
+/// Text of the given nodes, without any markup
+fn plain_text(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    collect_text(&mut text, nodes);
+    text
+}
+
+/// Encoded destination of a link or image, as rewritten by the options,
+/// or an empty one when its scheme is not allowed by the options.
+fn safe_url(options: &HtmlOptions, url: &[Node]) -> String {
+    sanitize_url(options, &plain_text(url))
+}
+
+/// Destination of a link or image as the renderer writes it: rewritten
+/// and encoded, or empty when its scheme is not allowed by the options.
+/// Meant for renderers building other kinds of output than HTML strings.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::renderer::{sanitize_url, HtmlOptions};
+/// let options = HtmlOptions::default();
+/// assert_eq!(sanitize_url(&options, "/docs/getting started"), "/docs/getting%20started");
+/// assert_eq!(sanitize_url(&options, "javascript:alert(1)"), "");
+/// ```
+pub fn sanitize_url(options: &HtmlOptions, url: &str) -> String {
+    let url = match &options.link_rewriter {
+        Some(rewriter) => rewriter.rewrite(url),
+        None => Cow::Borrowed(url),
+    };
+    if is_allowed_url(options, &url) {
+        encode_url(&url)
+    } else {
+        String::new()
+    }
+}
+
+pub(crate) fn is_allowed_url(options: &HtmlOptions, url: &str) -> bool {
+    options.unsafe_html
+        || match url_scheme(url) {
+            Some(scheme) => options
+                .allowed_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&scheme)),
+            None => true,
+        }
+}
+
+/// Scheme of the URL, e.g. `https` in `https://bpaulino.com`,
+/// or `None` for relative URLs.
+fn url_scheme(url: &str) -> Option<String> {
+    // Browsers ignore leading whitespace and control characters,
+    // as well as tabs and newlines anywhere in the URL.
+    let url: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let end = url.find([':', '/', '?', '#'])?;
+    url[end..].starts_with(':').then(|| url[..end].to_string())
+}
+
+/// Host of the URL in lowercase, without its port nor credentials,
+/// e.g. `bpaulino.com` in `https://bpaulino.com:443/blog`,
+/// or `None` for relative URLs and URLs without any host like `mailto:`.
+/// Backslashes count as slashes, as browsers read `https:\\evil.com` as `https://evil.com`.
+fn url_host(url: &str) -> Option<String> {
+    let url = url.trim_start_matches(|c: char| c <= ' ');
+    let rest = match url_scheme(url) {
+        Some(scheme) => &url[scheme.len() + 1..],
+        None => url,
+    };
+    let is_slash = |c: char| c == '/' || c == '\\';
+    let authority = rest.strip_prefix(is_slash)?.strip_prefix(is_slash)?;
+    let authority = &authority[..authority
+        .find(['/', '\\', '?', '#'])
+        .unwrap_or(authority.len())];
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Percent-encodes the bytes which are not allowed in a URL, like spaces,
+/// quotes or non-ASCII characters. Existing escapes like `%20` are kept as is.
+fn encode_url(url: &str) -> String {
+    const SAFE: &[u8] = b"-_.!~*'();/?:@&=+$,%#";
+    let mut encoded = String::with_capacity(url.len());
+    for byte in url.bytes() {
+        if byte.is_ascii_alphanumeric() || SAFE.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            // Writing into a String never fails
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+/// End of void elements, which have no closing tag
+fn void_end(options: &HtmlOptions) -> &'static str {
+    if options.self_closing_tags {
+        " />"
+    } else {
+        ">"
+    }
+}
+
+/// The default renderer, producing HTML as configured by its [`HtmlOptions`]
+#[derive(Debug, Default, Clone)]
+pub struct HtmlRenderer {
+    options: HtmlOptions,
+    slugger: Slugger,
+}
+
+impl HtmlRenderer {
+    pub fn new(options: HtmlOptions) -> Self {
+        Self {
+            options,
+            slugger: Slugger::default(),
+        }
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn options(&self) -> &HtmlOptions {
+        &self.options
+    }
+
+    fn slugger(&mut self) -> Option<&mut Slugger> {
+        Some(&mut self.slugger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::{self, Write},
+        fs,
+    };
+
+    use crate::{
+        ast::{Image, Link},
+        parser::{self, Extensions, ParserOptions},
+        renderer::{
+            render_html, render_html_fmt, render_html_with, AttributeOrder, Element,
+            ElementAttributes, ExternalLinks, HeadingAnchors, HtmlOptions, HtmlRenderer,
+            ImageDimensions, LinkRewriter, RenderError, Renderer, TocOptions,
+        },
+        sanitizer::Sanitizer,
+        toc::Slugger,
+    };
+
+    #[test]
+    fn render_html_string() {
+        insta::glob!("snapshot_inputs/*.md", |path| {
+            let markdown = fs::read_to_string(path).unwrap();
+            let result = render_html(&markdown).unwrap();
+            insta::assert_json_snapshot!(result);
+        });
+    }
+
+    #[test]
+    fn render_plan() {
+        let markdown = r"
+## Title
+
+I'm a **paragraph**.
+";
+        let html = render_html(markdown).unwrap();
+        assert_eq!(
+            html,
             "<h2>Title</h2><p>I'm a <strong>paragraph</strong>.</p>"
         );
     }
+
+    #[test]
+    fn render_nested_emphasis() {
+        let cases = [
+            (
+                "*italic with **bold** inside*",
+                "<p><em>italic with <strong>bold</strong> inside</em></p>",
+            ),
+            (
+                "__bold _and italic_ text__",
+                "<p><strong>bold <em>and italic</em> text</strong></p>",
+            ),
+            (
+                "***both at once***",
+                "<p><em><strong>both at once</strong></em></p>",
+            ),
+            ("**unbalanced*", "<p>*<em>unbalanced</em></p>"),
+            ("* not emphasis *", "<ul><li>not emphasis *</li></ul>"),
+            ("a * not emphasis *", "<p>a * not emphasis *</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_emphasis_flanking_rules() {
+        let cases = [
+            ("a**b **c", "<p>a**b **c</p>"),
+            ("foo**bar**baz", "<p>foo<strong>bar</strong>baz</p>"),
+            (r#"a*"foo"*"#, r#"<p>a*"foo"*</p>"#),
+            (r#"*"foo"*"#, r#"<p><em>"foo"</em></p>"#),
+            (
+                "**foo \"*bar*\" foo**",
+                "<p><strong>foo \"<em>bar</em>\" foo</strong></p>",
+            ),
+            (
+                "*foo**bar**baz*",
+                "<p><em>foo<strong>bar</strong>baz</em></p>",
+            ),
+            ("*foo**bar*", "<p><em>foo**bar</em></p>"),
+            ("5*6*78", "<p>5<em>6</em>78</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_unicode_whitespace() {
+        let cases = [
+            // Only spaces and tabs may follow the opening hashes of a heading
+            ("#\u{a0}Title", "<p>#\u{a0}Title</p>"),
+            ("##\u{3000}Title", "<p>##\u{3000}Title</p>"),
+            ("# Title\u{a0}", "<h1>Title\u{a0}</h1>"),
+            ("**\u{a0}foo**", "<p>**\u{a0}foo**</p>"),
+            ("**foo**\u{a0}bar", "<p><strong>foo</strong>\u{a0}bar</p>"),
+            ("foo\u{2009}_bar_", "<p>foo\u{2009}<em>bar</em></p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {:?}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_backslash_escapes() {
+        let cases = [
+            (r"\*not emphasis\*", "<p>*not emphasis*</p>"),
+            (r"\[not a link\](/url)", "<p>[not a link](/url)</p>"),
+            (r"\# not a heading", "<p># not a heading</p>"),
+            (
+                r"\<!-- not a comment -->",
+                "<p>&lt;!-- not a comment --&gt;</p>",
+            ),
+            (r"a \\ b \+ c", r"<p>a \ b + c</p>"),
+            (r"C:\Users", r"<p>C:\Users</p>"),
+            ("hard\\\nbreak", "<p>hard<br>break</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {:?}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_byte_order_mark_and_zero_width_joiners() {
+        let cases = [
+            ("\u{feff}# Title", "<h1>Title</h1>"),
+            ("\u{feff}- item", "<ul><li>item</li></ul>"),
+            ("\u{200d}# Title", "<p>\u{200d}# Title</p>"),
+            ("*a\u{200d}*", "<p><em>a\u{200d}</em></p>"),
+            ("**👩\u{200d}💻**", "<p><strong>👩\u{200d}💻</strong></p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {:?}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_intraword_emphasis() {
+        let cases = [
+            ("foo*bar*baz", "<p>foo<em>bar</em>baz</p>"),
+            ("foo_bar_baz", "<p>foo_bar_baz</p>"),
+            (
+                "call snake_case_name here",
+                "<p>call snake_case_name here</p>",
+            ),
+            ("foo__bar__baz", "<p>foo__bar__baz</p>"),
+            ("_foo_bar_", "<p><em>foo_bar</em></p>"),
+            ("(_foo_)", "<p>(<em>foo</em>)</p>"),
+            ("_foo_bar", "<p>_foo_bar</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_link_destinations() {
+        let cases = [
+            (
+                "[Foo](https://en.wikipedia.org/wiki/Foo_(bar))",
+                r#"<p><a href="https://en.wikipedia.org/wiki/Foo_(bar)">Foo</a></p>"#,
+            ),
+            (
+                "[nested](/a(b(c))d) after",
+                r#"<p><a href="/a(b(c))d">nested</a> after</p>"#,
+            ),
+            (
+                "[spaces](<my file.md>)",
+                r#"<p><a href="my%20file.md">spaces</a></p>"#,
+            ),
+            ("[unbalanced](/a(b)", "<p>[unbalanced](/a(b)</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_nested_brackets_in_link_text() {
+        let cases = [
+            (
+                "[see [spec] here](/spec)",
+                r#"<p><a href="/spec">see [spec] here</a></p>"#,
+            ),
+            ("[[double]](/d)", r#"<p><a href="/d">[double]</a></p>"#),
+            (
+                "[unbalanced [text](/a)",
+                r#"<p>[unbalanced <a href="/a">text</a></p>"#,
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_images() {
+        let cases = [
+            (
+                "![Gohan](./icon.png)",
+                r#"<p><img src="./icon.png" alt="Gohan"></p>"#,
+            ),
+            (
+                r#"![The **Gohan** _icon_](./icon.png "Gohan icon")"#,
+                r#"<p><img src="./icon.png" alt="The Gohan icon" title="Gohan icon"></p>"#,
+            ),
+            (
+                r#"[link](<my page.md> "A title")"#,
+                r#"<p><a href="my%20page.md" title="A title">link</a></p>"#,
+            ),
+            ("Bang! ![not an image]", "<p>Bang! ![not an image]</p>"),
+            ("[no title](/a b)", "<p>[no title](/a b)</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_empty_link_parts() {
+        let cases = [
+            ("[](/url)", r#"<p><a href="/url"></a></p>"#),
+            ("a [](/url) b", r#"<p>a <a href="/url"></a> b</p>"#),
+            ("[text]()", r#"<p><a href="">text</a></p>"#),
+            ("[text](<>)", r#"<p><a href="">text</a></p>"#),
+            ("- [text]( )", r#"<ul><li><a href="">text</a></li></ul>"#),
+            ("[]()", r#"<p><a href=""></a></p>"#),
+            ("![](/x.png)", r#"<p><img src="/x.png" alt=""></p>"#),
+            ("[](", "<p>[](</p>"),
+            ("[]", "<p>[]</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn degrade_deeply_nested_markdown() {
+        // Quotes beyond the maximum depth are kept as text within the deepest one
+        let quotes = render_html(&">".repeat(500)).unwrap();
+        assert!(quotes.starts_with("<blockquote><blockquote>"));
+        assert!(quotes.contains("&gt;&gt;"));
+
+        let emphasis = format!("{}a{}", "*".repeat(1000), "*".repeat(1000));
+        assert!(render_html(&emphasis).unwrap().contains("***"));
+
+        let nested = format!("{} quote", "> ".repeat(100));
+        assert!(render_html(&nested).unwrap().contains("<p>quote</p>"));
+    }
+
+    #[test]
+    fn render_html_comments() {
+        let markdown =
+            "<!-- draft -->\n# Title\n\nSome <!-- inline --> text\n\n  <!--\nmulti *line*\n-->\n";
+        assert_eq!(
+            render_html(markdown).unwrap(),
+            "<h1>Title</h1><p>Some  text</p>"
+        );
+
+        let options = HtmlOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(markdown, &options).unwrap(),
+            "<!-- draft --><h1>Title</h1><p>Some <!-- inline --> text</p><!--\nmulti *line*\n-->"
+        );
+
+        let options = HtmlOptions {
+            parser: ParserOptions {
+                extensions: Extensions::empty(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("Some <!-- inline --> text", &options).unwrap(),
+            "<p>Some &lt;!-- inline --&gt; text</p>"
+        );
+
+        let cases = [
+            ("<!-- not closed", "<p>&lt;!-- not closed</p>"),
+            ("> <!-- quoted -->", "<blockquote></blockquote>"),
+            (
+                "```\n<!-- code -->\n```",
+                "<pre><code>&lt;!-- code --&gt;\n</code></pre>",
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn keep_comments_from_closing_early() {
+        let options = HtmlOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let cases = [
+            (
+                "<!-- --!><script>alert(1)</script> -->",
+                "<!-- - -!><script>alert(1)</script> -->",
+            ),
+            (
+                "<!-->x<script>alert(1)</script>-->",
+                "<!-- >x<script>alert(1)</script>-->",
+            ),
+            ("<!---><b>x</b>-->", "<!-- -><b>x</b>-->"),
+            ("<!-- a <!-- b - -->", "<!-- a <!- - b - -->"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html_with(markdown, &options).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+
+        let options = HtmlOptions {
+            unsafe_html: true,
+            ..options
+        };
+        assert_eq!(
+            render_html_with("<!-- a -- b -->", &options).unwrap(),
+            "<!-- a -- b -->"
+        );
+    }
+
+    #[test]
+    fn render_images_inside_links() {
+        let cases = [
+            (
+                "[![badge](img.png)](https://ci.example.com)",
+                r#"<p><a href="https://ci.example.com"><img src="img.png" alt="badge"></a></p>"#,
+            ),
+            (
+                "[Logo ![icon](i.png) here](/home)",
+                r#"<p><a href="/home">Logo <img src="i.png" alt="icon"> here</a></p>"#,
+            ),
+            (
+                "[![not a badge]](/a)",
+                r#"<p><a href="/a">![not a badge]</a></p>"#,
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_code_blocks() {
+        let markdown = r"Some code:
+```{.rust linenos}
+fn main() {
+    println!();
+}
+```
+
+```
+no language
+";
+        let html = render_html(markdown).unwrap();
+        assert_eq!(
+            html,
+            "<p>Some code:</p><pre><code class=\"language-rust\">fn main() {\n    println!();\n}\n</code></pre>\
+            <pre><code>no language\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn keep_tabs_in_code_blocks() {
+        let cases = [
+            (
+                "```make\nall:\n\techo hi\n```",
+                "<pre><code class=\"language-make\">all:\n\techo hi\n</code></pre>",
+            ),
+            ("```\na\tb\n```", "<pre><code>a\tb\n</code></pre>"),
+            // Spaces of a tab taken as indentation leave the rest as spaces
+            ("  ```\n\tx\n  ```", "<pre><code>  x\n</code></pre>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {:?}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_headings_with_closing_sequence() {
+        let cases = [
+            ("## Title ##", "<h2>Title</h2>"),
+            ("# Title #####   ", "<h1>Title</h1>"),
+            ("### Title#", "<h3>Title#</h3>"),
+            ("## ##", "<h2></h2>"),
+            ("#   Spaced   ", "<h1>Spaced</h1>"),
+            ("# C# is # a language", "<h1>C# is # a language</h1>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_thematic_breaks() {
+        let cases = [
+            ("***", "<hr>"),
+            ("* * *", "<hr>"),
+            ("- - -", "<hr>"),
+            ("_____", "<hr>"),
+            ("-  -   -  ", "<hr>"),
+            (
+                "- foo\n- - -\n- bar",
+                "<ul><li>foo</li></ul><hr><ul><li>bar</li></ul>",
+            ),
+            ("text\n***\nmore", "<p>text</p><hr><p>more</p>"),
+            ("--", "<p>--</p>"),
+            ("*-*", "<p><em>-</em></p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_indented_block_markers() {
+        let cases = [
+            ("   ## Title", "<h2>Title</h2>"),
+            ("  > quote", "<blockquote><p>quote</p></blockquote>"),
+            (" ***", "<hr>"),
+            ("  - a\n    b\n - c", "<ul><li>a<br>b</li><li>c</li></ul>"),
+            (
+                "  ```\n  code\n   indented\n  ```",
+                "<pre><code>code\n indented\n</code></pre>",
+            ),
+            (
+                "    ## code\n\n      more\n",
+                "<pre><code>## code\n\n  more\n</code></pre>",
+            ),
+            ("text\n    ## not code", "<p>text<br>    ## not code</p>"),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_lazy_continuation_lines() {
+        let markdown = r"> A quote that
+keeps going lazily.
+
+- A list item that
+continues here
+- Second item
+";
+        let html = render_html(markdown).unwrap();
+        assert_eq!(
+            html,
+            "<blockquote><p>A quote that<br>keeps going lazily.</p></blockquote>\
+            <ul><li>A list item that<br>continues here</li><li>Second item</li></ul>"
+        );
+    }
+
+    #[test]
+    fn render_lists_indented_with_tabs() {
+        let with_tabs = render_html("-\tFirst\n\t-\tNested item\n").unwrap();
+        let with_spaces = render_html("-   First\n    -   Nested item\n").unwrap();
+        assert_eq!(with_tabs, with_spaces);
+        assert_eq!(
+            with_tabs,
+            "<ul><li>First<ul><li>Nested item</li></ul></li></ul>"
+        );
+    }
+
+    #[test]
+    fn render_ordered_list_start() {
+        let cases = [
+            (
+                "3. three\n7. four",
+                r#"<ol start="3"><li>three</li><li>four</li></ol>"#,
+            ),
+            ("1. one\n1. two", "<ol><li>one</li><li>two</li></ol>"),
+            ("0. zero", r#"<ol start="0"><li>zero</li></ol>"#),
+            ("2024. year", r#"<ol start="2024"><li>year</li></ol>"#),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn render_nested_lists() {
+        let markdown = r"1. First
+   - Nested item
+lazy line
+2. Second
+";
+        let html = render_html(markdown).unwrap();
+        assert_eq!(
+            html,
+            "<ol><li>First<ul><li>Nested item<br>lazy line</li></ul></li><li>Second</li></ol>"
+        );
+    }
+
+    const STREAMED_MARKDOWN: &str =
+        "# Gohan\n\nSome **bold** text and a [link](/home)\n\n- one\n- two\n";
+
+    #[test]
+    fn stream_html_into_fmt_writers() {
+        let mut text = String::from("<main>");
+        render_html_fmt(STREAMED_MARKDOWN, &mut text).unwrap();
+        assert_eq!(
+            text,
+            format!("<main>{}", render_html(STREAMED_MARKDOWN).unwrap())
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream_html_into_io_writers() {
+        use crate::renderer::render_html_to;
+
+        let mut bytes = Vec::new();
+        render_html_to(STREAMED_MARKDOWN, &mut bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            render_html(STREAMED_MARKDOWN).unwrap()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn report_failing_writers() {
+        use std::io;
+
+        use crate::renderer::render_html_to;
+
+        struct Closed;
+
+        impl io::Write for Closed {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        match render_html_to("# Gohan", &mut Closed) {
+            Err(RenderError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::BrokenPipe),
+            result => panic!("expected an IO error, got {:?}", result),
+        }
+
+        assert!(render_html_to(&">".repeat(500), &mut Vec::new()).is_ok());
+    }
+
+    /// Opens links in a new tab and leaves images out
+    struct NewTabLinks(HtmlRenderer);
+
+    impl Renderer for NewTabLinks {
+        fn options(&self) -> &HtmlOptions {
+            self.0.options()
+        }
+
+        fn slugger(&mut self) -> Option<&mut Slugger> {
+            self.0.slugger()
+        }
+
+        fn link(&mut self, out: &mut dyn Write, link: &Link) -> fmt::Result {
+            out.write_str(r#"<a target="_blank" href=""#)?;
+            self.render_nodes(out, &link.url)?;
+            out.write_str(r#"">"#)?;
+            self.render_nodes(out, &link.children)?;
+            out.write_str("</a>")
+        }
+
+        fn image(&mut self, _out: &mut dyn Write, _image: &Image) -> fmt::Result {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn render_with_custom_renderer() {
+        let markdown = "# [Gohan](/gohan)\n\n- **[docs](/docs) ![logo](logo.png)**\n";
+        let ast = parser::parse(markdown).unwrap();
+
+        assert_eq!(
+            NewTabLinks(HtmlRenderer::default()).render(&ast),
+            r#"<h1><a target="_blank" href="/gohan">Gohan</a></h1><ul><li><strong><a target="_blank" href="/docs">docs</a> </strong></li></ul>"#
+        );
+        assert_eq!(
+            HtmlRenderer::default().render(&ast),
+            render_html(markdown).unwrap()
+        );
+    }
+
+    /// Offers a WebP version of PNG images
+    struct Pictures(HtmlRenderer);
+
+    impl Renderer for Pictures {
+        fn options(&self) -> &HtmlOptions {
+            self.0.options()
+        }
+
+        fn image(&mut self, out: &mut dyn Write, image: &Image) -> fmt::Result {
+            let mut attributes = self.image_attributes(image);
+            let src = attributes.iter().find(|(name, _)| *name == "src");
+            let Some(webp) = src.and_then(|(_, src)| src.strip_suffix(".png")) else {
+                return self.0.image(out, image);
+            };
+            write!(
+                out,
+                r#"<picture><source srcset="{}.webp" type="image/webp"><img"#,
+                webp
+            )?;
+            self.attributes(out, &mut attributes)?;
+            out.write_str("></picture>")
+        }
+    }
+
+    #[test]
+    fn render_images_with_options_and_custom_markup() {
+        let ast = parser::parse("![a](a.png \"A\") ![b](b.jpg)").unwrap();
+        let options = HtmlOptions {
+            lazy_images: true,
+            image_dimensions: Some(ImageDimensions::new(|src| match src {
+                "a.png" => Some((800, 600)),
+                _ => None,
+            })),
+            attribute_order: AttributeOrder::Alphabetical,
+            ..Default::default()
+        };
+        assert_eq!(
+            Pictures(HtmlRenderer::new(options)).render(&ast),
+            concat!(
+                r#"<p><picture><source srcset="a.webp" type="image/webp"><img alt="a" decoding="async" height="600" loading="lazy" src="a.png" title="A" width="800"></picture> "#,
+                r#"<img alt="b" decoding="async" loading="lazy" src="b.jpg"></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn keep_options_in_custom_renderers() {
+        let ast = parser::parse("a <!-- b -->").unwrap();
+        let options = HtmlOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let mut renderer = NewTabLinks(HtmlRenderer::new(options));
+        assert_eq!(renderer.render(&ast), "<p>a <!-- b --></p>");
+    }
+
+    #[test]
+    fn render_with_output_options() {
+        let markdown = "# Title\n\nline\nbreak ![logo](l.png \"Logo\")\n\n---\n";
+        let xhtml = HtmlOptions {
+            self_closing_tags: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(markdown, &xhtml).unwrap(),
+            r#"<h1>Title</h1><p>line<br />break <img src="l.png" alt="logo" title="Logo" /></p><hr />"#
+        );
+
+        let sorted = HtmlOptions {
+            attribute_order: AttributeOrder::Alphabetical,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("![logo](l.png \"Logo\") [a](/a \"A\")", &sorted).unwrap(),
+            r#"<p><img alt="logo" src="l.png" title="Logo"> <a href="/a" title="A">a</a></p>"#
+        );
+
+        let document = HtmlOptions {
+            full_document: true,
+            trailing_newline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("Hi", &document).unwrap(),
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"></head><body><p>Hi</p></body></html>\n"
+        );
+
+        let fragment = HtmlOptions {
+            trailing_newline: true,
+            ..Default::default()
+        };
+        assert_eq!(render_html_with("Hi", &fragment).unwrap(), "<p>Hi</p>\n");
+    }
+
+    #[test]
+    fn shift_heading_levels() {
+        let options = HtmlOptions {
+            heading_offset: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("# One\n## Two\n###### Six", &options).unwrap(),
+            "<h2>One</h2><h3>Two</h3><h6>Six</h6>"
+        );
+
+        let options = HtmlOptions {
+            heading_offset: u8::MAX,
+            ..Default::default()
+        };
+        assert_eq!(render_html_with("# One", &options).unwrap(), "<h6>One</h6>");
+    }
+
+    #[test]
+    fn escape_html_in_text() {
+        let cases = [
+            (
+                "<script>alert(1)</script>",
+                "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>",
+            ),
+            ("Fish & Chips", "<p>Fish &amp; Chips</p>"),
+            ("# 1 < 2 > 0", "<h1>1 &lt; 2 &gt; 0</h1>"),
+            ("**<b>**", "<p><strong>&lt;b&gt;</strong></p>"),
+            (
+                "```\nif a < b && c {}\n```",
+                "<pre><code>if a &lt; b &amp;&amp; c {}\n</code></pre>",
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+
+        let trusted = HtmlOptions {
+            unsafe_html: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("<kbd>Ctrl</kbd> & C", &trusted).unwrap(),
+            "<p><kbd>Ctrl</kbd> & C</p>"
+        );
+    }
+
+    #[test]
+    fn escape_attribute_values() {
+        let cases = [
+            (
+                r#"[x](<a" onclick="alert(1)>)"#,
+                r#"<p><a href="a%22%20onclick=%22alert(1)">x</a></p>"#,
+            ),
+            (
+                "[x](/search?q=a&b=<c>)",
+                r#"<p><a href="/search?q=a&amp;b=%3Cc%3E">x</a></p>"#,
+            ),
+            (
+                "[x](/café%20bar)",
+                r#"<p><a href="/caf%C3%A9%20bar">x</a></p>"#,
+            ),
+            (
+                r#"![a "quoted" & <b>](<img src.png> "T<i>")"#,
+                r#"<p><img src="img%20src.png" alt="a &quot;quoted&quot; &amp; &lt;b&gt;" title="T&lt;i&gt;"></p>"#,
+            ),
+            (
+                "```rust\"><script>\ncode\n```",
+                r#"<pre><code class="language-rust&quot;&gt;&lt;script&gt;">code
+</code></pre>"#,
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn block_disallowed_url_schemes() {
+        let cases = [
+            ("[x](javascript:alert(1))", r#"<p><a href="">x</a></p>"#),
+            ("[x](JavaScript:alert(1))", r#"<p><a href="">x</a></p>"#),
+            ("[x](< javascript:alert(1)>)", r#"<p><a href="">x</a></p>"#),
+            (
+                "![x](data:image/png;base64,AAA)",
+                r#"<p><img src="" alt="x"></p>"#,
+            ),
+            ("[x](vbscript:msgbox)", r#"<p><a href="">x</a></p>"#),
+            (
+                "[x](https://bpaulino.com)",
+                r#"<p><a href="https://bpaulino.com">x</a></p>"#,
+            ),
+            (
+                "[x](mailto:hi@gohan.rs)",
+                r#"<p><a href="mailto:hi@gohan.rs">x</a></p>"#,
+            ),
+            ("[x](/a:b)", r#"<p><a href="/a:b">x</a></p>"#),
+            ("[x](?q=a:b#c:d)", r#"<p><a href="?q=a:b#c:d">x</a></p>"#),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html(markdown).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+
+        let options = HtmlOptions {
+            allowed_schemes: vec!["https".to_string(), "ftp".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("[a](ftp://a) [b](http://b)", &options).unwrap(),
+            r#"<p><a href="ftp://a">a</a> <a href="">b</a></p>"#
+        );
+
+        let trusted = HtmlOptions {
+            unsafe_html: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("[x](javascript:void(0))", &trusted).unwrap(),
+            r#"<p><a href="javascript:void(0)">x</a></p>"#
+        );
+    }
+
+    #[test]
+    fn rewrite_link_destinations() {
+        let options = HtmlOptions {
+            link_rewriter: Some(LinkRewriter::new(|url| match url.strip_prefix("slug:") {
+                Some(slug) => format!("/docs/{}.html", slug).into(),
+                None => url.into(),
+            })),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("[a](slug:intro) ![b](b.png)", &options).unwrap(),
+            r#"<p><a href="/docs/intro.html">a</a> <img src="b.png" alt="b"></p>"#
+        );
+
+        // Rewritten destinations are still checked against the allowed schemes
+        let options = HtmlOptions {
+            link_rewriter: Some(LinkRewriter::new(|_| "javascript:alert(1)".into())),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with("[a](/a)", &options).unwrap(),
+            r#"<p><a href="">a</a></p>"#
+        );
+    }
+
+    #[test]
+    fn inject_element_attributes() {
+        let options = HtmlOptions {
+            element_attributes: Some(ElementAttributes::new(|element| match element {
+                Element::Heading(level) => vec![("class", format!("heading-{}", level))],
+                Element::CodeBlock => vec![("class", "code".to_string())],
+                Element::OrderedList => vec![("start", "9".to_string())],
+                Element::Link => vec![
+                    ("href", "javascript:alert(1)".to_string()),
+                    ("data-kind", "link".to_string()),
+                ],
+                Element::ThematicBreak => vec![("class", "my-4".to_string())],
+                _ => Vec::new(),
+            })),
+            self_closing_tags: true,
+            ..Default::default()
+        };
+        let markdown = "## Title\n\n3. [a](/a)\n\n---\n\n```rust\nfn main() {}\n```\n";
+        assert_eq!(
+            render_html_with(markdown, &options).unwrap(),
+            concat!(
+                r#"<h2 class="heading-2">Title</h2>"#,
+                r#"<ol start="3"><li><a href="/a" data-kind="link">a</a></li></ol>"#,
+                r#"<hr class="my-4" />"#,
+                "<pre class=\"code\"><code class=\"language-rust\">fn main() {}\n</code></pre>"
+            )
+        );
+    }
+
+    #[test]
+    fn render_heading_anchors() {
+        let markdown = "# Intro\n\n> ## *Intro*\n\n## Intro 1\n\n### ?!";
+        let anchors = |heading_anchors| HtmlOptions {
+            heading_anchors,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(markdown, &anchors(HeadingAnchors::Id)).unwrap(),
+            concat!(
+                r#"<h1 id="intro">Intro</h1>"#,
+                r#"<blockquote><h2 id="intro-1"><em>Intro</em></h2></blockquote>"#,
+                r#"<h2 id="intro-1-1">Intro 1</h2><h3 id="section">?!</h3>"#
+            )
+        );
+        assert_eq!(
+            render_html_with("# A & B", &anchors(HeadingAnchors::Wrap)).unwrap(),
+            r##"<h1 id="a-b"><a class="anchor" href="#a-b">A &amp; B</a></h1>"##
+        );
+
+        // IDs start over with every rendered document
+        let ast = parser::parse("# Intro").unwrap();
+        let mut renderer = NewTabLinks(HtmlRenderer::new(anchors(HeadingAnchors::Append)));
+        let expected = r##"<h1 id="intro">Intro<a class="anchor" href="#intro" aria-hidden="true">#</a></h1>"##;
+        assert_eq!(renderer.render(&ast), expected);
+        assert_eq!(renderer.render(&ast), expected);
+    }
+
+    #[test]
+    fn render_source_positions() {
+        let markdown = "# Title\n\nSome *text* here\n\n> quote\n\n1. a\n2. b\n\n```\ncode\n```\n";
+        let options = HtmlOptions {
+            source_positions: true,
+            heading_anchors: HeadingAnchors::Id,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(markdown, &options).unwrap(),
+            concat!(
+                r#"<h1 id="title" data-sourcepos="1:1-1:7">Title</h1>"#,
+                r#"<p data-sourcepos="3:1-3:16">Some <em>text</em> here</p>"#,
+                r#"<blockquote data-sourcepos="5:1-5:7"><p data-sourcepos="5:3-5:7">quote</p></blockquote>"#,
+                r#"<ol data-sourcepos="7:1-8:4"><li data-sourcepos="7:1-7:4">a</li><li data-sourcepos="8:1-8:4">b</li></ol>"#,
+                "<pre data-sourcepos=\"10:1-12:3\"><code>code\n</code></pre>"
+            )
+        );
+    }
+
+    #[test]
+    fn render_table_of_contents() {
+        let markdown = "{:toc}\n\n# A\n\n## B\n\n#### C\n\n## B\n\n# D\n\nSee [TOC] here";
+        let options = HtmlOptions {
+            toc: Some(TocOptions::default()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(markdown, &options).unwrap(),
+            concat!(
+                r##"<nav class="toc"><ul><li><a href="#a">A</a><ul><li><a href="#b">B</a>"##,
+                r##"<ul><li><a href="#c">C</a></li></ul></li><li><a href="#b-1">B</a></li></ul></li>"##,
+                r##"<li><a href="#d">D</a></li></ul></nav>"##,
+                r#"<h1 id="a">A</h1><h2 id="b">B</h2><h4 id="c">C</h4><h2 id="b-1">B</h2>"#,
+                r#"<h1 id="d">D</h1><p>See [TOC] here</p>"#
+            )
+        );
+        assert_eq!(
+            render_html_with("[TOC]\n\n# A", &HtmlOptions::default()).unwrap(),
+            "<p>[TOC]</p><h1>A</h1>"
+        );
+    }
+
+    #[test]
+    fn mark_external_links() {
+        let external_links = ExternalLinks {
+            internal_hosts: vec!["bpaulino.com".to_string()],
+            ..Default::default()
+        };
+        let cases = [
+            ("https://github.com/brunojppb", true),
+            ("//cdn.example.com/a.js", true),
+            ("HTTPS://User@Example.com:8080", true),
+            ("https://bpaulino.com/blog", false),
+            ("https://www.BPaulino.com", false),
+            ("https://notbpaulino.com", true),
+            ("https://bpaulino.com.evil.com", true),
+            ("https:\\\\evil.com", true),
+            ("https:/\\evil.com", true),
+            ("\\\\evil.com\\path", true),
+            ("https:\\\\bpaulino.com\\blog", false),
+            ("/about", false),
+            ("#top", false),
+            ("mailto:hi@gohan.rs", false),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(external_links.is_external(url), expected, "url: {}", url);
+        }
+
+        let options = HtmlOptions {
+            external_links: Some(ExternalLinks {
+                rel: "nofollow noopener noreferrer".to_string(),
+                new_tab: false,
+                ..external_links
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_html_with(r#"[a](https://a.com "A") [b](/b)"#, &options).unwrap(),
+            r#"<p><a href="https://a.com" title="A" rel="nofollow noopener noreferrer">a</a> <a href="/b">b</a></p>"#
+        );
+    }
+
+    #[test]
+    fn sanitize_raw_html() {
+        let options = HtmlOptions {
+            sanitizer: Some(Sanitizer::default()),
+            ..Default::default()
+        };
+        let cases = [
+            (
+                "Press <kbd>Ctrl</kbd> + <kbd>2</kbd>",
+                "<p>Press <kbd>Ctrl</kbd> + <kbd>2</kbd></p>",
+            ),
+            (
+                "**<script>alert(1)</script>bold** <b onclick=\"x()\">b</b>",
+                "<p><strong>bold</strong> <b>b</b></p>",
+            ),
+            (
+                "<a href=\"javascript:alert(1)\" title=\"t\">x</a> 1 < 2",
+                r#"<p><a title="t">x</a> 1 &lt; 2</p>"#,
+            ),
+            (
+                "<b>never closed\n\nnext </b>paragraph",
+                "<p><b>never closed</b></p><p>next paragraph</p>",
+            ),
+        ];
+        for (markdown, expected) in cases {
+            assert_eq!(
+                render_html_with(markdown, &options).unwrap(),
+                expected,
+                "input: {}",
+                markdown
+            );
+        }
+
+        assert_eq!(
+            render_html("<kbd>Ctrl</kbd>").unwrap(),
+            "<p>&lt;kbd&gt;Ctrl&lt;/kbd&gt;</p>"
+        );
+    }
 }
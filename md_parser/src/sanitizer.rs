@@ -0,0 +1,376 @@
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+use crate::renderer::{escape_attribute, escape_html, is_allowed_url, HtmlOptions};
+
+/// Tags whose content is dropped along with them,
+/// since it's code or styling rather than text.
+const CONTENT_TAGS: [&str; 2] = ["script", "style"];
+
+/// Tags which never have content nor a closing tag
+const VOID_TAGS: [&str; 6] = ["br", "col", "hr", "img", "source", "wbr"];
+
+/// Attributes holding URLs, which must use one of the allowed schemes
+const URL_ATTRIBUTES: [&str; 3] = ["href", "src", "cite"];
+
+/// Allowlist of the raw HTML tags and attributes which may show up in the output.
+///
+/// Tags missing from the list are stripped, keeping their text escaped,
+/// and so are attributes missing from the list.
+/// The default allowlist only has formatting tags which can't run scripts.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::sanitizer::Sanitizer;
+/// let sanitizer = Sanitizer::default();
+/// assert_eq!(
+///     sanitizer.clean(r#"<kbd onclick="steal()">Ctrl</kbd><script>steal()</script>"#),
+///     "<kbd>Ctrl</kbd>"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: BTreeSet<String>,
+    /// Attributes allowed on every allowed tag
+    attributes: BTreeSet<String>,
+    tag_attributes: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self::empty()
+            .allow_tags([
+                "a", "abbr", "b", "br", "code", "del", "em", "i", "img", "ins", "kbd", "mark", "q",
+                "s", "small", "span", "strong", "sub", "sup", "u",
+            ])
+            .allow_attributes(["lang", "title"])
+            .allow_tag_attributes("a", ["href"])
+            .allow_tag_attributes("img", ["src", "alt", "width", "height"])
+    }
+}
+
+impl Sanitizer {
+    /// Sanitizer which strips every tag
+    pub fn empty() -> Self {
+        Self {
+            tags: BTreeSet::new(),
+            attributes: BTreeSet::new(),
+            tag_attributes: BTreeMap::new(),
+        }
+    }
+
+    pub fn allow_tags<'a>(mut self, tags: impl IntoIterator<Item = &'a str>) -> Self {
+        self.tags
+            .extend(tags.into_iter().map(|tag| tag.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allows the given attributes on every allowed tag
+    pub fn allow_attributes<'a>(mut self, attributes: impl IntoIterator<Item = &'a str>) -> Self {
+        self.attributes
+            .extend(attributes.into_iter().map(|name| name.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allows the given attributes on a single tag
+    pub fn allow_tag_attributes<'a>(
+        mut self,
+        tag: &str,
+        attributes: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        self.tag_attributes
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(attributes.into_iter().map(|name| name.to_ascii_lowercase()));
+        self
+    }
+
+    /// Sanitizes the given HTML with the default [`HtmlOptions`]
+    pub fn clean(&self, html: &str) -> String {
+        let mut output = String::new();
+        self.write_clean(&mut output, html, &HtmlOptions::default())
+            .expect("writing into a String never fails");
+        output
+    }
+
+    /// Writes the allowed tags of the given HTML, balanced so that they can't
+    /// wrap the markup written after it: closing tags without an opening one
+    /// are dropped, and the tags left open are closed at the end.
+    pub(crate) fn write_clean(
+        &self,
+        out: &mut dyn Write,
+        html: &str,
+        options: &HtmlOptions,
+    ) -> fmt::Result {
+        // Allowed tags opened but not closed yet, innermost last
+        let mut open: Vec<String> = Vec::new();
+        let mut rest = html;
+        while let Some(idx) = rest.find('<') {
+            escape_html(out, &rest[..idx])?;
+            rest = &rest[idx..];
+
+            let Some((tag, length)) = parse_tag(rest) else {
+                out.write_str("&lt;")?;
+                rest = &rest[1..];
+                continue;
+            };
+            rest = &rest[length..];
+
+            if !self.tags.contains(&tag.name) {
+                if !tag.closing && CONTENT_TAGS.contains(&tag.name.as_str()) {
+                    rest = skip_content(rest, &tag.name);
+                }
+            } else if !tag.closing {
+                self.write_tag(out, &tag, options)?;
+                if !VOID_TAGS.contains(&tag.name.as_str()) {
+                    open.push(tag.name);
+                }
+            } else if let Some(idx) = open.iter().rposition(|name| *name == tag.name) {
+                // Tags opened within the one being closed are closed first
+                for name in open.drain(idx..).rev() {
+                    write!(out, "</{}>", name)?;
+                }
+            }
+        }
+        escape_html(out, rest)?;
+        for name in open.iter().rev() {
+            write!(out, "</{}>", name)?;
+        }
+        Ok(())
+    }
+
+    fn write_tag(&self, out: &mut dyn Write, tag: &Tag, options: &HtmlOptions) -> fmt::Result {
+        write!(out, "<{}", tag.name)?;
+        for (name, value) in tag.attributes.iter() {
+            if !self.allows_attribute(&tag.name, name) {
+                continue;
+            }
+            if URL_ATTRIBUTES.contains(&name.as_str()) && !is_allowed_url(options, value) {
+                continue;
+            }
+            write!(out, r#" {}=""#, name)?;
+            escape_attribute(out, value)?;
+            out.write_char('"')?;
+        }
+        out.write_char('>')
+    }
+
+    fn allows_attribute(&self, tag: &str, attribute: &str) -> bool {
+        self.attributes.contains(attribute)
+            || self
+                .tag_attributes
+                .get(tag)
+                .is_some_and(|attributes| attributes.contains(attribute))
+    }
+}
+
+struct Tag {
+    /// Lowercase name of the tag
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, String)>,
+}
+
+/// Parses the opening or closing tag at the start of the given HTML,
+/// along with its length in bytes
+fn parse_tag(html: &str) -> Option<(Tag, usize)> {
+    let bytes = html.as_bytes();
+    let mut idx = 1;
+    let closing = bytes.get(idx) == Some(&b'/');
+    if closing {
+        idx += 1;
+    }
+
+    let length = name_length(&html[idx..], |c| c.is_ascii_alphabetic());
+    if length == 0 {
+        return None;
+    }
+    let name = html[idx..idx + length].to_ascii_lowercase();
+    idx += length;
+
+    let mut attributes = Vec::new();
+    let tag_length = loop {
+        let whitespace = html[idx..].len() - html[idx..].trim_start().len();
+        idx += whitespace;
+        match bytes.get(idx)? {
+            b'>' => break idx + 1,
+            b'/' if bytes.get(idx + 1) == Some(&b'>') => break idx + 2,
+            // Attributes must be separated from what comes before them
+            _ if whitespace == 0 || closing => return None,
+            _ => {}
+        }
+
+        let length = name_length(&html[idx..], |c| {
+            c.is_ascii_alphabetic() || c == '_' || c == ':'
+        });
+        if length == 0 {
+            return None;
+        }
+        let attribute = html[idx..idx + length].to_ascii_lowercase();
+        idx += length;
+
+        let value_start = idx + html[idx..].len() - html[idx..].trim_start().len();
+        if bytes.get(value_start) != Some(&b'=') {
+            attributes.push((attribute, String::new()));
+            continue;
+        }
+        idx = value_start + 1;
+        idx += html[idx..].len() - html[idx..].trim_start().len();
+
+        let value = match bytes.get(idx)? {
+            quote @ (b'"' | b'\'') => {
+                let end = html[idx + 1..].find(*quote as char)?;
+                let value = &html[idx + 1..idx + 1 + end];
+                idx += end + 2;
+                value
+            }
+            _ => {
+                let end = html[idx..]
+                    .find(|c: char| c.is_ascii_whitespace() || "\"'=<>`".contains(c))
+                    .unwrap_or(html.len() - idx);
+                if end == 0 {
+                    return None;
+                }
+                let value = &html[idx..idx + end];
+                idx += end;
+                value
+            }
+        };
+        attributes.push((attribute, value.to_string()));
+    };
+
+    let tag = Tag {
+        name,
+        closing,
+        attributes,
+    };
+    Some((tag, tag_length))
+}
+
+/// Length of the tag or attribute name at the start of the given text
+fn name_length(text: &str, is_first: impl Fn(char) -> bool) -> usize {
+    let mut chars = text.chars();
+    if !chars.next().is_some_and(is_first) {
+        return 0;
+    }
+    1 + chars
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        .count()
+}
+
+/// Skips everything up to, and including, the closing tag with the given name
+fn skip_content<'a>(html: &'a str, name: &str) -> &'a str {
+    let mut searched = 0;
+    while let Some(found) = html[searched..].find("</") {
+        let start = searched + found;
+        let after = &html[start + "</".len()..];
+        // The name must end there, so `</scripts>` doesn't close `<script>`
+        let closes = after
+            .get(..name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name))
+            && after
+                .as_bytes()
+                .get(name.len())
+                .is_none_or(|byte| matches!(byte, b'>' | b'/') || byte.is_ascii_whitespace());
+        if closes {
+            return match html[start..].find('>') {
+                Some(end) => &html[start + end + 1..],
+                None => "",
+            };
+        }
+        searched = start + "</".len();
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_allowed_markup() {
+        let sanitizer = Sanitizer::default();
+        let cases = [
+            ("<b>bold</b> & <i>it</i>", "<b>bold</b> &amp; <i>it</i>"),
+            (
+                r#"<A HREF="https://bpaulino.com" Title='Home'>x</A>"#,
+                r#"<a href="https://bpaulino.com" title="Home">x</a>"#,
+            ),
+            (
+                r#"<img src=logo.png alt="Gohan" width=32 />"#,
+                r#"<img src="logo.png" alt="Gohan" width="32">"#,
+            ),
+            ("a<br/>b", "a<br>b"),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitizer.clean(html), expected, "input: {}", html);
+        }
+    }
+
+    #[test]
+    fn strip_dangerous_markup() {
+        let sanitizer = Sanitizer::default();
+        let cases = [
+            ("<script>alert(1)</script>ok", "ok"),
+            ("<STYLE>body {}</Style>ok", "ok"),
+            ("<script>a</b> b</SCRIPT >ok", "ok"),
+            ("<script>a</scripts>b</script>ok", "ok"),
+            ("<style>a</styled>b</style\n>ok", "ok"),
+            ("<script>never closed", ""),
+            (r#"<img src=x onerror="alert(1)">"#, r#"<img src="x">"#),
+            (r#"<a href="javascript:alert(1)">x</a>"#, "<a>x</a>"),
+            ("<iframe src=//evil.com></iframe>text", "text"),
+            ("<b onmouseover=alert(1)>hi</b>", "<b>hi</b>"),
+            ("1 < 2 and <3", "1 &lt; 2 and &lt;3"),
+            (
+                r#"<b title="never closed>x"#,
+                r#"&lt;b title="never closed&gt;x"#,
+            ),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitizer.clean(html), expected, "input: {}", html);
+        }
+    }
+
+    #[test]
+    fn balance_allowed_tags() {
+        let sanitizer = Sanitizer::default();
+        let cases = [
+            (
+                r#"<a href="/x">never closed"#,
+                r#"<a href="/x">never closed</a>"#,
+            ),
+            ("<b><i>both</b> after", "<b><i>both</i></b> after"),
+            ("stray</a> and </b>", "stray and "),
+            ("<b>a<br>b", "<b>a<br>b</b>"),
+            ("<B>upper</b>", "<b>upper</b>"),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitizer.clean(html), expected, "input: {}", html);
+        }
+    }
+
+    #[test]
+    fn skip_many_content_tags() {
+        // Timed by the `pathological` benchmark, as searching a lowercase copy
+        // of the rest of the input for every tag took seconds
+        let html = format!("{}ok", "<style></style>".repeat(40_000));
+        assert_eq!(Sanitizer::default().clean(&html), "ok");
+    }
+
+    #[test]
+    fn configure_allowlist() {
+        let sanitizer = Sanitizer::empty()
+            .allow_tags(["details", "summary"])
+            .allow_tag_attributes("details", ["open"]);
+        assert_eq!(
+            sanitizer.clean("<details open class=x><summary>More</summary><b>hi</b></details>"),
+            r#"<details open=""><summary>More</summary>hi</details>"#
+        );
+    }
+}
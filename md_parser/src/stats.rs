@@ -0,0 +1,143 @@
+use alloc::string::String;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ast::Node, frontmatter, parser};
+
+/// Average reading speed used to estimate the reading time of a document
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Size of a document, as shown by blog frontends next to its title
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocStats {
+    /// Words of the prose, leaving out code blocks, comments and link destinations
+    pub words: usize,
+    /// Characters of those words, whitespace excluded
+    pub chars: usize,
+    pub headings: usize,
+    pub links: usize,
+    pub code_blocks: usize,
+    /// Estimated time to read the words, rounded up to the next minute
+    pub reading_minutes: usize,
+}
+
+/// Counts the words and elements of the given markdown. Any front matter
+/// at the beginning of the document is skipped.
+///
+/// # Examples
+///
+/// ```
+/// let stats = md_parser::stats("# Gohan\n\nRead [the docs](https://bpaulino.com).\n\n```\nlet code = 1;\n```");
+/// assert_eq!(stats.words, 4);
+/// assert_eq!(stats.links, 1);
+/// assert_eq!(stats.code_blocks, 1);
+/// assert_eq!(stats.reading_minutes, 1);
+/// ```
+pub fn stats(markdown: &str) -> DocStats {
+    let (_, body) = frontmatter::split(markdown);
+    let (ast, _) = parser::parse_with_diagnostics(body);
+    ast_stats(&ast)
+}
+
+/// Counts the words and elements of an AST already parsed
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::parser;
+/// let ast = parser::parse("# Gohan\n\nA markdown *parser*").unwrap();
+/// let stats = md_parser::ast_stats(&ast);
+/// assert_eq!((stats.words, stats.headings), (4, 1));
+/// ```
+pub fn ast_stats(ast: &[Node]) -> DocStats {
+    let mut stats = DocStats::default();
+    let mut text = String::new();
+    count_nodes(&mut stats, &mut text, ast);
+
+    // Punctuation on its own, like a dash between sentences, isn't a word
+    let words = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric));
+    for word in words {
+        stats.words += 1;
+        stats.chars += word.chars().count();
+    }
+    stats.reading_minutes = stats.words.div_ceil(WORDS_PER_MINUTE);
+    stats
+}
+
+/// Counts the elements of the nodes while gathering their prose.
+/// Blocks are separated by a space, so words never run across them.
+fn count_nodes(stats: &mut DocStats, text: &mut String, nodes: &[Node]) {
+    for node in nodes.iter() {
+        match node {
+            Node::Text(value) | Node::Digit(value) => text.push_str(value),
+            Node::LineBreak => text.push(' '),
+            Node::ThematicBreak | Node::Comment(_) | Node::Image(_) => {}
+            Node::CodeBlock(_) => stats.code_blocks += 1,
+            Node::Header(header) => {
+                stats.headings += 1;
+                count_nodes(stats, text, &header.children);
+                text.push(' ');
+            }
+            Node::Paragraph(paragraph) => {
+                count_nodes(stats, text, &paragraph.children);
+                text.push(' ');
+            }
+            Node::Link(link) => {
+                stats.links += 1;
+                count_nodes(stats, text, &link.children);
+            }
+            Node::Bold(bold) => count_nodes(stats, text, &bold.children),
+            Node::Italic(italic) => count_nodes(stats, text, &italic.children),
+            Node::BlockQuote(quote) => count_nodes(stats, text, &quote.children),
+            Node::List(list) => count_nodes(stats, text, &list.children),
+            Node::ListItem(item) => count_nodes(stats, text, &item.children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_words_and_elements() {
+        let markdown = r"---
+title: Gohan
+---
+# Getting **started**
+
+Some *intro* text with [a link](https://bpaulino.com) and ![an image](a.png).
+<!-- not counted -->
+
+- one
+- two
+
+> quoted
+words
+
+```rust
+fn main() {}
+```
+";
+        assert_eq!(
+            stats(markdown),
+            DocStats {
+                words: 13,
+                chars: 56,
+                headings: 1,
+                links: 1,
+                code_blocks: 1,
+                reading_minutes: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn round_reading_time_up() {
+        assert_eq!(stats("").reading_minutes, 0);
+        assert_eq!(stats(&"word ".repeat(200)).reading_minutes, 1);
+        assert_eq!(stats(&"word ".repeat(201)).reading_minutes, 2);
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::ast::{collect_text, List, Node};
+
+/// How the text is decorated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextStyle {
+    /// Plain text, without any markup
+    #[default]
+    Plain,
+    /// Bold, italic and underlined text written with ANSI escape sequences,
+    /// for terminals
+    Ansi,
+}
+
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const DIM: (&str, &str) = ("\x1b[2m", "\x1b[22m");
+const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+const UNDERLINE: (&str, &str) = ("\x1b[4m", "\x1b[24m");
+const CYAN: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+
+/// Renders the AST as text to be read as is, e.g. in a terminal
+///
+/// Blocks are separated by blank lines, list items get a marker,
+/// quotes are prefixed with `> ` and code is indented by four spaces.
+/// Links are followed by their destination.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{parser, text::{self, TextStyle}};
+/// let ast = parser::parse("# Gohan\n\n**Read** [the docs](https://gohan.bpaulino.com)").unwrap();
+/// assert_eq!(
+///     text::render_text(&ast, TextStyle::Plain),
+///     "Gohan\n\nRead the docs (https://gohan.bpaulino.com)\n"
+/// );
+/// assert_eq!(
+///     text::render_text(&ast, TextStyle::Ansi),
+///     "\x1b[1mGohan\x1b[22m\n\n\x1b[1mRead\x1b[22m \x1b[4mthe docs\x1b[24m \x1b[2m(https://gohan.bpaulino.com)\x1b[22m\n"
+/// );
+/// ```
+pub fn render_text(ast: &[Node], style: TextStyle) -> String {
+    let mut text = blocks(style, ast, "\n\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+fn blocks(style: TextStyle, nodes: &[Node], separator: &str) -> String {
+    let blocks: Vec<String> = nodes
+        .iter()
+        .map(|node| block(style, node))
+        .filter(|block| !block.is_empty())
+        .collect();
+    blocks.join(separator)
+}
+
+fn block(style: TextStyle, node: &Node) -> String {
+    match node {
+        Node::Header(header) => styled(style, BOLD, &inline(style, &header.children)),
+        Node::Paragraph(paragraph) => inline(style, &paragraph.children).trim_end().to_string(),
+        Node::CodeBlock(code_block) => {
+            let mut code = String::new();
+            collect_text(&mut code, &code_block.code);
+            let lines: Vec<String> = code
+                .trim_end_matches('\n')
+                .split('\n')
+                .map(|line| match line {
+                    "" => String::new(),
+                    line => format!("    {}", styled(style, CYAN, line)),
+                })
+                .collect();
+            lines.join("\n")
+        }
+        Node::BlockQuote(quote) => {
+            let marker = styled(style, DIM, ">");
+            let marker = format!("{} ", marker);
+            indent(&blocks(style, &quote.children, "\n\n"), &marker, &marker)
+        }
+        Node::List(list) => list_items(style, list),
+        Node::ListItem(item) => blocks(style, &item.children, "\n"),
+        Node::ThematicBreak => styled(style, DIM, "---"),
+        Node::Comment(_) => String::new(),
+        node => inline(style, core::slice::from_ref(node)),
+    }
+}
+
+fn list_items(style: TextStyle, list: &List) -> String {
+    let start = list.start.unwrap_or(1) as usize;
+    let items: Vec<String> = list
+        .children
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let marker = if list.ordered {
+                format!("{}. ", start + idx)
+            } else {
+                "- ".to_string()
+            };
+            let padding = " ".repeat(marker.len());
+            indent(&block(style, item), &marker, &padding)
+        })
+        .collect();
+    items.join("\n")
+}
+
+/// Puts the marker before the first line of the text,
+/// and the padding before the other ones
+fn indent(text: &str, marker: &str, padding: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .enumerate()
+        .map(|(idx, line)| {
+            let prefix = if idx == 0 { marker } else { padding };
+            format!("{}{}", prefix, line).trim_end().to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn inline(style: TextStyle, nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes.iter() {
+        match node {
+            Node::Text(value) | Node::Digit(value) => text.push_str(value),
+            Node::LineBreak => text.push('\n'),
+            Node::Comment(_) => {}
+            Node::Bold(bold) => text.push_str(&styled(style, BOLD, &inline(style, &bold.children))),
+            Node::Italic(italic) => {
+                text.push_str(&styled(style, ITALIC, &inline(style, &italic.children)))
+            }
+            Node::Link(link) => {
+                let label = inline(style, &link.children);
+                let mut url = String::new();
+                collect_text(&mut url, &link.url);
+                if label.is_empty() || label == url {
+                    text.push_str(&styled(style, UNDERLINE, &url));
+                } else {
+                    text.push_str(&styled(style, UNDERLINE, &label));
+                    text.push(' ');
+                    text.push_str(&styled(style, DIM, &format!("({})", url)));
+                }
+            }
+            Node::Image(image) => text.push_str(&inline(style, &image.alt)),
+            node => collect_text(&mut text, core::slice::from_ref(node)),
+        }
+    }
+    text
+}
+
+/// Wraps the text into the escape sequences turning the given decoration on and off
+fn styled(style: TextStyle, (start, end): (&str, &str), text: &str) -> String {
+    match style {
+        TextStyle::Plain => text.to_string(),
+        TextStyle::Ansi => format!("{}{}{}", start, text, end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+
+    use super::*;
+
+    #[test]
+    fn render_plain_text() {
+        let markdown = r"# Gohan
+
+Static *site*
+generator
+
+- First
+- Second
+  with [a link](/docs)
+
+3. Three
+
+```
+let a = 1;
+
+let b = 2;
+```
+
+> Quoted
+>
+> ![logo](logo.png)
+
+---
+";
+        let ast = parser::parse(markdown).unwrap();
+        assert_eq!(
+            render_text(&ast, TextStyle::Plain),
+            r"Gohan
+
+Static site
+generator
+
+- First
+- Second
+  with a link (/docs)
+
+3. Three
+
+    let a = 1;
+
+    let b = 2;
+
+> Quoted
+>
+> logo
+
+---
+"
+        );
+    }
+}
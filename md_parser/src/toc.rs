@@ -0,0 +1,299 @@
+use alloc::{
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{collect_text, Node},
+    frontmatter, parser,
+    token::Span,
+};
+
+/// A single heading entry in a document's table of contents
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: u8,
+    /// Plain text of the heading with any inline markup stripped
+    pub text: String,
+    /// URL-friendly identifier derived from the heading text
+    pub slug: String,
+}
+
+/// Builds a flat table of contents from the headings of the given AST,
+/// including the ones nested within block quotes and list items. Slugs are
+/// handed out by a [`Slugger`], matching the IDs of the rendered headings.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{lexer::Lexer, parser::{Parser, ParserOptions}, toc};
+/// let mut lexer = Lexer::new("## Getting **started**");
+/// let mut parser = Parser::new(lexer.scan(), ParserOptions::default());
+/// let entries = toc::build_toc(&parser.parse());
+/// assert_eq!(entries[0].level, 2);
+/// assert_eq!(entries[0].text, "Getting started");
+/// assert_eq!(entries[0].slug, "getting-started");
+/// ```
+pub fn build_toc(ast: &[Node]) -> Vec<TocEntry> {
+    let mut slugger = Slugger::default();
+    outline(ast)
+        .into_iter()
+        .map(|heading| TocEntry {
+            level: heading.level,
+            slug: slugger.slug(&heading.text),
+            text: heading.text,
+        })
+        .collect()
+}
+
+/// A heading of the document outline, located in the source
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    /// Plain text of the heading with any inline markup stripped
+    pub text: String,
+    /// Positions of the first and last source tokens of the heading
+    pub span: Range<Span>,
+}
+
+/// Lists the headings of the given AST in document order, including the ones
+/// nested within block quotes and list items
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{outline, parser};
+/// let ast = parser::parse("# Gohan\n\n> ## Quoted *heading*\n").unwrap();
+/// let headings = outline(&ast);
+/// assert_eq!(headings[1].level, 2);
+/// assert_eq!(headings[1].text, "Quoted heading");
+/// assert_eq!(headings[1].span.start.line, 3);
+/// ```
+pub fn outline(ast: &[Node]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    collect_headings(&mut headings, ast);
+    headings
+}
+
+fn collect_headings(headings: &mut Vec<Heading>, nodes: &[Node]) {
+    for node in nodes.iter() {
+        match node {
+            Node::Header(header) => {
+                let mut text = String::new();
+                collect_text(&mut text, &header.children);
+                headings.push(Heading {
+                    level: header.level,
+                    text,
+                    span: header.span.clone(),
+                });
+            }
+            Node::BlockQuote(quote) => collect_headings(headings, &quote.children),
+            Node::List(list) => collect_headings(headings, &list.children),
+            Node::ListItem(item) => collect_headings(headings, &item.children),
+            _ => {}
+        }
+    }
+}
+
+/// Title of a document: the text of its first level-1 heading, or else the
+/// `title` field of its front matter when the `yaml` or `toml` feature is enabled
+///
+/// # Examples
+///
+/// ```
+/// let markdown = "Intro\n\n## Section\n\n# Gohan *docs*\n";
+/// assert_eq!(md_parser::title(markdown), Some("Gohan docs".to_string()));
+/// assert_eq!(md_parser::title("No heading"), None);
+/// ```
+pub fn title(markdown: &str) -> Option<String> {
+    let (_, body) = frontmatter::split(markdown);
+    let (ast, _) = parser::parse_with_diagnostics(body);
+    let heading = ast.iter().find_map(|node| match node {
+        Node::Header(header) if header.level == 1 => {
+            let mut text = String::new();
+            collect_text(&mut text, &header.children);
+            Some(text.trim().to_string())
+        }
+        _ => None,
+    });
+    heading
+        .filter(|text| !text.is_empty())
+        .or_else(|| front_matter_title(markdown))
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+fn front_matter_title(markdown: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Meta {
+        title: String,
+    }
+
+    frontmatter::extract::<Meta>(markdown)
+        .0
+        .map(|meta| meta.title)
+}
+
+#[cfg(not(any(feature = "yaml", feature = "toml")))]
+fn front_matter_title(_markdown: &str) -> Option<String> {
+    None
+}
+
+/// Hands out the slugs of the headings of a document, numbering the
+/// repeated ones so every slug is unique, e.g. `intro`, then `intro-1`.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::toc::Slugger;
+/// let mut slugger = Slugger::default();
+/// assert_eq!(slugger.slug("Usage"), "usage");
+/// assert_eq!(slugger.slug("Usage"), "usage-1");
+/// assert_eq!(slugger.slug("Usage 1"), "usage-1-1");
+/// assert_eq!(slugger.slug("?"), "section");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Slugger {
+    taken: BTreeSet<String>,
+}
+
+impl Slugger {
+    /// Slug of the given heading text, not given out before.
+    /// Text without any alphanumeric character is slugged as `section`.
+    pub fn slug(&mut self, text: &str) -> String {
+        let mut base = slugify(text);
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+        let mut slug = base.clone();
+        let mut count = 0;
+        while self.taken.contains(&slug) {
+            count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.taken.insert(slug.clone());
+        slug
+    }
+
+    /// Forgets the slugs given so far, to start over with another document
+    pub fn reset(&mut self) {
+        self.taken.clear();
+    }
+}
+
+/// Lowercases the text, keeps alphanumeric characters
+/// and joins the remaining words with dashes.
+fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lexer::Lexer,
+        parser::{Parser, ParserOptions},
+    };
+
+    use super::*;
+
+    #[test]
+    fn build_toc_from_headings() {
+        let markdown = r"
+# Gohan
+
+Some intro text.
+
+## Getting **started**
+
+### Links to [the docs](https://bpaulino.com)
+";
+        let mut lexer = Lexer::new(markdown);
+        let mut parser = Parser::new(lexer.scan(), ParserOptions::default());
+        let entries = build_toc(&parser.parse());
+        assert_eq!(
+            entries,
+            vec![
+                TocEntry {
+                    level: 1,
+                    text: "Gohan".to_string(),
+                    slug: "gohan".to_string(),
+                },
+                TocEntry {
+                    level: 2,
+                    text: "Getting started".to_string(),
+                    slug: "getting-started".to_string(),
+                },
+                TocEntry {
+                    level: 3,
+                    text: "Links to the docs".to_string(),
+                    slug: "links-to-the-docs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn build_toc_with_unique_slugs() {
+        let markdown = "# Usage\n\n# Usage\n\n> ## ?\n\n- ### Usage\n";
+        let ast = crate::parser::parse(markdown).unwrap();
+        let slugs: Vec<String> = build_toc(&ast)
+            .into_iter()
+            .map(|entry| entry.slug)
+            .collect();
+        assert_eq!(slugs, vec!["usage", "usage-1", "section", "usage-2"]);
+    }
+
+    #[test]
+    fn outline_nested_headings() {
+        let markdown = "# Gohan\n\n- ## In a list\n\n> ### In a quote\n\nText\n## Last\n";
+        let ast = crate::parser::parse(markdown).unwrap();
+        let headings: Vec<(u8, String, usize)> = outline(&ast)
+            .into_iter()
+            .map(|heading| (heading.level, heading.text, heading.span.start.line))
+            .collect();
+        assert_eq!(
+            headings,
+            vec![
+                (1, "Gohan".to_string(), 1),
+                (2, "In a list".to_string(), 3),
+                (3, "In a quote".to_string(), 5),
+                (2, "Last".to_string(), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn title_from_first_top_level_heading() {
+        assert_eq!(title("## Sub\n# Main\n# Other"), Some("Main".to_string()));
+        // Headings nested in other blocks don't title the document
+        assert_eq!(title("> # Quoted\n\nText"), None);
+        assert_eq!(title("#\n\nText"), None);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn title_from_front_matter() {
+        let markdown = "---\ntitle: Gohan\n---\nNo heading here";
+        assert_eq!(title(markdown), Some("Gohan".to_string()));
+        let markdown = "---\ntitle: Gohan\n---\n# Heading first";
+        assert_eq!(title(markdown), Some("Heading first".to_string()));
+    }
+
+    #[test]
+    fn slugify_strips_punctuation() {
+        assert_eq!(slugify("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(slugify("  Hello -- World  "), "hello-world");
+    }
+}
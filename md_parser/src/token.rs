@@ -1,47 +1,63 @@
-use std::fmt::{self, Debug, Display};
+use core::{
+    fmt::{self, Debug, Display},
+    ops::Range,
+};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Token<'a> {
     Hash,
+    Backtick,
     Star,
     Bang,
     Underscore,
     Newline,
-    Tab,
     Space,
     Dot,
     Dash,
     Backslash,
+    DoubleQuote,
+    LessThan,
+    GreaterThan,
     LeftParen,
     RightParen,
     LeftSquareBracket,
     RightSquareBracket,
-    Digit(&'a str),
+    Number(&'a str),
     Text(&'a str),
+    /// Whole HTML comment, including its `<!--` and `-->` delimiters
+    Comment(&'a str),
+    /// Run of Unicode whitespace other than ASCII spaces, tabs and line breaks,
+    /// like the non-breaking spaces pasted from word processors
+    Whitespace(&'a str),
     EndOfFile,
 }
 
 impl<'a> Display for Token<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Hash => f.write_str("`#`"),
+            Self::Backtick => f.write_str("```"),
             Self::Star => f.write_str("`*`"),
             Self::Bang => f.write_str("`!`"),
             Self::Dot => f.write_str("`.`"),
             Self::Dash => f.write_str("`-`"),
             Self::Backslash => f.write_str("`\\`"),
+            Self::DoubleQuote => f.write_str("`\"`"),
+            Self::LessThan => f.write_str("`<`"),
+            Self::GreaterThan => f.write_str("`>`"),
             Self::LeftParen => f.write_str("`(`"),
             Self::RightParen => f.write_str("`)`"),
             Self::LeftSquareBracket => f.write_str("`[`"),
             Self::RightSquareBracket => f.write_str("`]`"),
-            Self::Tab => f.write_str("`\\t`"),
             Self::Space => f.write_str("` `"),
             Self::Newline => f.write_str("`\\n`"),
             Self::Underscore => f.write_str("`_`"),
-            Self::Digit(number) => f.write_str(&format!("digit:'{}'", &number.to_string())),
-            Self::Text(text) => f.write_str(&format!("text:'{}'", text)),
+            Self::Number(number) => write!(f, "number:'{}'", number),
+            Self::Text(text) => write!(f, "text:'{}'", text),
+            Self::Comment(comment) => write!(f, "comment:'{}'", comment),
+            Self::Whitespace(whitespace) => write!(f, "whitespace:{:?}", whitespace),
             Self::EndOfFile => f.write_str("`EOF`"),
         }
     }
@@ -52,21 +68,26 @@ impl<'a> Token<'a> {
     pub fn literal(&self) -> &'a str {
         match self {
             Self::Hash => "#",
+            Self::Backtick => "`",
             Self::Star => "*",
             Self::Bang => "!",
             Self::Dot => ".",
             Self::Dash => "-",
             Self::Underscore => "_",
             Self::Backslash => "\\",
+            Self::DoubleQuote => "\"",
+            Self::LessThan => "<",
+            Self::GreaterThan => ">",
             Self::LeftParen => "(",
             Self::RightParen => ")",
             Self::LeftSquareBracket => "[",
             Self::RightSquareBracket => "]",
-            Self::Tab => "\t",
             Self::Newline => "\n",
             Self::Space => " ",
-            Self::Digit(d) => d,
+            Self::Number(n) => n,
             Self::Text(t) => t,
+            Self::Comment(c) => c,
+            Self::Whitespace(w) => w,
             Self::EndOfFile => "",
         }
     }
@@ -80,10 +101,67 @@ impl<'a> Token<'a> {
 pub struct Span {
     pub line: usize,
     pub col: usize,
+    /// Byte offset in the source where the token starts
+    #[serde(default)]
+    pub start: usize,
+    /// Byte offset in the source right after the token
+    #[serde(default)]
+    pub end: usize,
+    /// Whether the token is one of the spaces a tab was expanded into
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub tab: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Span {
+    /// Byte range of the token in the source
+    pub fn bytes(&self) -> Range<usize> {
+        self.start..self.end
+    }
 }
 
 impl fmt::Debug for Span {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, " @ {}:{}", self.line, self.col)
+        write!(
+            f,
+            " @ {}:{} ({}..{})",
+            self.line, self.col, self.start, self.end
+        )
     }
 }
+
+/// Byte range in the source of an element spanning the given tokens,
+/// from the start of its first token to the end of its last one
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::Node, parser, token};
+/// let markdown = "Some **bold** text";
+/// let ast = parser::parse(markdown).unwrap();
+/// let Node::Paragraph(paragraph) = &ast[0] else { unreachable!() };
+/// let Node::Bold(bold) = &paragraph.children[1] else { unreachable!() };
+/// assert_eq!(&markdown[token::byte_range(&bold.span)], "**bold**");
+/// ```
+pub fn byte_range(span: &Range<Span>) -> Range<usize> {
+    span.start.start..span.end.end
+}
+
+/// Position of a byte range of the source, for elements found without the lexer.
+/// Like token positions, it has the line the range starts on,
+/// and the column right after the range.
+pub(crate) fn source_span(source: &str, bytes: Range<usize>) -> Range<Span> {
+    let before = &source[..bytes.start];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let position = Span {
+        line: before.matches('\n').count() + 1,
+        col: bytes.end - line_start,
+        start: bytes.start,
+        end: bytes.end,
+        tab: false,
+    };
+    position..position
+}
@@ -0,0 +1,160 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::ast::Node;
+
+mod variables;
+
+pub use variables::Variables;
+
+/// A pass rewriting the AST in between parsing and rendering,
+/// e.g. to demote headings or to rewrite relative links.
+///
+/// Closures taking the AST are transforms as well.
+pub trait Transform {
+    fn apply(&self, ast: &mut Vec<Node<'_>>);
+}
+
+impl<F> Transform for F
+where
+    F: Fn(&mut Vec<Node<'_>>),
+{
+    fn apply(&self, ast: &mut Vec<Node<'_>>) {
+        self(ast)
+    }
+}
+
+/// Ordered list of transforms applied one after the other over the same AST
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{ast::Node, parser, renderer, transform::Pipeline};
+///
+/// let pipeline = Pipeline::new().with(|ast: &mut Vec<Node>| {
+///     ast.retain(|node| !matches!(node, Node::ThematicBreak))
+/// });
+///
+/// let mut ast = parser::parse("# Gohan\n\n---\n").unwrap();
+/// pipeline.run(&mut ast);
+/// let html = renderer::render_ast(&ast, &Default::default());
+/// assert_eq!(html, "<h1>Gohan</h1>");
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    passes: Vec<Box<dyn Transform>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a transform, which runs after the ones already registered
+    pub fn with(mut self, pass: impl Transform + 'static) -> Self {
+        self.add(pass);
+        self
+    }
+
+    /// Appends a transform, which runs after the ones already registered
+    pub fn add(&mut self, pass: impl Transform + 'static) {
+        self.passes.push(Box::new(pass));
+    }
+
+    pub fn run(&self, ast: &mut Vec<Node<'_>>) {
+        for pass in self.passes.iter() {
+            pass.apply(ast);
+        }
+    }
+}
+
+/// Calls `f` on each of the given nodes and their descendants,
+/// visiting a parent before its children.
+pub fn for_each_node_mut<'s>(nodes: &mut [Node<'s>], f: &mut impl FnMut(&mut Node<'s>)) {
+    for node in nodes.iter_mut() {
+        f(node);
+        if let Some(children) = node.children_mut() {
+            for_each_node_mut(children, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{ast::collect_text, parser, renderer};
+
+    use super::*;
+
+    /// Lowers every heading by the given number of levels, down to `h6`
+    struct DemoteHeadings(u8);
+
+    impl Transform for DemoteHeadings {
+        fn apply(&self, ast: &mut Vec<Node<'_>>) {
+            for_each_node_mut(ast, &mut |node| {
+                if let Node::Header(header) = node {
+                    header.level = (header.level + self.0).min(6);
+                }
+            });
+        }
+    }
+
+    fn strip_images(ast: &mut Vec<Node<'_>>) {
+        fn strip(nodes: &mut Vec<Node<'_>>) {
+            nodes.retain(|node| !matches!(node, Node::Image(_)));
+            for node in nodes.iter_mut() {
+                if let Some(children) = node.children_mut() {
+                    strip(children);
+                }
+            }
+        }
+        strip(ast);
+    }
+
+    fn rewrite_relative_links(ast: &mut Vec<Node<'_>>) {
+        for_each_node_mut(ast, &mut |node| {
+            if let Node::Link(link) = node {
+                let mut url = String::new();
+                collect_text(&mut url, &link.url);
+                if url.starts_with('/') {
+                    link.url = vec![Node::Text(Cow::Owned(format!(
+                        "https://bpaulino.com{}",
+                        url
+                    )))];
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn run_passes_in_order() {
+        let markdown = r"# Gohan
+
+Read the [docs](/docs) or the [source](https://github.com).
+
+> ![logo](./logo.png) **Bold ![icon](./icon.png)**
+";
+        let mut ast = parser::parse(markdown).unwrap();
+        let pipeline = Pipeline::new()
+            .with(DemoteHeadings(1))
+            .with(strip_images)
+            .with(rewrite_relative_links);
+        pipeline.run(&mut ast);
+
+        assert_eq!(
+            renderer::render_ast(&ast, &Default::default()),
+            r#"<h2>Gohan</h2><p>Read the <a href="https://bpaulino.com/docs">docs</a> or the <a href="https://github.com">source</a>.</p><blockquote><p> <strong>Bold </strong></p></blockquote>"#
+        );
+    }
+
+    #[test]
+    fn later_passes_see_earlier_changes() {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(DemoteHeadings(4));
+        pipeline.add(DemoteHeadings(4));
+
+        let mut ast = parser::parse("# Title").unwrap();
+        pipeline.run(&mut ast);
+        assert!(matches!(&ast[0], Node::Header(header) if header.level == 6));
+    }
+}
@@ -0,0 +1,223 @@
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, vec::Vec};
+
+use crate::ast::{collect_text, Node};
+
+use super::Transform;
+
+/// Replaces `{{name}}` placeholders in text with the value of the variable,
+/// e.g. the version of the project in versioned docs.
+/// Code blocks are left as is, and so are placeholders of unknown variables.
+///
+/// # Examples
+///
+/// ```
+/// use md_parser::{parser, renderer, transform::{Transform, Variables}};
+///
+/// let variables = Variables::new().with("version", "1.2.0");
+/// let mut ast = parser::parse("Install {{ version }}\n\n```\n{{version}}\n```").unwrap();
+/// variables.apply(&mut ast);
+/// let html = renderer::render_ast(&ast, &Default::default());
+/// assert_eq!(html, "<p>Install 1.2.0</p><pre><code>{{version}}\n</code></pre>");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Variables {
+    values: BTreeMap<String, String>,
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Variables for the top-level fields of the front matter holding
+    /// a string, a number or a boolean
+    #[cfg(any(feature = "yaml", feature = "toml"))]
+    pub fn from_front_matter(markdown: &str) -> Self {
+        use alloc::string::ToString;
+
+        use serde::{de::IgnoredAny, Deserialize};
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Text(String),
+            Bool(bool),
+            Integer(i64),
+            Float(f64),
+            Other(IgnoredAny),
+        }
+
+        let (fields, _) = crate::frontmatter::extract::<BTreeMap<String, Value>>(markdown);
+        fields
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = match value {
+                    Value::Text(text) => text,
+                    Value::Bool(value) => value.to_string(),
+                    Value::Integer(value) => value.to_string(),
+                    Value::Float(value) => value.to_string(),
+                    Value::Other(_) => return None,
+                };
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    /// Text with the placeholders of known variables replaced.
+    /// Spaces around the name are allowed, as in `{{ version }}`.
+    pub fn substitute<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut output = String::new();
+        let mut rest = text;
+        let mut replaced = false;
+        while let Some(start) = rest.find("{{") {
+            let Some(length) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let end = start + 2 + length + 2;
+            match self.values.get(rest[start + 2..end - 2].trim()) {
+                Some(value) => {
+                    output.push_str(&rest[..start]);
+                    output.push_str(value);
+                    replaced = true;
+                }
+                None => output.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        if !replaced {
+            return Cow::Borrowed(text);
+        }
+        output.push_str(rest);
+        Cow::Owned(output)
+    }
+
+    fn substitute_nodes(&self, nodes: &mut Vec<Node<'_>>) {
+        merge_text(nodes);
+        for node in nodes.iter_mut() {
+            match node {
+                Node::Text(text) => {
+                    if let Cow::Owned(value) = self.substitute(text) {
+                        *text = Cow::Owned(value);
+                    }
+                }
+                Node::CodeBlock(_) | Node::Comment(_) => {}
+                Node::Link(link) => {
+                    self.substitute_nodes(&mut link.children);
+                    self.substitute_nodes(&mut link.url);
+                    if let Some(title) = &mut link.title {
+                        self.substitute_nodes(title);
+                    }
+                }
+                Node::Image(image) => {
+                    self.substitute_nodes(&mut image.alt);
+                    self.substitute_nodes(&mut image.url);
+                    if let Some(title) = &mut image.title {
+                        self.substitute_nodes(title);
+                    }
+                }
+                node => {
+                    if let Some(children) = node.children_mut() {
+                        self.substitute_nodes(children);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Variables {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let values = iter
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        Self { values }
+    }
+}
+
+impl Transform for Variables {
+    fn apply(&self, ast: &mut Vec<Node<'_>>) {
+        self.substitute_nodes(ast);
+    }
+}
+
+/// Joins the runs of text nodes holding a placeholder, which the lexer
+/// splits wherever a name has digits, underscores or spaces
+fn merge_text(nodes: &mut Vec<Node<'_>>) {
+    let mut idx = 0;
+    while idx < nodes.len() {
+        let run = nodes[idx..]
+            .iter()
+            .take_while(|node| matches!(node, Node::Text(_) | Node::Digit(_)))
+            .count();
+        if run > 1 {
+            let mut text = String::new();
+            collect_text(&mut text, &nodes[idx..idx + run]);
+            if text.contains("{{") {
+                nodes.splice(idx..idx + run, [Node::Text(Cow::Owned(text))]);
+                idx += 1;
+                continue;
+            }
+        }
+        idx += run.max(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser, renderer};
+
+    use super::*;
+
+    #[test]
+    fn substitute_placeholders() {
+        let variables: Variables = [("version", "1.2.0"), ("project_name", "gohan")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            variables.substitute("{{project_name}} {{ version }} {{missing}} {{"),
+            "gohan 1.2.0 {{missing}} {{"
+        );
+        assert!(matches!(
+            variables.substitute("{{missing}}"),
+            Cow::Borrowed(_)
+        ));
+
+        let markdown = "# {{project_name}} v{{version}}\n\n**{{ version }}** and {{missing}}\n\n```\n{{version}}\n```\n\n[Download](/v/{{version}}/gohan.tar.gz)";
+        let mut ast = parser::parse(markdown).unwrap();
+        variables.apply(&mut ast);
+        assert_eq!(
+            renderer::render_ast(&ast, &Default::default()),
+            concat!(
+                "<h1>gohan v1.2.0</h1><p><strong>1.2.0</strong> and {{missing}}</p>",
+                "<pre><code>{{version}}\n</code></pre>",
+                r#"<p><a href="/v/1.2.0/gohan.tar.gz">Download</a></p>"#
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn read_variables_from_front_matter() {
+        let markdown =
+            "---\nversion: 1.2\nstable: true\ntitle: Gohan\ntags: [a, b]\n---\n# {{title}}";
+        let variables = Variables::from_front_matter(markdown);
+        assert_eq!(
+            variables,
+            Variables::new()
+                .with("version", "1.2")
+                .with("stable", "true")
+                .with("title", "Gohan")
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! Helpers shared by the integration tests comparing rendered HTML
+
+/// Makes HTML comparable without caring about insignificant differences,
+/// like whitespace between tags or how void elements are closed
+pub fn normalize_html(html: &str) -> String {
+    let mut normalized = html.replace(" />", ">").replace("/>", ">");
+    while let Some(idx) = normalized.find(">\n") {
+        normalized.replace_range(idx..idx + 2, ">");
+    }
+    normalized.trim().to_string()
+}
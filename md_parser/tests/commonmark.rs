@@ -0,0 +1,165 @@
+//! Runs the examples of the CommonMark spec against the HTML renderer.
+//!
+//! The spec isn't checked in. Fetch it with `make commonmark-spec`,
+//! then print the conformance report with `make commonmark`.
+
+use std::{collections::BTreeMap, fs, panic, path::Path};
+
+use common::normalize_html;
+use md_parser::renderer;
+
+mod common;
+
+/// Fence opening and closing every example of the spec
+const EXAMPLE_FENCE: &str = "````````````````````````````````";
+
+#[derive(Debug, PartialEq, Eq)]
+struct Example {
+    /// Number of the example, counted from 1 across the whole spec
+    number: usize,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+/// Extracts the examples from the given `spec.txt`,
+/// where `→` stands for the tabs of the markdown and HTML.
+fn parse_examples(spec: &str) -> Vec<Example> {
+    let mut examples = Vec::new();
+    let mut section = String::new();
+    let mut lines = spec.lines();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with('#') && !line.starts_with("#####") {
+            section = line.trim_start_matches('#').trim().to_string();
+            continue;
+        }
+        if !line.starts_with(EXAMPLE_FENCE) || !line.ends_with(" example") {
+            continue;
+        }
+
+        let mut markdown = String::new();
+        for line in lines.by_ref().take_while(|line| *line != ".") {
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        let mut html = String::new();
+        for line in lines.by_ref().take_while(|line| *line != EXAMPLE_FENCE) {
+            html.push_str(line);
+            html.push('\n');
+        }
+
+        examples.push(Example {
+            number: examples.len() + 1,
+            section: section.clone(),
+            markdown: markdown.replace('→', "\t"),
+            html: html.replace('→', "\t"),
+        });
+    }
+    examples
+}
+
+/// Whether the renderer turns the example into the expected HTML,
+/// counting errors and panics as failures
+fn passes(example: &Example) -> bool {
+    let rendered = panic::catch_unwind(|| renderer::render_html(&example.markdown));
+    match rendered {
+        Ok(Ok(html)) => normalize_html(&html) == normalize_html(&example.html),
+        _ => false,
+    }
+}
+
+#[test]
+fn parse_spec_examples() {
+    let spec = format!(
+        "# Introduction\n\n## Tabs\n\n{fence} example\n→foo\n.\n<pre><code>foo\n</code></pre>\n{fence}\n\n\
+         Text between examples.\n\n## Thematic breaks\n\n{fence} example\n***\n.\n<hr />\n{fence}\n",
+        fence = EXAMPLE_FENCE
+    );
+    assert_eq!(
+        parse_examples(&spec),
+        [
+            Example {
+                number: 1,
+                section: "Tabs".to_string(),
+                markdown: "\tfoo\n".to_string(),
+                html: "<pre><code>foo\n</code></pre>\n".to_string(),
+            },
+            Example {
+                number: 2,
+                section: "Thematic breaks".to_string(),
+                markdown: "***\n".to_string(),
+                html: "<hr />\n".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn normalize_insignificant_html_differences() {
+    assert_eq!(
+        normalize_html("<ul>\n<li>a<br /></li>\n</ul>\n"),
+        normalize_html("<ul><li>a<br></li></ul>")
+    );
+    assert_ne!(normalize_html("<p>a b</p>"), normalize_html("<p>a  b</p>"));
+}
+
+/// Prints how many examples of each section of the spec pass.
+/// Ignored by default, since the spec has to be fetched first
+/// and most examples don't pass yet.
+#[test]
+#[ignore]
+fn conformance_report() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/spec.txt");
+    let spec = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "couldn't read {}, fetch it with `make commonmark-spec`: {}",
+            path.display(),
+            err
+        )
+    });
+    let examples = parse_examples(&spec);
+    assert!(!examples.is_empty(), "no examples found in the spec");
+
+    // Keep panics of failing examples out of the report
+    panic::set_hook(Box::new(|_| {}));
+    // Sections in the order they show up in the spec, with their pass and total counts
+    let mut sections: Vec<(&str, usize, usize)> = Vec::new();
+    let mut failures: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for example in examples.iter() {
+        let passed = passes(example);
+        match sections.last_mut() {
+            Some((section, _, _)) if *section == example.section => {}
+            _ => sections.push((&example.section, 0, 0)),
+        }
+        let (_, pass_count, total) = sections.last_mut().unwrap();
+        *total += 1;
+        if passed {
+            *pass_count += 1;
+        } else {
+            failures
+                .entry(&example.section)
+                .or_default()
+                .push(example.number);
+        }
+    }
+    let _ = panic::take_hook();
+
+    println!("{:<40} {:>6} {:>6}", "Section", "Pass", "Total");
+    for (section, pass_count, total) in sections.iter() {
+        println!("{:<40} {:>6} {:>6}", section, pass_count, total);
+    }
+    let passed: usize = sections.iter().map(|(_, pass_count, _)| pass_count).sum();
+    println!(
+        "\n{} of {} examples pass ({:.1}%)",
+        passed,
+        examples.len(),
+        passed as f64 * 100.0 / examples.len() as f64
+    );
+
+    if std::env::var_os("COMMONMARK_FAILURES").is_some() {
+        for (section, numbers) in failures.iter() {
+            println!("{}: {:?}", section, numbers);
+        }
+    }
+}
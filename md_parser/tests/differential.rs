@@ -0,0 +1,175 @@
+//! Property-based tests over generated markdown.
+//!
+//! Any input, however malformed, must be lexed, parsed and rendered without panicking.
+//! Documents made of the constructs gohan already supports must render like
+//! pulldown-cmark, used as the reference implementation of CommonMark.
+//!
+//! Run more cases with `make differential`, or `make differential PROPTEST_CASES=100000`.
+//! The constructs compared can be narrowed down with a comma separated list
+//! in `GOHAN_CONSTRUCTS`, e.g. `GOHAN_CONSTRUCTS=heading,emphasis`.
+
+use std::env;
+
+use common::normalize_html;
+use md_parser::{lexer::Lexer, parser, renderer};
+use proptest::{collection::vec, prelude::*, strategy::Union};
+
+mod common;
+
+/// Characters which are markdown syntax, or make lexing tricky, mixed in with a few words
+const MARKDOWNISH: &str = r#"([#*_`\[\]()!<>"\\ \t\r\n.:/0-9\-]|[a-z]{1,4}|\x{e9}|\x{65e5}|\x{301}|\x{1f389}|\x{200d}|\x{a0}|\x{3000}|\x{feff}){0,80}"#;
+
+/// Construct generated in the documents compared against pulldown-cmark.
+/// Paragraphs of plain words are always generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Construct {
+    Heading,
+    Emphasis,
+    Strong,
+    List,
+    BlockQuote,
+    ThematicBreak,
+    /// Backslash escaped punctuation
+    Escape,
+}
+
+impl Construct {
+    const ALL: [Construct; 7] = [
+        Construct::Heading,
+        Construct::Emphasis,
+        Construct::Strong,
+        Construct::List,
+        Construct::BlockQuote,
+        Construct::ThematicBreak,
+        Construct::Escape,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Construct::Heading => "heading",
+            Construct::Emphasis => "emphasis",
+            Construct::Strong => "strong",
+            Construct::List => "list",
+            Construct::BlockQuote => "blockquote",
+            Construct::ThematicBreak => "thematic-break",
+            Construct::Escape => "escape",
+        }
+    }
+}
+
+/// Constructs given in `GOHAN_CONSTRUCTS`, or all of them
+fn constructs() -> Vec<Construct> {
+    let Ok(ids) = env::var("GOHAN_CONSTRUCTS") else {
+        return Construct::ALL.to_vec();
+    };
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            Construct::ALL
+                .into_iter()
+                .find(|construct| construct.id() == id)
+                .unwrap_or_else(|| panic!("unknown construct `{}` in GOHAN_CONSTRUCTS", id))
+        })
+        .collect()
+}
+
+fn word() -> BoxedStrategy<String> {
+    "[a-z]{1,8}".boxed()
+}
+
+fn inline(constructs: &[Construct]) -> BoxedStrategy<String> {
+    let mut choices = vec![word()];
+    if constructs.contains(&Construct::Emphasis) {
+        choices.push(word().prop_map(|word| format!("*{}*", word)).boxed());
+    }
+    if constructs.contains(&Construct::Strong) {
+        choices.push(word().prop_map(|word| format!("**{}**", word)).boxed());
+    }
+    if constructs.contains(&Construct::Escape) {
+        let punctuation = prop::sample::select(vec!['*', '_', '[', ']', '\\', '#', '!', '<']);
+        let escaped = (punctuation, word()).prop_map(|(c, word)| format!("\\{}{}", c, word));
+        choices.push(escaped.boxed());
+    }
+    Union::new(choices).boxed()
+}
+
+/// Single line of inline elements separated by spaces
+fn line(constructs: &[Construct]) -> BoxedStrategy<String> {
+    vec(inline(constructs), 1..6)
+        .prop_map(|parts| parts.join(" "))
+        .boxed()
+}
+
+fn block(constructs: &[Construct]) -> BoxedStrategy<String> {
+    let mut choices = vec![line(constructs)];
+    if constructs.contains(&Construct::Heading) {
+        let heading = (1..=6usize, line(constructs))
+            .prop_map(|(level, line)| format!("{} {}", "#".repeat(level), line));
+        choices.push(heading.boxed());
+    }
+    if constructs.contains(&Construct::List) {
+        let list = vec(line(constructs), 1..4).prop_map(|items| {
+            let items: Vec<String> = items.iter().map(|item| format!("- {}", item)).collect();
+            items.join("\n")
+        });
+        choices.push(list.boxed());
+    }
+    if constructs.contains(&Construct::BlockQuote) {
+        let quote = line(constructs).prop_map(|line| format!("> {}", line));
+        choices.push(quote.boxed());
+    }
+    if constructs.contains(&Construct::ThematicBreak) {
+        choices.push(Just("---".to_string()).boxed());
+    }
+    Union::new(choices).boxed()
+}
+
+/// Blocks separated by blank lines
+fn document(constructs: &[Construct]) -> BoxedStrategy<String> {
+    vec(block(constructs), 1..8)
+        // Lists only separated by a blank line make up a single loose list,
+        // which is left out of the comparison
+        .prop_filter("adjacent lists", |blocks| {
+            !blocks
+                .windows(2)
+                .any(|pair| pair[0].starts_with("- ") && pair[1].starts_with("- "))
+        })
+        .prop_map(|blocks| blocks.join("\n\n"))
+        .boxed()
+}
+
+fn reference_html(markdown: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(markdown));
+    html
+}
+
+proptest! {
+    #[test]
+    fn never_panic(markdown in MARKDOWNISH) {
+        let tokens = Lexer::new(&markdown).into_tokens();
+        for (token, span) in tokens.iter() {
+            prop_assert!(
+                markdown.get(span.bytes()).is_some(),
+                "{} at{:?} splits a character",
+                token,
+                span
+            );
+        }
+        let _ = parser::parse_with_diagnostics(&markdown);
+        let _ = renderer::render_html(&markdown);
+    }
+
+    #[test]
+    fn render_like_pulldown_cmark(markdown in document(&constructs())) {
+        let html = renderer::render_html(&markdown);
+        prop_assert!(html.is_ok(), "{:?} failed to render: {:?}", markdown, html);
+        prop_assert_eq!(
+            normalize_html(&html.unwrap()),
+            normalize_html(&reference_html(&markdown)),
+            "markdown: {:?}",
+            markdown
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::rc::Rc;
+
+use serde_json::Value;
+use yew::prelude::*;
+
+use md_parser::ast::OwnedAst;
+
+#[derive(Properties, PartialEq)]
+pub struct AstViewProps {
+    pub ast: Rc<OwnedAst>,
+}
+
+/// Collapsible tree of the AST, as serialized by serde
+#[function_component(AstView)]
+pub fn ast_view(props: &AstViewProps) -> Html {
+    let Ok(Value::Array(nodes)) = serde_json::to_value(&*props.ast) else {
+        return Html::default();
+    };
+
+    html! {
+        <ul class="font-mono text-xs text-gray-900 dark:text-gray-100">
+            { for nodes.iter().map(|node| html! { <li>{ tree("", node) }</li> }) }
+        </ul>
+    }
+}
+
+/// Tree of a serialized value, under the given label
+fn tree(label: &str, value: &Value) -> Html {
+    let (summary, children): (String, Vec<Html>) = match value {
+        // Nodes serialize as their kind holding their fields, e.g. `{"Header": {..}}`
+        Value::Object(fields) if fields.len() == 1 => {
+            let (kind, inner) = fields.iter().next().expect("the object has one field");
+            let label = if label.is_empty() {
+                kind.clone()
+            } else {
+                format!("{}: {}", label, kind)
+            };
+            return tree(&label, inner);
+        }
+        Value::Object(fields) => (
+            label.to_string(),
+            fields
+                .iter()
+                .map(|(name, field)| tree(name, field))
+                .collect(),
+        ),
+        Value::Array(items) => (
+            format!("{} [{}]", label, items.len()),
+            items.iter().map(|item| tree("", item)).collect(),
+        ),
+        value if label.is_empty() => return html! { <span>{ value.to_string() }</span> },
+        value => {
+            return html! {
+                <span>
+                    <span class="text-sky-700 dark:text-sky-400">{ label }</span>{ ": " }{ value.to_string() }
+                </span>
+            }
+        }
+    };
+
+    // Spans are mostly noise while reading the tree, so they start collapsed
+    let open = !label.starts_with("span");
+    html! {
+        <details {open}>
+            <summary class="cursor-pointer text-emerald-700 dark:text-emerald-400">{ summary }</summary>
+            <ul class="pl-4 border-l border-gray-300 dark:border-gray-600">
+                { for children.into_iter().map(|child| html! { <li>{ child }</li> }) }
+            </ul>
+        </details>
+    }
+}
@@ -0,0 +1,6 @@
+use gloo_worker::Registrable;
+use web_repl::worker::ParserWorker;
+
+fn main() {
+    ParserWorker::registrar().register();
+}
@@ -0,0 +1,23 @@
+use gloo_timers::callback::Timeout;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use yew::UseStateSetter;
+
+/// How long a button says its content was copied
+const FEEDBACK_MS: u32 = 1500;
+
+/// Copies the text to the clipboard, then marks it as copied for a little while,
+/// so that the button that copied it can say so
+pub fn copy<T: 'static>(text: &str, copied: UseStateSetter<Option<T>>, what: T) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let written = JsFuture::from(window.navigator().clipboard().write_text(text));
+    spawn_local(async move {
+        if let Err(error) = written.await {
+            web_sys::console::error_1(&error);
+            return;
+        }
+        copied.set(Some(what));
+        Timeout::new(FEEDBACK_MS, move || copied.set(None)).forget();
+    });
+}
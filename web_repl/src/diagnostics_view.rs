@@ -0,0 +1,49 @@
+use std::{ops::Range, rc::Rc};
+
+use yew::prelude::*;
+
+use md_parser::{
+    diagnostic::{Diagnostic, Severity},
+    token,
+};
+
+#[derive(Properties, PartialEq)]
+pub struct DiagnosticsViewProps {
+    pub diagnostics: Rc<Vec<Diagnostic>>,
+    /// Called with the byte range of the diagnostic under the pointer, or clicked
+    pub on_select: Callback<Range<usize>>,
+}
+
+/// Problems the parser found, with where they are in the source
+#[function_component(DiagnosticsView)]
+pub fn diagnostics_view(props: &DiagnosticsViewProps) -> Html {
+    if props.diagnostics.is_empty() {
+        return Html::default();
+    }
+
+    html! {
+        <ul class="mt-2 max-h-40 overflow-y-auto text-xs rounded-lg border border-gray-300 dark:border-gray-600">
+            { for props.diagnostics.iter().map(|diagnostic| {
+                let bytes = token::byte_range(&diagnostic.span);
+                let on_select = props.on_select.reform(move |_: MouseEvent| bytes.clone());
+                // Token columns point right after the token
+                let start = &diagnostic.span.start;
+                let col = start.col.saturating_sub(start.end - start.start) + 1;
+                let (label, color) = match diagnostic.severity {
+                    Severity::Error => ("error", "text-red-600 dark:text-red-400"),
+                    Severity::Warning => ("warning", "text-amber-600 dark:text-amber-400"),
+                };
+                html! {
+                    <li
+                        onmouseenter={on_select.clone()}
+                        onclick={on_select}
+                        class="px-2 py-1 cursor-pointer text-gray-900 dark:text-gray-100 hover:bg-gray-100 dark:hover:bg-gray-700">
+                        <span class={classes!("font-bold", color)}>{ label }</span>
+                        <span class="mx-2 font-mono text-gray-500 dark:text-gray-400">{ format!("{}:{}", start.line, col) }</span>
+                        { &diagnostic.message }
+                    </li>
+                }
+            }) }
+        </ul>
+    }
+}
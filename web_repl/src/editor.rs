@@ -0,0 +1,182 @@
+use std::ops::Range;
+
+use web_sys::HtmlTextAreaElement;
+
+use crate::scroll_sync;
+
+/// Markdown syntax the toolbar and the keyboard shortcuts wrap the selection with
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Bold,
+    Italic,
+    Link,
+    Heading,
+}
+
+impl Format {
+    /// Formats the selected bytes of the text, giving the formatted text
+    /// and the bytes to select in it. Without any selection, a placeholder
+    /// is inserted and selected, ready to be typed over.
+    pub fn apply(self, text: &str, selection: Range<usize>) -> (String, Range<usize>) {
+        let (before, placeholder, after) = match self {
+            Format::Bold => ("**", "bold text", "**"),
+            Format::Italic => ("*", "italic text", "*"),
+            Format::Link => ("[", "link text", "](url)"),
+            Format::Heading => return heading(text, selection),
+        };
+        let selected = &text[selection.clone()];
+        let inner = if selected.is_empty() {
+            placeholder
+        } else {
+            selected
+        };
+        let formatted = format!(
+            "{}{}{}{}{}",
+            &text[..selection.start],
+            before,
+            inner,
+            after,
+            &text[selection.end..]
+        );
+        let start = selection.start + before.len();
+        let selection = if self == Format::Link && !selected.is_empty() {
+            // The text of the link is there already, what's missing is where it goes
+            let url = start + inner.len() + "](".len();
+            url..url + "url".len()
+        } else {
+            start..start + inner.len()
+        };
+        (formatted, selection)
+    }
+}
+
+/// Turns the line of the selection into a heading, or into a heading one level
+/// deeper, or back into a paragraph once at level 6
+fn heading(text: &str, selection: Range<usize>) -> (String, Range<usize>) {
+    let line_start = text[..selection.start].rfind('\n').map_or(0, |idx| idx + 1);
+    let line = &text[line_start..];
+    let level = line.bytes().take_while(|byte| *byte == b'#').count();
+    let is_heading = (1..=6).contains(&level) && line[level..].starts_with(' ');
+    let (removed, inserted) = match level {
+        6 if is_heading => ("###### ".len(), ""),
+        _ if is_heading => (0, "#"),
+        _ => (0, "# "),
+    };
+    let formatted = format!(
+        "{}{}{}",
+        &text[..line_start],
+        inserted,
+        &text[line_start + removed..]
+    );
+    let shift = |offset: usize| {
+        (offset + inserted.len())
+            .saturating_sub(removed)
+            .max(line_start)
+    };
+    (formatted, shift(selection.start)..shift(selection.end))
+}
+
+/// Applies the formatting to the selection of the editor, giving the formatted markdown
+pub fn format(editor: &HtmlTextAreaElement, format: Format) -> String {
+    let text = editor.value();
+    let start = editor.selection_start().ok().flatten().unwrap_or(0);
+    let end = editor.selection_end().ok().flatten().unwrap_or(start);
+    let selection = byte_offset(&text, start)..byte_offset(&text, end);
+    let (formatted, selection) = format.apply(&text, selection);
+    editor.set_value(&formatted);
+    set_selection(editor, &formatted, selection);
+    formatted
+}
+
+/// Selects the byte range of the markdown in the editor, scrolling it into view
+pub fn select(editor: &HtmlTextAreaElement, markdown: &str, bytes: Range<usize>) {
+    let Some(before) = markdown.get(..bytes.start) else {
+        return;
+    };
+    let line = before.matches('\n').count() as f64;
+    set_selection(editor, markdown, bytes);
+
+    // Leaves a few lines above the selection
+    let top = line * scroll_sync::line_height(editor) - editor.client_height() as f64 / 3.0;
+    editor.set_scroll_top(top.max(0.0) as i32);
+}
+
+/// Selects the byte range of the markdown in the editor,
+/// which counts UTF-16 code units rather than bytes
+fn set_selection(editor: &HtmlTextAreaElement, markdown: &str, bytes: Range<usize>) {
+    let (Some(before), Some(selected)) = (markdown.get(..bytes.start), markdown.get(bytes)) else {
+        return;
+    };
+    let start = before.encode_utf16().count() as u32;
+    let end = start + selected.encode_utf16().count() as u32;
+    let _ = editor.focus();
+    let _ = editor.set_selection_range(start, end);
+}
+
+/// Byte offset of a position of the editor, counted in UTF-16 code units
+fn byte_offset(text: &str, position: u32) -> usize {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units >= position as usize {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_the_selection() {
+        let text = "Say hi to Gohan";
+        let apply = |format: Format, selection: Range<usize>| {
+            let (formatted, selection) = format.apply(text, selection);
+            let selected = formatted[selection].to_string();
+            (formatted, selected)
+        };
+        assert_eq!(
+            apply(Format::Bold, 4..6),
+            ("Say **hi** to Gohan".to_string(), "hi".to_string())
+        );
+        assert_eq!(
+            apply(Format::Italic, 15..15),
+            (
+                "Say hi to Gohan*italic text*".to_string(),
+                "italic text".to_string()
+            )
+        );
+        assert_eq!(
+            apply(Format::Link, 10..15),
+            ("Say hi to [Gohan](url)".to_string(), "url".to_string())
+        );
+    }
+
+    #[test]
+    fn cycle_heading_levels() {
+        let (text, selection) = Format::Heading.apply("Intro\nUsage", 8..8);
+        assert_eq!(
+            (text.as_str(), selection.clone()),
+            ("Intro\n# Usage", 10..10)
+        );
+        let (text, selection) = Format::Heading.apply(&text, selection);
+        assert_eq!(
+            (text.as_str(), selection.clone()),
+            ("Intro\n## Usage", 11..11)
+        );
+        let (text, selection) = Format::Heading.apply("###### Usage", 9..12);
+        assert_eq!((text.as_str(), selection), ("Usage", 2..5));
+    }
+
+    #[test]
+    fn convert_utf16_positions() {
+        let text = "a🦀é b";
+        assert_eq!(byte_offset(text, 0), 0);
+        assert_eq!(byte_offset(text, 1), 1);
+        assert_eq!(byte_offset(text, 3), 5);
+        assert_eq!(byte_offset(text, 4), 7);
+        assert_eq!(byte_offset(text, 99), text.len());
+    }
+}
@@ -0,0 +1,30 @@
+//! Documents of the snapshot tests of the parser, showing what it supports
+
+/// Example document to load into the editor
+pub struct Example {
+    pub name: &'static str,
+    pub markdown: &'static str,
+}
+
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "Headings and paragraphs",
+        markdown: include_str!("../../md_parser/src/snapshot_inputs/input2.md"),
+    },
+    Example {
+        name: "Links and emphasis",
+        markdown: include_str!("../../md_parser/src/snapshot_inputs/lexer_input.md"),
+    },
+    Example {
+        name: "Images within links",
+        markdown: include_str!("../../md_parser/src/snapshot_inputs/badges.md"),
+    },
+    Example {
+        name: "Quotes and lists",
+        markdown: include_str!("../../md_parser/src/snapshot_inputs/quotes_and_lists.md"),
+    },
+    Example {
+        name: "Code blocks",
+        markdown: include_str!("../../md_parser/src/snapshot_inputs/code_blocks.md"),
+    },
+];
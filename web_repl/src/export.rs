@@ -0,0 +1,97 @@
+use gloo_timers::callback::Timeout;
+use js_sys::Array;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+use md_parser::{
+    ast,
+    parser::{self, ParseError},
+    renderer::{self, HtmlOptions, RenderError},
+};
+
+/// Styles of the standalone pages, close to the ones of the preview
+const PROSE_CSS: &str = include_str!("../assets/export.css");
+
+/// How long a page opened in a new tab stays reachable through its blob URL
+const REVOKE_DELAY_MS: u32 = 60_000;
+
+pub const HTML: &str = "text/html;charset=utf-8";
+pub const MARKDOWN: &str = "text/markdown;charset=utf-8";
+
+/// HTML source the markdown renders into
+pub fn html_source(markdown: &str) -> Result<String, RenderError> {
+    let ast = parser::parse(markdown)?;
+    Ok(renderer::render_ast(&ast, &HtmlOptions::default()))
+}
+
+/// AST of the markdown, following the stable JSON schema
+pub fn ast_json(markdown: &str) -> Result<String, ParseError> {
+    let ast = parser::parse(markdown)?;
+    Ok(ast::to_json(&ast))
+}
+
+/// Complete page showing the rendered markdown on its own, titled after its first heading
+pub fn standalone(markdown: &str, html: &str) -> String {
+    let title = md_parser::title(markdown).unwrap_or_else(|| "Gohan".to_string());
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{}</title>
+<style>
+{}</style>
+</head>
+<body>
+<article class="prose">
+{}</article>
+</body>
+</html>
+"#,
+        escape(&title),
+        PROSE_CSS,
+        html
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// URL of a blob holding the content, to revoke once it's no longer needed
+fn object_url(content: &str, mime_type: &str) -> Result<String, JsValue> {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let parts = Array::of1(&JsValue::from_str(content));
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    Url::create_object_url_with_blob(&blob)
+}
+
+/// Makes the browser download the content as a file of the given name
+pub fn download(file_name: &str, content: &str, mime_type: &str) -> Result<(), JsValue> {
+    let url = object_url(content, mime_type)?;
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document to download from")?;
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    Url::revoke_object_url(&url)
+}
+
+/// Opens the page in a new tab, ready to be printed
+pub fn open_in_new_tab(page: &str) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("no window to open the page from")?;
+    let url = object_url(page, HTML)?;
+    window.open_with_url_and_target(&url, "_blank")?;
+    // The new tab loads the page on its own time, so the URL can't be revoked right away
+    Timeout::new(REVOKE_DELAY_MS, move || {
+        let _ = Url::revoke_object_url(&url);
+    })
+    .forget();
+    Ok(())
+}
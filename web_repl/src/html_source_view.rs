@@ -0,0 +1,149 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct HtmlSourceViewProps {
+    /// HTML to show the source of
+    pub html: AttrValue,
+}
+
+/// HTML source, exactly as rendered, with its markup highlighted
+#[function_component(HtmlSourceView)]
+pub fn html_source_view(props: &HtmlSourceViewProps) -> Html {
+    html! {
+        <pre class="p-2 h-[70vh] overflow-y-auto whitespace-pre-wrap break-all font-mono text-xs rounded-lg border border-gray-300 text-gray-900 dark:border-gray-600 dark:text-gray-100">
+            <code>
+                { for highlight(&props.html).into_iter().map(|(kind, text)| match kind.class() {
+                    Some(class) => html! { <span {class}>{ text }</span> },
+                    None => html! { { text } },
+                }) }
+            </code>
+        </pre>
+    }
+}
+
+/// What a piece of HTML source is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Punctuation,
+    Tag,
+    Attribute,
+    Value,
+    Comment,
+}
+
+impl Kind {
+    fn class(self) -> Option<&'static str> {
+        match self {
+            Kind::Text => None,
+            Kind::Punctuation => Some("text-gray-500 dark:text-gray-400"),
+            Kind::Tag => Some("text-sky-700 dark:text-sky-400"),
+            Kind::Attribute => Some("text-emerald-700 dark:text-emerald-400"),
+            Kind::Value => Some("text-amber-700 dark:text-amber-400"),
+            Kind::Comment => Some("italic text-gray-500 dark:text-gray-400"),
+        }
+    }
+}
+
+/// Splits the HTML into the pieces to highlight, which put back together make the HTML.
+/// Only meant for the well-formed HTML of the renderer, with quoted attribute values.
+fn highlight(html: &str) -> Vec<(Kind, &str)> {
+    let mut pieces = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        let end = if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |idx| idx + "-->".len());
+            pieces.push((Kind::Comment, &rest[..end]));
+            end
+        } else if rest.starts_with('<') {
+            let end = rest.find('>').map_or(rest.len(), |idx| idx + 1);
+            tag(&mut pieces, &rest[..end]);
+            end
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            pieces.push((Kind::Text, &rest[..end]));
+            end
+        };
+        rest = &rest[end..];
+    }
+    pieces
+}
+
+fn tag<'a>(pieces: &mut Vec<(Kind, &'a str)>, tag: &'a str) {
+    let open = if tag.starts_with("</") { 2 } else { 1 };
+    pieces.push((Kind::Punctuation, &tag[..open]));
+    let mut rest = &tag[open..];
+    let is_name_end = |c: char| c.is_whitespace() || matches!(c, '=' | '/' | '>');
+    let name_end = rest.find(is_name_end).unwrap_or(rest.len());
+    if name_end > 0 {
+        pieces.push((Kind::Tag, &rest[..name_end]));
+    }
+    rest = &rest[name_end..];
+
+    while let Some(c) = rest.chars().next() {
+        let (kind, end) = match c {
+            '"' => (
+                Kind::Value,
+                rest[1..].find('"').map_or(rest.len(), |idx| idx + 2),
+            ),
+            '=' | '/' | '>' => (Kind::Punctuation, 1),
+            c if c.is_whitespace() => (
+                Kind::Text,
+                rest.find(|c: char| !c.is_whitespace())
+                    .unwrap_or(rest.len()),
+            ),
+            _ => (
+                Kind::Attribute,
+                rest.find(is_name_end).unwrap_or(rest.len()),
+            ),
+        };
+        pieces.push((kind, &rest[..end]));
+        rest = &rest[end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_markup() {
+        let html = r#"<p>Hi <a href="/a?b=c" title="A">link</a><br /><!-- note --></p>"#;
+        let pieces = highlight(html);
+        assert_eq!(
+            pieces,
+            [
+                (Kind::Punctuation, "<"),
+                (Kind::Tag, "p"),
+                (Kind::Punctuation, ">"),
+                (Kind::Text, "Hi "),
+                (Kind::Punctuation, "<"),
+                (Kind::Tag, "a"),
+                (Kind::Text, " "),
+                (Kind::Attribute, "href"),
+                (Kind::Punctuation, "="),
+                (Kind::Value, "\"/a?b=c\""),
+                (Kind::Text, " "),
+                (Kind::Attribute, "title"),
+                (Kind::Punctuation, "="),
+                (Kind::Value, "\"A\""),
+                (Kind::Punctuation, ">"),
+                (Kind::Text, "link"),
+                (Kind::Punctuation, "</"),
+                (Kind::Tag, "a"),
+                (Kind::Punctuation, ">"),
+                (Kind::Punctuation, "<"),
+                (Kind::Tag, "br"),
+                (Kind::Text, " "),
+                (Kind::Punctuation, "/"),
+                (Kind::Punctuation, ">"),
+                (Kind::Comment, "<!-- note -->"),
+                (Kind::Punctuation, "</"),
+                (Kind::Tag, "p"),
+                (Kind::Punctuation, ">"),
+            ]
+        );
+        let source: String = pieces.iter().map(|(_, text)| *text).collect();
+        assert_eq!(source, html);
+    }
+}
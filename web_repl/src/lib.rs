@@ -0,0 +1,4 @@
+//! Parts of the REPL shared with its web worker, which parses the markdown
+//! off the main thread so that typing stays responsive on large documents.
+
+pub mod worker;
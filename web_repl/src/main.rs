@@ -1,8 +1,41 @@
-use wasm_bindgen::JsCast;
-use web_sys::{EventTarget, HtmlTextAreaElement};
+use std::{cell::Cell, ops::Range, rc::Rc};
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Element, EventTarget, HtmlElement, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement,
+};
 use yew::prelude::*;
 
-use md_parser::renderer::render_html;
+use gloo_timers::callback::Timeout;
+use gloo_worker::Spawnable;
+use md_parser::{
+    ast::OwnedAst,
+    diagnostic::Diagnostic,
+    renderer::{self, HtmlOptions},
+    DocStats,
+};
+use web_repl::worker::{self, ParseRequest, Parsed, ParserWorker};
+
+use crate::{
+    ast_view::AstView, diagnostics_view::DiagnosticsView, editor::Format,
+    html_source_view::HtmlSourceView, sourcepos::SourcePos, theme::ThemeSwitcher,
+    tokens_view::TokensView,
+};
+
+mod ast_view;
+mod clipboard;
+mod diagnostics_view;
+mod editor;
+mod examples;
+mod export;
+mod html_source_view;
+mod open_file;
+mod scroll_sync;
+mod share;
+mod sourcepos;
+mod storage;
+mod theme;
+mod tokens_view;
 
 const INITIAL_MD: &str = r"## Hello from Gohan!
 
@@ -10,18 +43,253 @@ Gohan is a [Rust-based 🦀](https://www.rust-lang.org/) markdown parser and HTM
 Give it a **try!**.
 ";
 
+/// How long typing has to pause before the markdown is parsed again
+const PARSE_DELAY_MS: u32 = 150;
+
+const BUTTON: &str = "px-3 py-1 text-sm rounded-lg border border-gray-300 text-gray-900 hover:bg-gray-100 dark:border-gray-600 dark:text-white dark:hover:bg-gray-700";
+
+/// How the output panel shows the rendered markdown
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Preview,
+    /// HTML source, as the renderer writes it
+    Source,
+}
+
+impl Output {
+    /// Identifier the tab is saved as
+    fn id(self) -> &'static str {
+        match self {
+            Output::Preview => "preview",
+            Output::Source => "source",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "preview" => Some(Output::Preview),
+            "source" => Some(Output::Source),
+            _ => None,
+        }
+    }
+}
+
+/// What the inspector panel shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Inspector {
+    Ast,
+    Tokens,
+}
+
+impl Inspector {
+    /// Identifier the tab is saved as
+    fn id(self) -> &'static str {
+        match self {
+            Inspector::Ast => "ast",
+            Inspector::Tokens => "tokens",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "ast" => Some(Inspector::Ast),
+            "tokens" => Some(Inspector::Tokens),
+            _ => None,
+        }
+    }
+}
+
+/// Size of the document, as counted from its AST
+fn stats_summary(stats: &DocStats) -> String {
+    let plural = |count: usize, noun: &str| {
+        format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+    };
+    format!(
+        "{} ⋅ {} ⋅ {} ⋅ {} min read",
+        plural(stats.words, "word"),
+        plural(stats.chars, "character"),
+        plural(stats.headings, "heading"),
+        stats.reading_minutes
+    )
+}
+
+/// Classes of a tab, standing out when selected
+fn tab_class(selected: bool) -> &'static str {
+    if selected {
+        "px-2 py-1 font-bold border-b-2 border-emerald-600"
+    } else {
+        "px-2 py-1 text-gray-500 dark:text-gray-400"
+    }
+}
+
+/// AST the worker parsed the markdown into, or the reason it couldn't be parsed
+type ParseResult = Result<Rc<OwnedAst>, String>;
+
+/// Renders the parsed markdown, or the reason it couldn't be parsed
+fn render_or_report(parsed: &ParseResult) -> Html {
+    match parsed {
+        Ok(ast) => {
+            // Source positions map the preview back to the editor
+            let options = HtmlOptions {
+                source_positions: true,
+                ..Default::default()
+            };
+            gohan_yew::render_yew_with(&ast.0, &options)
+        }
+        Err(error) => html! { <p>{ error }</p> },
+    }
+}
+
+/// Output copied to the clipboard
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Copied {
+    Html,
+    AstJson,
+}
+
+/// Pane of the page that scrolls along with the other one
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Editor,
+    Preview,
+}
+
+/// Logs the error of an action to the console, as there's nowhere else to report it
+fn report(result: Result<(), JsValue>) {
+    if let Err(error) = result {
+        web_sys::console::error_1(&error);
+    }
+}
+
 #[function_component(App)]
 fn app() -> Html {
-    let rendered_html_handle = use_state(|| render_html(INITIAL_MD));
-    let html_value: String = (*rendered_html_handle).clone();
-
-    let input_value_handle = use_state(|| INITIAL_MD.to_string());
+    // Documents shared through the URL win over the one saved in the browser.
+    // Keys that don't edit anything, like arrows, leave the input as it was.
+    let input_value_handle = use_state_eq(|| {
+        share::load()
+            .or_else(|| storage::load(storage::MARKDOWN))
+            .filter(|markdown| !markdown.is_empty())
+            .unwrap_or_else(|| INITIAL_MD.to_string())
+    });
     let input_value: String = (*input_value_handle).clone();
+    let show_inspector_handle =
+        use_state(|| storage::load(storage::SHOW_INSPECTOR).as_deref() == Some("true"));
+    let show_inspector = *show_inspector_handle;
+    let inspector_handle = use_state(|| {
+        storage::load(storage::INSPECTOR)
+            .and_then(|id| Inspector::from_id(&id))
+            .unwrap_or(Inspector::Ast)
+    });
+    let inspector = *inspector_handle;
+    let output_handle = use_state(|| {
+        storage::load(storage::OUTPUT)
+            .and_then(|id| Output::from_id(&id))
+            .unwrap_or(Output::Preview)
+    });
+    let output = *output_handle;
+    let standalone_handle =
+        use_state(|| storage::load(storage::STANDALONE).as_deref() != Some("false"));
+    let standalone = *standalone_handle;
+    let textarea_ref = use_node_ref();
+    let preview_ref = use_node_ref();
+    // Pane scrolled to follow the other one, whose next scroll event isn't from the user
+    let following = use_memo((), |_| Cell::new(None::<Pane>));
+
+    use_effect_with(
+        (
+            input_value.clone(),
+            show_inspector,
+            inspector,
+            output,
+            standalone,
+        ),
+        |(markdown, show_inspector, inspector, output, standalone)| {
+            storage::save(storage::MARKDOWN, markdown);
+            if share::is_shared() {
+                share::save(markdown);
+            }
+            storage::save(storage::SHOW_INSPECTOR, &show_inspector.to_string());
+            storage::save(storage::INSPECTOR, inspector.id());
+            storage::save(storage::OUTPUT, output.id());
+            storage::save(storage::STANDALONE, &standalone.to_string());
+            || ()
+        },
+    );
 
-    let rendered_html = Html::from_html_unchecked(AttrValue::from(html_value));
+    // Parsing happens in a worker, answering with the AST of the latest input.
+    // Answers equal to the current AST don't render anything again.
+    let parsed_handle = use_state_eq(|| None::<ParseResult>);
+    let diagnostics_handle = use_state_eq(|| Rc::new(Vec::<Diagnostic>::new()));
+    let stats_handle = use_state_eq(DocStats::default);
+    let revision = use_memo((), |_| Cell::new(0_u64));
+    let worker = {
+        let parsed = parsed_handle.setter();
+        let diagnostics = diagnostics_handle.setter();
+        let stats = stats_handle.setter();
+        let revision = Rc::clone(&revision);
+        use_memo((), move |_| {
+            ParserWorker::spawner()
+                .callback(move |response: Parsed| {
+                    // Answers to requests sent before the last one are already outdated
+                    if response.revision == revision.get() {
+                        parsed.set(Some(response.ast.map(Rc::new)));
+                        diagnostics.set(Rc::new(response.diagnostics));
+                        stats.set(response.stats);
+                    }
+                })
+                .spawn(worker::SCRIPT)
+        })
+    };
+    let pending_request = use_mut_ref(|| None::<Timeout>);
+    {
+        let revision = Rc::clone(&revision);
+        use_effect_with(input_value.clone(), move |markdown| {
+            // Bumping the revision right away drops the answers still on their way
+            revision.set(revision.get() + 1);
+            let request = ParseRequest {
+                revision: revision.get(),
+                markdown: markdown.clone(),
+            };
+            // Replacing the pending request drops its timeout, cancelling it
+            *pending_request.borrow_mut() =
+                Some(Timeout::new(PARSE_DELAY_MS, move || worker.send(request)));
+            || ()
+        });
+    }
+    let rendered_html = (*parsed_handle)
+        .as_ref()
+        .map(render_or_report)
+        .unwrap_or_default();
+
+    let on_scroll = |pane: Pane| {
+        let textarea_ref = textarea_ref.clone();
+        let preview_ref = preview_ref.clone();
+        let following = Rc::clone(&following);
+        Callback::from(move |_: Event| {
+            if following.get() == Some(pane) {
+                following.set(None);
+                return;
+            }
+            let (Some(editor), Some(preview)) = (
+                textarea_ref.cast::<HtmlTextAreaElement>(),
+                preview_ref.cast::<HtmlElement>(),
+            ) else {
+                return;
+            };
+            match pane {
+                Pane::Editor => {
+                    following.set(Some(Pane::Preview));
+                    scroll_sync::sync_preview(&editor, &preview);
+                }
+                Pane::Preview => {
+                    following.set(Some(Pane::Editor));
+                    scroll_sync::sync_editor(&preview, &editor);
+                }
+            }
+        })
+    };
 
     let on_change = {
-        let html_value = rendered_html_handle.clone();
         let input_value = input_value_handle.clone();
 
         Callback::from(move |e: KeyboardEvent| {
@@ -33,33 +301,308 @@ fn app() -> Html {
             let input = target.and_then(|t| t.dyn_into::<HtmlTextAreaElement>().ok());
 
             if let Some(input) = input {
-                let h = render_html(&input.value());
-                html_value.set(h);
                 input_value.set(input.value());
             }
         })
     };
 
+    let on_toggle_inspector = {
+        let show_inspector = show_inspector_handle.clone();
+        Callback::from(move |_: MouseEvent| show_inspector.set(!*show_inspector))
+    };
+
+    let on_share = {
+        let markdown = input_value.clone();
+        Callback::from(move |_: MouseEvent| share::save(&markdown))
+    };
+
+    let on_load = {
+        let input_value = input_value_handle.clone();
+        Callback::from(move |markdown: String| input_value.set(markdown))
+    };
+
+    let on_pick_example = {
+        let input_value = input_value_handle.clone();
+        Callback::from(move |e: Event| {
+            let Some(select) = e.target_dyn_into::<HtmlSelectElement>() else {
+                return;
+            };
+            let example = select
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| examples::EXAMPLES.get(idx));
+            if let Some(example) = example {
+                input_value.set(example.markdown.to_string());
+            }
+            // Back to the placeholder, so picking the same example again loads it again
+            select.set_value("");
+        })
+    };
+
+    let on_pick_file = {
+        let on_load = on_load.clone();
+        Callback::from(move |e: Event| {
+            let Some(input) = e.target_dyn_into::<HtmlInputElement>() else {
+                return;
+            };
+            open_file::read(input.files(), on_load.clone());
+            // Picking the same file again should load it again
+            input.set_value("");
+        })
+    };
+
+    // Files can't be dropped unless the default handling of dragging is prevented
+    let on_drag_over = Callback::from(|e: DragEvent| e.prevent_default());
+    let on_drop = Callback::from(move |e: DragEvent| {
+        e.prevent_default();
+        let files = e.data_transfer().and_then(|transfer| transfer.files());
+        open_file::read(files, on_load.clone());
+    });
+
+    let on_download_markdown = {
+        let markdown = input_value.clone();
+        Callback::from(move |_: MouseEvent| {
+            report(export::download("document.md", &markdown, export::MARKDOWN));
+        })
+    };
+
+    let on_download_html = {
+        let markdown = input_value.clone();
+        Callback::from(move |_: MouseEvent| {
+            let html = match export::html_source(&markdown) {
+                Ok(html) if standalone => export::standalone(&markdown, &html),
+                Ok(html) => html,
+                Err(error) => return report(Err(error.to_string().into())),
+            };
+            report(export::download("document.html", &html, export::HTML));
+        })
+    };
+
+    let on_open_preview = {
+        let markdown = input_value.clone();
+        Callback::from(move |_: MouseEvent| {
+            let html = match export::html_source(&markdown) {
+                Ok(html) => html,
+                Err(error) => return report(Err(error.to_string().into())),
+            };
+            let page = export::standalone(&markdown, &html);
+            report(export::open_in_new_tab(&page));
+        })
+    };
+
+    // Outputs copied lately, which their button says for a little while
+    let copied_handle = use_state(|| None::<Copied>);
+    let copied = *copied_handle;
+    let on_copy = |what: Copied| {
+        let markdown = input_value.clone();
+        let copied = copied_handle.setter();
+        Callback::from(move |_: MouseEvent| {
+            let output = match what {
+                Copied::Html => export::html_source(&markdown).map_err(|error| error.to_string()),
+                Copied::AstJson => export::ast_json(&markdown).map_err(|error| error.to_string()),
+            };
+            match output {
+                Ok(output) => clipboard::copy(&output, copied.clone(), what),
+                Err(error) => report(Err(error.into())),
+            }
+        })
+    };
+    let copy_label = |what: Copied, label: &'static str| {
+        if copied == Some(what) {
+            "Copied!"
+        } else {
+            label
+        }
+    };
+
+    let on_toggle_standalone = {
+        let standalone = standalone_handle.clone();
+        Callback::from(move |_: Event| standalone.set(!*standalone))
+    };
+
+    let on_select = |selected: Inspector| {
+        let inspector = inspector_handle.clone();
+        Callback::from(move |_: MouseEvent| inspector.set(selected))
+    };
+    let tab = |selected: Inspector| tab_class(selected == inspector);
+
+    let on_select_output = |selected: Output| {
+        let output = output_handle.clone();
+        Callback::from(move |_: MouseEvent| output.set(selected))
+    };
+    let output_tab = |selected: Output| tab_class(selected == output);
+    // The source is rendered from the AST of the preview, so they always match
+    let html_source = match (*parsed_handle).as_ref() {
+        Some(Ok(ast)) if output == Output::Source => {
+            renderer::render_ast(&ast.0, &HtmlOptions::default())
+        }
+        _ => String::new(),
+    };
+
+    let on_format = {
+        let textarea_ref = textarea_ref.clone();
+        let input_value = input_value_handle.clone();
+        Callback::from(move |format: Format| {
+            if let Some(textarea) = textarea_ref.cast::<HtmlTextAreaElement>() {
+                input_value.set(editor::format(&textarea, format));
+            }
+        })
+    };
+    let on_format_click = |format: Format| on_format.reform(move |_: MouseEvent| format);
+
+    let on_shortcut = {
+        let on_format = on_format.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if !(e.ctrl_key() || e.meta_key()) {
+                return;
+            }
+            let format = match e.key().to_lowercase().as_str() {
+                "b" => Format::Bold,
+                "i" => Format::Italic,
+                "k" => Format::Link,
+                _ => return,
+            };
+            e.prevent_default();
+            on_format.emit(format);
+        })
+    };
+
+    // Selects the hovered token or diagnostic in the textarea
+    let on_select_source = {
+        let textarea_ref = textarea_ref.clone();
+        let markdown = input_value.clone();
+        Callback::from(move |bytes: Range<usize>| {
+            if let Some(textarea) = textarea_ref.cast::<HtmlTextAreaElement>() {
+                editor::select(&textarea, &markdown, bytes);
+            }
+        })
+    };
+
+    // Selects the source of the block clicked in the preview
+    let on_preview_click = {
+        let textarea_ref = textarea_ref.clone();
+        let markdown = input_value.clone();
+        Callback::from(move |e: MouseEvent| {
+            let bytes = e
+                .target_dyn_into::<Element>()
+                .and_then(|target| target.closest("[data-sourcepos]").ok().flatten())
+                .and_then(|block| block.get_attribute("data-sourcepos"))
+                .and_then(|position| SourcePos::parse(&position))
+                .and_then(|position| position.bytes(&markdown));
+            if let (Some(bytes), Some(textarea)) =
+                (bytes, textarea_ref.cast::<HtmlTextAreaElement>())
+            {
+                editor::select(&textarea, &markdown, bytes);
+            }
+        })
+    };
+
+    let columns = if show_inspector {
+        "grid-cols-3"
+    } else {
+        "grid-cols-2"
+    };
+    let heading =
+        "mb-4 text-xl font-extrabold leading-none tracking-tight text-gray-900 dark:text-white";
+
     html! {
         <>
-            <div>
+            <div class="flex items-center justify-between">
                 <h1 class="mb-4 text-3xl font-extrabold leading-none tracking-tight text-gray-900 dark:text-white">
                     <span class="text-transparent bg-clip-text bg-gradient-to-r to-emerald-600 from-sky-400">{"Gohan - Markdown Parser"}</span>
                 </h1>
+                <div class="flex items-center gap-2">
+                    <select onchange={on_pick_example} class={BUTTON} aria-label="Load an example">
+                        <option value="" selected=true>{"Examples…"}</option>
+                        { for examples::EXAMPLES.iter().enumerate().map(|(idx, example)| html! {
+                            <option value={idx.to_string()}>{ example.name }</option>
+                        }) }
+                    </select>
+                    <label class={classes!(BUTTON, "cursor-pointer")} title="Load a markdown file, or drop one onto the editor">
+                        {"Open file"}
+                        <input type="file" accept={open_file::ACCEPT} onchange={on_pick_file} class="hidden" />
+                    </label>
+                    <button onclick={on_download_markdown} class={BUTTON}>{"Download Markdown"}</button>
+                    <button onclick={on_download_html} class={BUTTON}>{"Download HTML"}</button>
+                    <label class="flex items-center gap-1 text-sm text-gray-900 dark:text-white" title="Wrap the HTML into a page styled like the preview">
+                        <input type="checkbox" checked={standalone} onchange={on_toggle_standalone} />
+                        {"Standalone page"}
+                    </label>
+                    <button onclick={on_open_preview} class={BUTTON} title="Open the preview on its own, ready to be printed">
+                        {"Open preview in new tab"}
+                    </button>
+                    <button onclick={on_copy(Copied::Html)} class={BUTTON} title="Copy the HTML source of the preview">
+                        { copy_label(Copied::Html, "Copy HTML") }
+                    </button>
+                    <button onclick={on_copy(Copied::AstJson)} class={BUTTON}>
+                        { copy_label(Copied::AstJson, "Copy AST JSON") }
+                    </button>
+                    <button onclick={on_share} class={BUTTON} title="Put the document into the URL of the page">
+                        {"Share"}
+                    </button>
+                    <button onclick={on_toggle_inspector} class={BUTTON}>
+                        { if show_inspector { "Hide inspector" } else { "Show inspector" } }
+                    </button>
+                    <ThemeSwitcher />
+                </div>
             </div>
-            <div class="grid grid-cols-2 gap-8 mt-4">
-                <h2 class="mb-4 text-xl font-extrabold leading-none tracking-tight text-gray-900 dark:text-white">{"Markdown input"}</h2>
-                <h2 class="mb-4 text-xl font-extrabold leading-none tracking-tight text-gray-900 dark:text-white">{"HTML output"}</h2>
+            <div class={classes!("grid", columns, "gap-8", "mt-4")}>
+                <h2 class={heading}>{"Markdown input"}</h2>
+                <div class="flex gap-2 mb-4 text-sm text-gray-900 dark:text-white">
+                    <button onclick={on_select_output(Output::Preview)} class={output_tab(Output::Preview)}>{"Preview"}</button>
+                    <button onclick={on_select_output(Output::Source)} class={output_tab(Output::Source)}>{"HTML source"}</button>
+                </div>
+                if show_inspector {
+                    <div class="flex gap-2 mb-4 text-sm text-gray-900 dark:text-white">
+                        <button onclick={on_select(Inspector::Ast)} class={tab(Inspector::Ast)}>{"AST"}</button>
+                        <button onclick={on_select(Inspector::Tokens)} class={tab(Inspector::Tokens)}>{"Tokens"}</button>
+                    </div>
+                }
             </div>
 
-            <div class="grid grid-cols-2 gap-8 mt-2">
-                <textarea
-                    onkeyup={on_change}
-                    value={input_value}
-                    class="block p-2.5 w-full text-sm text-gray-900 bg-gray-50 rounded-lg border border-gray-300 focus:ring-blue-500 focus:border-blue-500 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white dark:focus:ring-blue-500 dark:focus:border-blue-500"/>
-                <article class="p-2 prose lg:prose-xl rounded-lg border border-gray-300 dark:border-gray-600 dark:prose-invert">
-                    {rendered_html}
-                </article>
+            <div class={classes!("grid", columns, "gap-8", "mt-2")}>
+                <div>
+                    <div class="flex gap-1 mb-1" role="toolbar" aria-label="Formatting">
+                        <button onclick={on_format_click(Format::Bold)} class={classes!(BUTTON, "font-bold")} title="Bold (Ctrl+B)">{"B"}</button>
+                        <button onclick={on_format_click(Format::Italic)} class={classes!(BUTTON, "italic")} title="Italic (Ctrl+I)">{"I"}</button>
+                        <button onclick={on_format_click(Format::Link)} class={BUTTON} title="Link (Ctrl+K)">{"Link"}</button>
+                        <button onclick={on_format_click(Format::Heading)} class={BUTTON} title="Heading, one level deeper on every click">{"H"}</button>
+                    </div>
+                    <textarea
+                        ref={textarea_ref}
+                        onkeydown={on_shortcut}
+                        onkeyup={on_change}
+                        ondragover={on_drag_over}
+                        ondrop={on_drop}
+                        onscroll={on_scroll(Pane::Editor)}
+                        value={input_value.clone()}
+                        class="block p-2.5 w-full h-[70vh] text-sm text-gray-900 bg-gray-50 rounded-lg border border-gray-300 focus:ring-blue-500 focus:border-blue-500 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white dark:focus:ring-blue-500 dark:focus:border-blue-500"/>
+                    <p class="mt-1 text-xs text-gray-500 dark:text-gray-400">{ stats_summary(&stats_handle) }</p>
+                    <DiagnosticsView diagnostics={Rc::clone(&diagnostics_handle)} on_select={on_select_source.clone()} />
+                </div>
+                if output == Output::Preview {
+                    <article
+                        ref={preview_ref}
+                        onscroll={on_scroll(Pane::Preview)}
+                        onclick={on_preview_click}
+                        class="relative p-2 h-[70vh] overflow-y-auto prose lg:prose-xl rounded-lg border border-gray-300 dark:border-gray-600 dark:prose-invert">
+                        {rendered_html}
+                    </article>
+                } else {
+                    <HtmlSourceView html={html_source} />
+                }
+                if show_inspector {
+                    <div class="p-2 overflow-auto rounded-lg border border-gray-300 dark:border-gray-600">
+                        if inspector == Inspector::Ast {
+                            if let Some(Ok(ast)) = (*parsed_handle).as_ref() {
+                                <AstView ast={Rc::clone(ast)} />
+                            }
+                        } else {
+                            <TokensView markdown={input_value.clone()} on_hover={on_select_source.clone()} />
+                        }
+                    </div>
+                }
             </div>
             <p class="mt-4 text-gray-500 dark:text-gray-200 text-xs">
                 {"Built with ❤️ by "}<a href="https://x.com/bpaulino0" class="underline">{"Bruno Paulino"}</a> {" ⋅ "}
@@ -0,0 +1,23 @@
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::FileList;
+use yew::Callback;
+
+/// Files the picker accepts, though anything dropped onto the editor is read as text
+pub const ACCEPT: &str = ".md,.markdown,.txt,text/markdown,text/plain";
+
+/// Reads the first of the files, handing its content over once loaded
+pub fn read(files: Option<FileList>, on_load: Callback<String>) {
+    let Some(file) = files.and_then(|files| files.get(0)) else {
+        return;
+    };
+    spawn_local(async move {
+        match JsFuture::from(file.text()).await {
+            Ok(text) => {
+                if let Some(text) = text.as_string() {
+                    on_load.emit(text);
+                }
+            }
+            Err(error) => web_sys::console::error_1(&error),
+        }
+    });
+}
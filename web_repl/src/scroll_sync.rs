@@ -0,0 +1,83 @@
+//! Keeps the editor and the preview scrolled to the same block, mapping
+//! source lines to the preview elements through their `data-sourcepos`.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, HtmlTextAreaElement};
+
+use crate::sourcepos::SourcePos;
+
+/// Line height assumed when the one of the editor can't be read
+const DEFAULT_LINE_HEIGHT: f64 = 20.0;
+
+/// Element of the preview rendered from a range of source lines
+struct Block {
+    first_line: usize,
+    last_line: usize,
+    element: HtmlElement,
+}
+
+/// Blocks of the preview in document order, so parents come before their children
+fn blocks(preview: &Element) -> Vec<Block> {
+    let Ok(nodes) = preview.query_selector_all("[data-sourcepos]") else {
+        return Vec::new();
+    };
+    (0..nodes.length())
+        .filter_map(|idx| {
+            let element: HtmlElement = nodes.item(idx)?.dyn_into().ok()?;
+            let position = SourcePos::parse(&element.get_attribute("data-sourcepos")?)?;
+            Some(Block {
+                first_line: position.start.0,
+                last_line: position.end.0,
+                element,
+            })
+        })
+        .collect()
+}
+
+pub fn line_height(editor: &HtmlTextAreaElement) -> f64 {
+    web_sys::window()
+        .and_then(|window| window.get_computed_style(editor).ok().flatten())
+        .and_then(|style| style.get_property_value("line-height").ok())
+        .and_then(|height| height.trim_end_matches("px").parse().ok())
+        .unwrap_or(DEFAULT_LINE_HEIGHT)
+}
+
+/// Scrolls the preview to the block rendered from the line at the top of the editor.
+/// The preview has to be the offset parent of its blocks, e.g. positioned relatively.
+pub fn sync_preview(editor: &HtmlTextAreaElement, preview: &HtmlElement) {
+    let line_height = line_height(editor);
+    let line = editor.scroll_top() as f64 / line_height + 1.0;
+    let blocks = blocks(preview);
+    // The innermost block holding the line, or else the last one before it
+    let Some(block) = blocks
+        .iter()
+        .rev()
+        .find(|block| block.first_line as f64 <= line)
+    else {
+        preview.set_scroll_top(0);
+        return;
+    };
+    let lines = (block.last_line - block.first_line + 1) as f64;
+    let progress = ((line - block.first_line as f64) / lines).clamp(0.0, 1.0);
+    let top = block.element.offset_top() as f64 + progress * block.element.offset_height() as f64;
+    preview.set_scroll_top(top as i32);
+}
+
+/// Scrolls the editor to the line the block at the top of the preview was rendered from
+pub fn sync_editor(preview: &HtmlElement, editor: &HtmlTextAreaElement) {
+    let top = preview.scroll_top();
+    let blocks = blocks(preview);
+    let Some(block) = blocks
+        .iter()
+        .filter(|block| block.element.offset_top() <= top)
+        .max_by_key(|block| block.element.offset_top())
+    else {
+        editor.set_scroll_top(0);
+        return;
+    };
+    let height = block.element.offset_height().max(1) as f64;
+    let progress = ((top - block.element.offset_top()) as f64 / height).clamp(0.0, 1.0);
+    let lines = (block.last_line - block.first_line + 1) as f64;
+    let line = block.first_line as f64 + progress * lines;
+    editor.set_scroll_top(((line - 1.0) * line_height(editor)) as i32);
+}
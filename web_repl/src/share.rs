@@ -0,0 +1,67 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+use wasm_bindgen::JsValue;
+
+/// Start of the URL fragments holding a shared document
+const PREFIX: &str = "md=";
+
+/// Shared documents inflating past this size are rejected,
+/// so that a crafted link can't exhaust the memory of the page
+const MAX_SIZE: usize = 8 * 1024 * 1024;
+
+/// URL fragment holding the markdown, compressed and encoded as URL-safe base64
+pub fn encode(markdown: &str) -> String {
+    let compressed = compress_to_vec(markdown.as_bytes(), 9);
+    format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// Markdown held by a URL fragment, with or without its leading `#`
+pub fn decode(fragment: &str) -> Option<String> {
+    let encoded = fragment.strip_prefix('#').unwrap_or(fragment);
+    let compressed = URL_SAFE_NO_PAD.decode(encoded.strip_prefix(PREFIX)?).ok()?;
+    let markdown = decompress_to_vec_with_limit(&compressed, MAX_SIZE).ok()?;
+    String::from_utf8(markdown).ok()
+}
+
+fn fragment() -> Option<String> {
+    web_sys::window()?.location().hash().ok()
+}
+
+/// Markdown shared through the URL of the page, if any
+pub fn load() -> Option<String> {
+    decode(&fragment()?)
+}
+
+/// Whether the URL of the page holds a document, which then has to be kept up to date
+pub fn is_shared() -> bool {
+    fragment().is_some_and(|fragment| fragment.starts_with(&format!("#{}", PREFIX)))
+}
+
+/// Puts the markdown into the URL of the page, ready to be copied from the address bar.
+/// The URL is replaced rather than pushed, so edits don't pile up in the history.
+pub fn save(markdown: &str) {
+    let Some(history) = web_sys::window().and_then(|window| window.history().ok()) else {
+        return;
+    };
+    let url = format!("#{}", encode(markdown));
+    let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let markdown = "## Hello from Gohan!\n\nA [link](https://bpaulino.com) 🦀\n";
+        let fragment = encode(markdown);
+        assert!(fragment.starts_with("md="));
+        assert!(fragment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '=' | '-' | '_')));
+        assert_eq!(decode(&fragment).as_deref(), Some(markdown));
+        assert_eq!(decode(&format!("#{}", fragment)).as_deref(), Some(markdown));
+        assert_eq!(decode("#usage"), None);
+        assert_eq!(decode("#md=not base64!"), None);
+    }
+}
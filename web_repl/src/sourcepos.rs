@@ -0,0 +1,72 @@
+use std::ops::Range;
+
+/// Position of a preview element in the source, as given by its `data-sourcepos`.
+/// Lines and byte columns start at 1, and both ends are included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePos {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl SourcePos {
+    /// Parses a `data-sourcepos` value, like `3:1-5:12`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.split_once('-')?;
+        Some(Self {
+            start: point(start)?,
+            end: point(end)?,
+        })
+    }
+
+    /// Byte range of the element in the markdown it was rendered from
+    pub fn bytes(&self, markdown: &str) -> Option<Range<usize>> {
+        let start = line_start(markdown, self.start.0)? + self.start.1.saturating_sub(1);
+        let end = line_start(markdown, self.end.0)? + self.end.1;
+        (start <= end && end <= markdown.len()).then_some(start..end)
+    }
+}
+
+fn point(value: &str) -> Option<(usize, usize)> {
+    let (line, col) = value.split_once(':')?;
+    Some((line.parse().ok()?, col.parse().ok()?))
+}
+
+/// Byte offset the line, starting at 1, starts at
+fn line_start(markdown: &str, line: usize) -> Option<usize> {
+    match line {
+        0 => None,
+        1 => Some(0),
+        line => markdown
+            .match_indices('\n')
+            .nth(line - 2)
+            .map(|(idx, _)| idx + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_positions_to_bytes() {
+        let markdown = "# Title\n\nSome *text* héré\nand more\n";
+        let paragraph = SourcePos::parse("3:1-4:8").unwrap();
+        assert_eq!(
+            paragraph,
+            SourcePos {
+                start: (3, 1),
+                end: (4, 8)
+            }
+        );
+        assert_eq!(
+            &markdown[paragraph.bytes(markdown).unwrap()],
+            "Some *text* héré\nand more"
+        );
+        let heading = SourcePos::parse("1:1-1:7").unwrap();
+        assert_eq!(&markdown[heading.bytes(markdown).unwrap()], "# Title");
+
+        assert_eq!(SourcePos::parse("3:1"), None);
+        assert_eq!(SourcePos::parse("a:1-b:2"), None);
+        assert_eq!(SourcePos::parse("9:1-9:2").unwrap().bytes(markdown), None);
+    }
+}
@@ -0,0 +1,31 @@
+use web_sys::Storage;
+
+/// Key of the markdown being edited
+pub const MARKDOWN: &str = "gohan.markdown";
+/// Key of whether the inspector panel is shown
+pub const SHOW_INSPECTOR: &str = "gohan.show-inspector";
+/// Key of the tab selected in the inspector panel
+pub const INSPECTOR: &str = "gohan.inspector";
+/// Key of the tab selected in the output panel
+pub const OUTPUT: &str = "gohan.output";
+/// Key of whether HTML downloads are standalone pages
+pub const STANDALONE: &str = "gohan.standalone";
+/// Key of the theme picked, also read by `index.html` before the app loads
+pub const THEME: &str = "gohan.theme";
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Value saved under the key, if any. Nothing is ever saved when the
+/// browser doesn't allow it, e.g. in some private windows.
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Saves the value under the key, so that it survives reloading the page
+pub fn save(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(key, value);
+    }
+}
@@ -0,0 +1,91 @@
+use yew::prelude::*;
+
+use crate::{storage, BUTTON};
+
+/// Color scheme of the page, applied through the `dark` class Tailwind looks for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// Identifier the theme is saved as
+    fn id(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+
+    /// Theme picked the last time, or else the one the system prefers
+    fn load() -> Self {
+        storage::load(storage::THEME)
+            .and_then(|id| Theme::from_id(&id))
+            .unwrap_or_else(Theme::preferred)
+    }
+
+    fn preferred() -> Self {
+        let dark = web_sys::window()
+            .and_then(|window| window.match_media("(prefers-color-scheme: dark)").ok())
+            .flatten()
+            .is_some_and(|query| query.matches());
+        if dark {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn apply(self) {
+        let root = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element());
+        if let Some(root) = root {
+            let _ = root
+                .class_list()
+                .toggle_with_force("dark", self == Theme::Dark);
+        }
+    }
+
+    fn toggled(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+}
+
+/// Button switching between the light and dark themes. The theme is only
+/// saved once picked, following the system preference until then.
+#[function_component(ThemeSwitcher)]
+pub fn theme_switcher() -> Html {
+    let theme_handle = use_state(Theme::load);
+    let theme = *theme_handle;
+    use_effect_with(theme, |theme| {
+        theme.apply();
+        || ()
+    });
+
+    let on_toggle = Callback::from(move |_: MouseEvent| {
+        let theme = theme_handle.toggled();
+        storage::save(storage::THEME, theme.id());
+        theme_handle.set(theme);
+    });
+    let (icon, title) = match theme {
+        Theme::Light => ("🌙", "Switch to the dark theme"),
+        Theme::Dark => ("☀️", "Switch to the light theme"),
+    };
+
+    html! {
+        <button onclick={on_toggle} class={BUTTON} {title} aria-label={title}>{ icon }</button>
+    }
+}
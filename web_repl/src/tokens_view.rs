@@ -0,0 +1,38 @@
+use std::ops::Range;
+
+use yew::prelude::*;
+
+use md_parser::lexer::Lexer;
+
+#[derive(Properties, PartialEq)]
+pub struct TokensViewProps {
+    /// Markdown whose tokens to list
+    pub markdown: AttrValue,
+    /// Called with the byte range of the token under the pointer
+    pub on_hover: Callback<Range<usize>>,
+}
+
+/// Tokens scanned from the markdown, with their position in the source
+#[function_component(TokensView)]
+pub fn tokens_view(props: &TokensViewProps) -> Html {
+    let tokens = Lexer::new(&props.markdown).into_tokens();
+
+    html! {
+        <table class="w-full font-mono text-xs text-left text-gray-900 dark:text-gray-100">
+            <tbody>
+                { for tokens.iter().map(|(token, span)| {
+                    let on_hover = props.on_hover.clone();
+                    let bytes = span.bytes();
+                    let onmouseenter = Callback::from(move |_: MouseEvent| on_hover.emit(bytes.clone()));
+                    html! {
+                        <tr {onmouseenter} class="hover:bg-sky-100 dark:hover:bg-sky-900">
+                            <td class="pr-4 text-emerald-700 dark:text-emerald-400">{ token.to_string() }</td>
+                            <td class="pr-4">{ format!("{}:{}", span.line, span.col) }</td>
+                            <td class="text-gray-500 dark:text-gray-400">{ format!("{}..{}", span.start, span.end) }</td>
+                        </tr>
+                    }
+                }) }
+            </tbody>
+        </table>
+    }
+}
@@ -0,0 +1,63 @@
+use gloo_worker::{HandlerId, Worker, WorkerScope};
+use serde::{Deserialize, Serialize};
+
+use md_parser::{
+    ast::OwnedAst,
+    diagnostic::Diagnostic,
+    events::{self, EventIter},
+    DocStats,
+};
+
+/// Path Trunk serves the script of the worker at
+pub const SCRIPT: &str = "/worker.js";
+
+/// Markdown to parse, numbered so that outdated answers can be told apart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParseRequest {
+    pub revision: u64,
+    pub markdown: String,
+}
+
+/// AST of the markdown of a request, or the reason it couldn't be parsed,
+/// along with the problems found while parsing it and the size of the document
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Parsed {
+    pub revision: u64,
+    pub ast: Result<OwnedAst, String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub stats: DocStats,
+}
+
+/// Parses the markdown it receives, one request after the other
+pub struct ParserWorker;
+
+impl Worker for ParserWorker {
+    type Message = ();
+    type Input = ParseRequest;
+    type Output = Parsed;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _message: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, request: Self::Input, id: HandlerId) {
+        let mut events = EventIter::new(&request.markdown);
+        let ast = events::build_ast(&mut events);
+        let stats = md_parser::ast_stats(&ast);
+        let ast = match events.error() {
+            Some(error) => Err(error.to_string()),
+            None => Ok(OwnedAst::from(ast)),
+        };
+        scope.respond(
+            id,
+            Parsed {
+                revision: request.revision,
+                ast,
+                diagnostics: events.into_diagnostics(),
+                stats,
+            },
+        );
+    }
+}